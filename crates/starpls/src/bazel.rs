@@ -1,3 +1,7 @@
+use std::env;
+use std::path::Path;
+use std::path::PathBuf;
+
 use anyhow::anyhow;
 use log::debug;
 use log::info;
@@ -72,4 +76,42 @@ impl BazelContext {
             bzlmod_enabled,
         })
     }
+
+    /// Creates a context for running without a working Bazel installation. The workspace root
+    /// is found by searching upwards from `from` for a `WORKSPACE`, `MODULE.bazel` or `REPO.bazel`
+    /// file, falling back to `from` itself. No rules are available beyond the bundled builtins.
+    pub(crate) fn standalone(
+        from: &Path,
+        output_base: Option<PathBuf>,
+    ) -> anyhow::Result<BazelContext> {
+        let from = if from.is_absolute() {
+            from.to_path_buf()
+        } else {
+            env::current_dir()?.join(from)
+        }
+        .canonicalize()?;
+        let workspace = match starpls_bazel::resolve_workspace(&from)? {
+            Some((workspace, _)) => workspace,
+            None if from.is_dir() => from,
+            None => from
+                .parent()
+                .map(|parent| parent.to_path_buf())
+                .unwrap_or(from),
+        };
+
+        info!(
+            "running in standalone mode, workspace root: {:?}",
+            workspace
+        );
+
+        Ok(BazelContext {
+            info: BazelInfo {
+                output_base: output_base.unwrap_or_default(),
+                workspace,
+                ..Default::default()
+            },
+            rules: Default::default(),
+            bzlmod_enabled: false,
+        })
+    }
 }
@@ -1,9 +1,11 @@
 use clap::Args;
+use walkdir::DirEntry;
 
 pub(crate) mod check;
+pub(crate) mod fmt;
 pub(crate) mod server;
 
-#[derive(Args, Default)]
+#[derive(Args, Clone, Default)]
 pub(crate) struct InferenceOptions {
     /// Infer attributes on a rule implementation function's context parameter.
     #[clap(long = "experimental_infer_ctx_attributes", default_value_t = false)]
@@ -12,4 +14,19 @@ pub(crate) struct InferenceOptions {
     /// Use code-flow analysis during typechecking.
     #[clap(long = "experimental_use_code_flow_analysis", default_value_t = false)]
     pub(crate) use_code_flow_analysis: bool,
+
+    /// Check assignments with stricter rules, e.g. treating `list` and `dict` as invariant.
+    #[clap(long = "experimental_strict_type_checking", default_value_t = false)]
+    pub(crate) strict_type_checking: bool,
+}
+
+pub(crate) fn is_hidden(entry: &DirEntry) -> bool {
+    entry
+        .file_name()
+        .to_str()
+        .map(|s| {
+            // Don't consider lone "." as a hidden entry.
+            s.starts_with('.') && s != "."
+        })
+        .unwrap_or(false)
 }
@@ -1,9 +1,14 @@
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fs;
+use std::io::Write;
+use std::io::{self};
+use std::ops::Range;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::thread;
 
 use annotate_snippets::Level;
 use annotate_snippets::Message;
@@ -12,6 +17,13 @@ use annotate_snippets::Snippet;
 use anyhow::anyhow;
 use anyhow::bail;
 use clap::Args;
+use line_index::LineIndex;
+use parking_lot::Mutex;
+use rayon::iter::IndexedParallelIterator;
+use rayon::iter::IntoParallelRefIterator;
+use rayon::iter::ParallelIterator;
+use rayon::ThreadPool;
+use rayon::ThreadPoolBuilder;
 use starpls_bazel::client::BazelCLI;
 use starpls_bazel::client::BazelInfo;
 use starpls_common::Diagnostic;
@@ -20,17 +32,35 @@ use starpls_common::FileId;
 use starpls_common::FileInfo;
 use starpls_common::Severity;
 use starpls_ide::Analysis;
-use starpls_ide::AnalysisSnapshot;
+use starpls_ide::Applicability;
 use starpls_ide::Change;
-use walkdir::DirEntry;
+use starpls_ide::Fix;
+use starpls_ide::TextEdit;
+use starpls_syntax::TextRange;
 use walkdir::WalkDir;
 
 use crate::bazel::BazelContext;
+use crate::commands::check::baseline::Baseline;
+use crate::commands::check::baseline::BaselineEntry;
+use crate::commands::check::output::OutputFormat;
+use crate::commands::check::output::Record;
+use crate::commands::check::output::Summary;
+use crate::commands::check::severity::SeverityOptions;
+use crate::commands::is_hidden;
 use crate::commands::InferenceOptions;
+use crate::diff::unified_diff;
 use crate::document::DefaultFileLoader;
 use crate::document::PathInterner;
 use crate::document::{self};
+use crate::ignore::IgnoreFilter;
 use crate::server::load_bazel_builtins;
+use crate::utils::write_file_atomically;
+use crate::workspace_config::WorkspaceConfig;
+
+mod baseline;
+mod output;
+mod severity;
+mod watch;
 
 #[derive(Args, Default)]
 pub(crate) struct CheckCommand {
@@ -41,51 +71,140 @@ pub(crate) struct CheckCommand {
     #[clap(long = "output_base")]
     pub(crate) output_base: Option<String>,
 
-    /// Specify patterns of files/directories to ignore.
+    /// Specify gitignore-style patterns of files/directories to ignore, e.g. "third_party/**".
+    /// Files ignored by `.gitignore` and `.bazelignore` are always skipped.
     #[clap(long = "ignore_pattern")]
     pub(crate) ignore_patterns: Vec<String>,
 
     #[clap(long = "ext")]
     pub(crate) extensions: Vec<String>,
 
+    /// Don't invoke Bazel. Labels are resolved from the filesystem and only the bundled
+    /// builtins are available. This mode is also used if Bazel fails to initialize.
+    #[clap(long = "standalone", default_value_t = false)]
+    pub(crate) standalone: bool,
+
+    /// Only report diagnostics that aren't recorded in the given baseline file.
+    #[clap(long = "baseline")]
+    pub(crate) baseline: Option<PathBuf>,
+
+    /// Record all current diagnostics to the given baseline file instead of reporting them.
+    #[clap(long = "write_baseline")]
+    pub(crate) write_baseline: Option<PathBuf>,
+
+    /// The number of files to check in parallel. Defaults to the number of available CPUs.
+    #[clap(long = "jobs", short = 'j')]
+    pub(crate) jobs: Option<usize>,
+
+    /// The format to report diagnostics in.
+    #[clap(long = "output_format", value_enum, default_value_t = OutputFormat::Human)]
+    pub(crate) output_format: OutputFormat,
+
+    /// Apply all safe fixes in place, then report the diagnostics that remain.
+    #[clap(long = "fix", default_value_t = false)]
+    pub(crate) fix: bool,
+
+    /// Print the changes that `--fix` would make as a unified diff, without writing them.
+    #[clap(long = "diff", default_value_t = false, conflicts_with = "fix")]
+    pub(crate) diff: bool,
+
+    /// Keep running and re-check whenever files change, reporting only the diagnostics that
+    /// changed.
+    #[clap(
+        long = "watch",
+        default_value_t = false,
+        conflicts_with_all = ["fix", "diff", "baseline", "write_baseline"]
+    )]
+    pub(crate) watch: bool,
+
+    #[command(flatten)]
+    pub(crate) severity_options: SeverityOptions,
+
     #[command(flatten)]
     pub(crate) inference_options: InferenceOptions,
 }
 
 impl CheckCommand {
     pub(crate) fn run(self) -> anyhow::Result<()> {
+        if self.watch && self.output_format != OutputFormat::Human {
+            bail!("--watch only supports the human-readable output format");
+        }
+
         let bazel_client = Arc::new(BazelCLI::default());
-        let bazel_cx = BazelContext::new(&*bazel_client)
-            .map_err(|err| anyhow!("failed to initialize Bazel context: {}", err))?;
+        let (bazel_cx, standalone) = if self.standalone {
+            (self.standalone_bazel_context()?, true)
+        } else {
+            match BazelContext::new(&*bazel_client) {
+                Ok(bazel_cx) => (bazel_cx, false),
+                Err(err) => {
+                    anstream::eprintln!(
+                        "{}\n",
+                        Renderer::styled().render(Level::Warning.title(&format!(
+                            "failed to initialize Bazel context, running in standalone mode: {}",
+                            err
+                        )))
+                    );
+                    (self.standalone_bazel_context()?, true)
+                }
+            }
+        };
+        let workspace_config = WorkspaceConfig::load(&bazel_cx.info.workspace)?;
         let builtins = load_bazel_builtins();
         let (fetch_repo_sender, _) = crossbeam_channel::unbounded();
         let interner = Arc::new(PathInterner::default());
-        let loader = DefaultFileLoader::new(
+        let mut loader = DefaultFileLoader::new(
             bazel_client,
             interner.clone(),
             bazel_cx.info.workspace.clone(),
             bazel_cx.info.workspace_name.clone(),
-            bazel_cx.info.output_base.join("external"),
+            if bazel_cx.info.output_base.as_os_str().is_empty() {
+                PathBuf::new()
+            } else {
+                bazel_cx.info.output_base.join("external")
+            },
             fetch_repo_sender,
             bazel_cx.bzlmod_enabled,
         );
+        if standalone {
+            loader = loader.with_standalone_mode();
+        }
 
         let mut analysis = Analysis::new(
             Arc::new(loader),
             starpls_ide::InferenceOptions {
-                infer_ctx_attributes: self.inference_options.infer_ctx_attributes,
-                use_code_flow_analysis: self.inference_options.use_code_flow_analysis,
+                infer_ctx_attributes: self.inference_options.infer_ctx_attributes
+                    || workspace_config.infer_ctx_attributes,
+                use_code_flow_analysis: self.inference_options.use_code_flow_analysis
+                    || workspace_config.use_code_flow_analysis,
+                strict_type_checking: self.inference_options.strict_type_checking
+                    || workspace_config.strict_type_checking,
                 ..Default::default()
             },
         );
 
+        let num_jobs = match self.jobs {
+            Some(jobs) => jobs.max(1),
+            None => thread::available_parallelism()
+                .map(|jobs| jobs.get())
+                .unwrap_or(1),
+        };
+        analysis.set_max_inference_threads(num_jobs);
         analysis.set_builtin_defs(builtins, bazel_cx.rules);
+        analysis.set_enabled_lints(
+            workspace_config
+                .enabled_lints
+                .iter()
+                .chain(&self.severity_options.error)
+                .chain(&self.severity_options.warn)
+                .copied(),
+        );
 
         // Strip off the leading "." from each of the specified extensions.
         // This works better when filtering against files with .extension().
         let extensions = self
             .extensions
             .iter()
+            .chain(&workspace_config.extensions)
             .map(|ext| match ext.strip_prefix('.') {
                 Some(ext) => ext,
                 None => ext,
@@ -93,29 +212,138 @@ impl CheckCommand {
             .chain(["star", "sky"])
             .collect::<Vec<_>>();
 
-        let checker = Checker::new(
+        let ignore_filter = IgnoreFilter::new(
+            &bazel_cx.info.workspace,
+            workspace_config
+                .ignore
+                .with_patterns(self.ignore_patterns.iter().map(String::as_str))?,
+        );
+        let mut checker = Checker::new(
             analysis,
             bazel_cx.info,
             interner,
             self.paths,
-            self.ignore_patterns,
+            ignore_filter,
             &extensions,
+            ReportOptions {
+                jobs: num_jobs,
+                output_format: self.output_format,
+                baseline: match self.write_baseline {
+                    Some(_) => None,
+                    None => self.baseline.as_deref().map(Baseline::load).transpose()?,
+                },
+                write_baseline: self.write_baseline,
+                workspace_config,
+                severity_options: self.severity_options,
+            },
         )?;
+
+        if self.watch {
+            return checker.watch();
+        }
+        if self.fix || self.diff {
+            let original_contents = checker.apply_fixes()?;
+            if self.diff {
+                return checker.report_fix_diffs(original_contents);
+            }
+            checker.write_fixes(original_contents)?;
+        }
         checker.report_diagnostics()
     }
+
+    fn standalone_bazel_context(&self) -> anyhow::Result<BazelContext> {
+        let from = self
+            .paths
+            .first()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."));
+        BazelContext::standalone(&from, self.output_base.as_ref().map(PathBuf::from))
+            .map_err(|err| anyhow!("failed to initialize standalone context: {}", err))
+    }
 }
 
+/// The maximum number of times to apply fixes. Applying a fix can make new fixes available, e.g.
+/// removing the last unused item from a `load` statement, so fixes are applied repeatedly until
+/// none remain.
+const MAX_FIX_ITERATIONS: usize = 10;
+
 struct FileMetadata {
     path: PathBuf,
     contents: String,
 }
 
+struct ReportOptions {
+    jobs: usize,
+    output_format: OutputFormat,
+    baseline: Option<Baseline>,
+    write_baseline: Option<PathBuf>,
+    workspace_config: WorkspaceConfig,
+    severity_options: SeverityOptions,
+}
+
+/// State accumulated while reporting diagnostics across files.
+#[derive(Default)]
+struct Report {
+    summary: Summary,
+    records: Vec<Record>,
+    baseline_entries: Vec<BaselineEntry>,
+    num_baselined: usize,
+    checked_paths: HashSet<String>,
+}
+
 struct Checker {
     analysis: Analysis,
     bazel_info: BazelInfo,
     interner: Arc<PathInterner>,
     files: HashMap<FileId, FileMetadata>,
     ignored_files: HashSet<PathBuf>,
+    walker: FileWalker,
+    extensions: Vec<String>,
+    thread_pool: Arc<ThreadPool>,
+    options: ReportOptions,
+}
+
+/// Finds the files to check under the paths passed on the command line.
+struct FileWalker {
+    paths: Vec<String>,
+    ignore_filter: IgnoreFilter,
+    /// The directories visited by the last walk, and the ones containing the files that were
+    /// passed explicitly. These are the directories to watch for changes.
+    dirs: Vec<PathBuf>,
+}
+
+impl FileWalker {
+    /// Returns the files under the checked paths, and whether each of them was passed explicitly.
+    fn walk(&mut self) -> anyhow::Result<Vec<(PathBuf, bool)>> {
+        let mut files = Vec::new();
+        let mut dirs = Vec::new();
+        for path in &self.paths {
+            // Paths that are passed explicitly are never ignored, but anything found by walking
+            // a directory is matched against the ignore filter.
+            let root = PathBuf::from(path).canonicalize()?;
+            for entry in WalkDir::new(path).into_iter().filter_entry(|e| {
+                e.depth() == 0
+                    || (!is_hidden(e)
+                        && !self.ignore_filter.is_ignored(
+                            &root.join(e.path().strip_prefix(path).unwrap_or(e.path())),
+                            e.file_type().is_dir(),
+                        ))
+            }) {
+                let entry = entry?;
+                if entry.file_type().is_dir() {
+                    dirs.push(entry.into_path());
+                } else if entry.file_type().is_file() {
+                    let is_explicit = entry.path().as_os_str().to_str() == Some(path.as_str());
+                    if is_explicit {
+                        dirs.push(root.parent().map(Path::to_path_buf).unwrap_or_default());
+                    }
+                    files.push((entry.into_path(), is_explicit));
+                }
+            }
+        }
+        self.dirs = dirs;
+        Ok(files)
+    }
 }
 
 fn diagnostic_to_message<'a>(
@@ -125,6 +353,7 @@ fn diagnostic_to_message<'a>(
     let start: usize = diagnostic.range.range.start().into();
     let end: usize = diagnostic.range.range.end().into();
     let level = match diagnostic.severity {
+        Severity::Hint => Level::Help,
         Severity::Info => Level::Info,
         Severity::Warning => Level::Warning,
         Severity::Error => Level::Error,
@@ -138,15 +367,43 @@ fn diagnostic_to_message<'a>(
     )
 }
 
-fn is_hidden(entry: &DirEntry) -> bool {
-    entry
-        .file_name()
-        .to_str()
-        .map(|s| {
-            // Don't consider lone "." as a hidden entry.
-            s.starts_with('.') && s != "."
-        })
-        .unwrap_or(false)
+/// Applies the edits for every safe fix that doesn't conflict with an earlier one. Returns `None`
+/// if no fixes were applied.
+fn apply_safe_fixes(contents: &str, fixes: Vec<Fix>) -> Option<String> {
+    let mut edits: Vec<TextEdit> = Vec::new();
+    for fix in fixes {
+        if fix.applicability != Applicability::Safe
+            || fix.edits.iter().any(|edit| {
+                edits
+                    .iter()
+                    .any(|other| ranges_conflict(edit.range, other.range))
+            })
+        {
+            continue;
+        }
+        edits.extend(fix.edits);
+    }
+
+    if edits.is_empty() {
+        return None;
+    }
+
+    let mut contents = contents.to_string();
+    edits.sort_by_key(|edit| Reverse(edit.range.start()));
+    for edit in edits {
+        contents.replace_range(Range::<usize>::from(edit.range), &edit.new_text);
+    }
+    Some(contents)
+}
+
+fn ranges_conflict(a: TextRange, b: TextRange) -> bool {
+    // Adjacent edits can be applied together, unless one of them is an insertion, in which case
+    // the order of the resulting text would be ambiguous.
+    if a.is_empty() || b.is_empty() {
+        a.start() <= b.end() && b.start() <= a.end()
+    } else {
+        a.start() < b.end() && b.start() < a.end()
+    }
 }
 
 impl Checker {
@@ -155,8 +412,9 @@ impl Checker {
         bazel_info: BazelInfo,
         interner: Arc<PathInterner>,
         paths: Vec<String>,
-        ignore_patterns: Vec<String>,
+        ignore_filter: IgnoreFilter,
         extensions: &[&str],
+        options: ReportOptions,
     ) -> anyhow::Result<Self> {
         let mut checker = Self {
             analysis,
@@ -164,38 +422,39 @@ impl Checker {
             bazel_info,
             files: Default::default(),
             ignored_files: Default::default(),
+            walker: FileWalker {
+                paths,
+                ignore_filter,
+                dirs: Vec::new(),
+            },
+            extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+            thread_pool: Arc::new(ThreadPoolBuilder::new().num_threads(options.jobs).build()?),
+            options,
         };
         let mut change = Change::default();
-
-        for path in paths {
-            for entry in WalkDir::new(&path).into_iter().filter_entry(|e| {
-                !is_hidden(e)
-                    && !ignore_patterns
-                        .iter()
-                        .any(|pat| e.file_name().to_str().map(|s| s == pat).unwrap_or(false))
-            }) {
-                let entry = entry?;
-                if entry.file_type().is_file() {
-                    let is_explicit = entry.path().as_os_str().to_str() == Some(path.as_str());
-                    checker.load_file(&mut change, entry.path(), is_explicit, extensions)?;
-                }
-            }
-        }
-
+        checker.load_files(&mut change)?;
         checker.analysis.apply_change(change);
         Ok(checker)
     }
 
+    /// Loads every file under the checked paths that isn't loaded yet.
+    fn load_files(&mut self, change: &mut Change) -> anyhow::Result<()> {
+        for (path, is_explicit) in self.walker.walk()? {
+            self.load_file(change, &path, is_explicit)?;
+        }
+        Ok(())
+    }
+
     fn load_file(
         &mut self,
         change: &mut Change,
         path: &Path,
         is_explicit: bool,
-        extensions: &[&str],
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<Option<FileId>> {
         let canonical_path = PathBuf::from(&path).canonicalize()?;
-        if self.interner.lookup_by_path_buf(&canonical_path).is_some() {
-            return Ok(());
+        let existing_file_id = self.interner.lookup_by_path_buf(&canonical_path);
+        if existing_file_id.is_some_and(|file_id| self.files.contains_key(&file_id)) {
+            return Ok(None);
         }
 
         let (dialect, api_context) = match document::dialect_and_api_context_for_workspace_path(
@@ -212,24 +471,38 @@ impl Checker {
             && !path
                 .extension()
                 .and_then(|ext| ext.to_str())
-                .map(|ext| extensions.contains(&ext))
+                .map(|ext| self.extensions.iter().any(|other| other == ext))
                 .unwrap_or(false)
         {
             if is_explicit {
                 self.ignored_files.insert(path.to_path_buf());
             }
-            return Ok(());
+            return Ok(None);
         }
 
         let contents = fs::read_to_string(&canonical_path)?;
 
+        // In standalone mode, the output base may be unknown, in which case no files are external.
+        let output_base = &self.bazel_info.output_base;
         let info = api_context.map(|api_context| FileInfo::Bazel {
             api_context,
-            is_external: canonical_path.starts_with(&self.bazel_info.output_base),
+            is_external: !output_base.as_os_str().is_empty()
+                && canonical_path.starts_with(output_base),
         });
 
-        let file_id = self.interner.intern_path(canonical_path);
-        change.create_file(file_id, dialect, info, contents.clone());
+        // Files that were already loaded as dependencies of other files only need to be tracked.
+        // This happens in watch mode, when a file is created after the initial check.
+        let file_id = match existing_file_id {
+            Some(file_id) => {
+                change.update_file(file_id, contents.clone());
+                file_id
+            }
+            None => {
+                let file_id = self.interner.intern_path(canonical_path);
+                change.create_file(file_id, dialect, info, contents.clone());
+                file_id
+            }
+        };
         self.files.insert(
             file_id,
             FileMetadata {
@@ -238,66 +511,328 @@ impl Checker {
             },
         );
 
+        Ok(Some(file_id))
+    }
+
+    /// Returns the path of the given file relative to the workspace root, which is used as the
+    /// key for baseline entries, in diffs, and to match severity overrides.
+    fn workspace_relative_path(&self, file_id: FileId) -> String {
+        let path = self.interner.lookup_by_file_id(file_id);
+        path.strip_prefix(&self.bazel_info.workspace)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    /// Repeatedly applies safe fixes to the checked files until no more are available. The
+    /// analysis and file metadata are updated with the fixed contents, and the original contents
+    /// of every changed file are returned.
+    fn apply_fixes(&mut self) -> anyhow::Result<Vec<(FileId, String)>> {
+        let mut file_ids = self.files.keys().copied().collect::<Vec<_>>();
+        file_ids.sort();
+
+        let mut original_contents = HashMap::new();
+        for _ in 0..MAX_FIX_ITERATIONS {
+            let snapshot = self.analysis.snapshot();
+            let mut change = Change::default();
+            let mut has_changes = false;
+            for file_id in &file_ids {
+                // Skip fixes for diagnostics that are turned off for this file.
+                let path = self.workspace_relative_path(*file_id);
+                let mut fixes = snapshot.fixes(*file_id)?;
+                fixes.retain(|fix| {
+                    self.options.workspace_config.is_enabled(&path, fix.code)
+                        && !self.options.severity_options.allow.contains(&fix.code)
+                });
+                let metadata = self.files.get_mut(file_id).expect("file metadata");
+                let Some(contents) = apply_safe_fixes(&metadata.contents, fixes) else {
+                    continue;
+                };
+                let contents = std::mem::replace(&mut metadata.contents, contents);
+                original_contents.entry(*file_id).or_insert(contents);
+                change.update_file(*file_id, metadata.contents.clone());
+                has_changes = true;
+            }
+
+            drop(snapshot);
+            if !has_changes {
+                break;
+            }
+            self.analysis.apply_change(change);
+        }
+
+        let mut original_contents = original_contents
+            .into_iter()
+            .filter(|(file_id, contents)| &self.files[file_id].contents != contents)
+            .collect::<Vec<_>>();
+        original_contents.sort_by_key(|(file_id, _)| *file_id);
+        Ok(original_contents)
+    }
+
+    fn write_fixes(&self, original_contents: Vec<(FileId, String)>) -> anyhow::Result<()> {
+        for (file_id, _) in &original_contents {
+            let path = self.interner.lookup_by_file_id(*file_id);
+            write_file_atomically(&path, self.files[file_id].contents.as_bytes())
+                .map_err(|err| anyhow!("failed to write fixes to {:?}: {}", path, err))?;
+        }
+
+        if !original_contents.is_empty() {
+            anstream::eprintln!(
+                "{}\n",
+                Renderer::styled().render(Level::Info.title(&format!(
+                    "applied fixes to {} files",
+                    original_contents.len()
+                )))
+            );
+        }
+        Ok(())
+    }
+
+    /// Prints the changes made by `apply_fixes` as a unified diff. Exits with a non-zero status if
+    /// there are any changes.
+    fn report_fix_diffs(&self, original_contents: Vec<(FileId, String)>) -> anyhow::Result<()> {
+        let mut stdout = io::stdout().lock();
+        for (file_id, contents) in &original_contents {
+            let path = self.workspace_relative_path(*file_id);
+            write!(
+                stdout,
+                "{}",
+                unified_diff(
+                    contents,
+                    &self.files[file_id].contents,
+                    &format!("a/{}", path),
+                    &format!("b/{}", path),
+                )
+            )?;
+        }
+        stdout.flush()?;
+
+        if !original_contents.is_empty() {
+            std::process::exit(1);
+        }
         Ok(())
     }
 
+    /// Computes diagnostics for the given files and passes them to `f` in the order the files
+    /// were given. Results are reported as soon as the diagnostics for every earlier file are
+    /// available, rather than after the whole workspace has been checked.
+    fn for_each_file_diagnostics(
+        &mut self,
+        file_ids: &[FileId],
+        mut f: impl FnMut(&mut Self, FileId, Vec<Diagnostic>) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        // Each of the pool's threads computes diagnostics with its own snapshot of the database,
+        // forked from a shared snapshot through a lock, since snapshots themselves aren't `Sync`.
+        let root = Mutex::new(self.analysis.snapshot());
+        let thread_pool = self.thread_pool.clone();
+        let (sender, receiver) = crossbeam_channel::unbounded();
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                // Sending fails once the receiver has been dropped after an error, which stops
+                // the remaining files from being checked.
+                let _ = thread_pool.install(|| {
+                    file_ids.par_iter().enumerate().try_for_each_init(
+                        || root.lock().snapshot(),
+                        |snapshot, (index, file_id)| {
+                            sender.send((index, snapshot.diagnostics(*file_id)))
+                        },
+                    )
+                });
+                drop(sender);
+            });
+
+            // Files finish in an arbitrary order, so buffer results until every earlier file has
+            // been reported, which keeps the output deterministic.
+            let mut pending = HashMap::new();
+            let mut next = 0;
+            for (index, diagnostics) in receiver {
+                pending.insert(index, diagnostics);
+                while let Some(diagnostics) = pending.remove(&next) {
+                    f(self, file_ids[next], diagnostics?)?;
+                    next += 1;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Applies severity overrides from `starpls.toml` and the command line to the diagnostics for
+    /// a file, dropping any that shouldn't be reported.
+    fn filter_diagnostics(&self, file_id: FileId, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        self.options
+            .workspace_config
+            .apply_severity_overrides(&self.workspace_relative_path(file_id), diagnostics)
+            .into_iter()
+            .filter_map(|diagnostic| self.options.severity_options.apply(diagnostic))
+            .collect()
+    }
+
     fn report_diagnostics_for_file(
-        &self,
-        snapshot: &AnalysisSnapshot,
+        &mut self,
         file_id: FileId,
-        metadata: &FileMetadata,
-        num_errors: &mut usize,
-        num_warnings: &mut usize,
-        num_infos: &mut usize,
+        diagnostics: Vec<Diagnostic>,
+        report: &mut Report,
     ) -> anyhow::Result<()> {
+        let diagnostics = self.filter_diagnostics(file_id, diagnostics);
         let renderer = Renderer::styled();
-        for diagnostic in snapshot.diagnostics(file_id)? {
-            match diagnostic.severity {
-                Severity::Info => *num_infos += 1,
-                Severity::Warning => *num_warnings += 1,
-                Severity::Error => *num_errors += 1,
+        let metadata = &self.files[&file_id];
+        let line_index = match self.options.output_format {
+            OutputFormat::Human => None,
+            _ => Some(LineIndex::new(&metadata.contents)),
+        };
+        let uses_baseline =
+            self.options.baseline.is_some() || self.options.write_baseline.is_some();
+        let baseline_path = uses_baseline.then(|| self.workspace_relative_path(file_id));
+
+        if let Some(ref baseline_path) = baseline_path {
+            report.checked_paths.insert(baseline_path.clone());
+        }
+
+        for diagnostic in diagnostics {
+            if let Some(ref baseline_path) = baseline_path {
+                let entry =
+                    BaselineEntry::new(baseline_path.clone(), &diagnostic, &metadata.contents);
+                if self.options.write_baseline.is_some() {
+                    report.baseline_entries.push(entry);
+                    continue;
+                }
+                if let Some(baseline) = self.options.baseline.as_mut() {
+                    if baseline.take(&entry) {
+                        report.num_baselined += 1;
+                        continue;
+                    }
+                }
             }
-            anstream::print!(
-                "{}\n\n",
-                renderer.render(diagnostic_to_message(&diagnostic, metadata))
+
+            report.summary.add(&diagnostic.severity);
+            match line_index {
+                Some(ref line_index) => report.records.push(Record::from_diagnostic(
+                    &metadata.path,
+                    line_index,
+                    &diagnostic,
+                )),
+                None => anstream::print!(
+                    "{}\n\n",
+                    renderer.render(diagnostic_to_message(&diagnostic, metadata))
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    fn report_baseline(&self, report: &mut Report) -> anyhow::Result<()> {
+        if let Some(ref path) = self.options.write_baseline {
+            let num_entries = report.baseline_entries.len();
+            baseline::write_baseline(path, std::mem::take(&mut report.baseline_entries))?;
+            anstream::eprintln!(
+                "{}",
+                Renderer::styled().render(Level::Info.title(&format!(
+                    "wrote {} entries to baseline file {:?}",
+                    num_entries, path
+                )))
             );
+            return Ok(());
         }
+
+        let Some(ref baseline) = self.options.baseline else {
+            return Ok(());
+        };
+
+        if report.num_baselined > 0 {
+            anstream::eprintln!(
+                "{}\n",
+                Renderer::styled().render(Level::Info.title(&format!(
+                    "{} diagnostics were suppressed by the baseline",
+                    report.num_baselined
+                )))
+            );
+        }
+
+        // Report entries that no longer match any diagnostic, so that the baseline can be shrunk by
+        // rerunning with `--write_baseline`.
+        let stale_entries = baseline.stale_entries(&report.checked_paths);
+        if !stale_entries.is_empty() {
+            let mut message = format!(
+                "{} baseline entries no longer match any diagnostic and can be removed with `--write_baseline`",
+                stale_entries.len()
+            );
+            for entry in stale_entries {
+                message.push_str(&format!(
+                    "\n- {}: {}: {}",
+                    entry.path, entry.code, entry.message
+                ));
+            }
+            anstream::eprintln!(
+                "{}\n",
+                Renderer::styled().render(Level::Info.title(&message))
+            );
+        }
+
         Ok(())
     }
 
-    fn report_diagnostics(&self) -> anyhow::Result<()> {
-        let snapshot = self.analysis.snapshot();
-        let mut num_errors = 0;
-        let mut num_warnings = 0;
-        let mut num_infos = 0;
+    fn report_diagnostics(mut self) -> anyhow::Result<()> {
+        let mut report = Report::default();
 
         let mut ignored_files = self.ignored_files.iter().collect::<Vec<_>>();
         ignored_files.sort();
 
         for path in ignored_files {
-            anstream::print!(
-                "{}\n\n",
-                Renderer::styled().render(
-                    Level::Warning.title(&format!("non-Starlark file {:?} was ignored", path))
-                )
-            );
-            num_warnings += 1;
+            let Some(severity) = self
+                .options
+                .severity_options
+                .adjust_severity(Severity::Warning)
+            else {
+                continue;
+            };
+            let message = format!("non-Starlark file {:?} was ignored", path);
+            if self.options.output_format == OutputFormat::Human {
+                let level = match severity {
+                    Severity::Error => Level::Error,
+                    _ => Level::Warning,
+                };
+                anstream::print!("{}\n\n", Renderer::styled().render(level.title(&message)));
+            } else {
+                report
+                    .records
+                    .push(Record::for_path(path, severity.clone(), message));
+            }
+            report.summary.add(&severity);
+        }
+
+        let mut file_ids = self.files.keys().copied().collect::<Vec<_>>();
+        file_ids.sort();
+
+        self.for_each_file_diagnostics(&file_ids, |checker, file_id, diagnostics| {
+            checker.report_diagnostics_for_file(file_id, diagnostics, &mut report)
+        })?;
+
+        self.report_baseline(&mut report)?;
+        if self.options.write_baseline.is_some() {
+            return Ok(());
         }
 
-        let mut files = self.files.iter().collect::<Vec<_>>();
-        files.sort_by_key(|(file_id, _)| **file_id);
-
-        for (file_id, metadata) in files {
-            self.report_diagnostics_for_file(
-                &snapshot,
-                *file_id,
-                metadata,
-                &mut num_errors,
-                &mut num_warnings,
-                &mut num_infos,
+        let summary = report.summary;
+        let exceeds_max_warnings = self
+            .options
+            .severity_options
+            .exceeds_max_warnings(summary.warnings);
+        if self.options.output_format != OutputFormat::Human {
+            output::write_records(
+                &mut io::stdout().lock(),
+                self.options.output_format,
+                &report.records,
+                &summary,
             )?;
+            if summary.errors > 0 || exceeds_max_warnings {
+                std::process::exit(1);
+            }
+            return Ok(());
         }
 
+        let num_errors = summary.errors;
+        let num_warnings = summary.warnings;
         if num_errors > 0 {
             if num_warnings > 0 {
                 anstream::println!(
@@ -316,6 +851,17 @@ impl Checker {
             }
             std::process::exit(1);
         }
+        if exceeds_max_warnings {
+            anstream::println!(
+                "{}",
+                Renderer::styled().render(Level::Error.title(&format!(
+                    "failed with {} warnings, which exceeds the maximum of {}",
+                    num_warnings,
+                    self.options.severity_options.max_warnings.unwrap_or_default()
+                )))
+            );
+            std::process::exit(1);
+        }
         if num_warnings > 0 {
             anstream::println!(
                 "{}",
@@ -328,3 +874,123 @@ impl Checker {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::glob::GlobSet;
+
+    /// A temporary workspace that is removed when dropped.
+    struct TestWorkspace(PathBuf);
+
+    impl TestWorkspace {
+        fn new(name: &str, files: &[(&str, &str)]) -> Self {
+            let root =
+                std::env::temp_dir().join(format!("starpls-check-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            for (path, contents) in files {
+                let path = root.join(path);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, contents).unwrap();
+            }
+            Self(root)
+        }
+
+        fn read(&self, path: &str) -> String {
+            fs::read_to_string(self.0.join(path)).unwrap()
+        }
+    }
+
+    impl Drop for TestWorkspace {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Creates a checker for the whole workspace in standalone mode, with the default options.
+    fn checker(workspace: &TestWorkspace) -> Checker {
+        let bazel_cx = BazelContext::standalone(&workspace.0, None).unwrap();
+        let interner = Arc::new(PathInterner::default());
+        let (fetch_repo_sender, _) = crossbeam_channel::unbounded();
+        let loader = DefaultFileLoader::new(
+            Arc::new(BazelCLI::default()),
+            interner.clone(),
+            bazel_cx.info.workspace.clone(),
+            None,
+            PathBuf::new(),
+            fetch_repo_sender,
+            false,
+        )
+        .with_standalone_mode();
+        let mut analysis = Analysis::new(Arc::new(loader), Default::default());
+        analysis.set_builtin_defs(load_bazel_builtins(), bazel_cx.rules);
+
+        Checker::new(
+            analysis,
+            bazel_cx.info,
+            interner,
+            vec![workspace.0.to_string_lossy().into_owned()],
+            IgnoreFilter::new(&workspace.0, GlobSet::default()),
+            &["star", "sky"],
+            ReportOptions {
+                jobs: 1,
+                output_format: OutputFormat::Human,
+                baseline: None,
+                write_baseline: None,
+                workspace_config: Default::default(),
+                severity_options: Default::default(),
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fix() {
+        let workspace = TestWorkspace::new(
+            "fix",
+            &[
+                ("MODULE.bazel", ""),
+                (
+                    "defs.bzl",
+                    "def my_macro(**kwargs):\n    pass\n\ndef unused_macro(**kwargs):\n    pass\n",
+                ),
+                (
+                    "pkg/BUILD.bazel",
+                    r#"load("//:defs.bzl", "my_macro", "unused_macro")
+
+my_macro(
+    name = "pkg",
+    deps = [
+        "//pkg/lib:lib",
+        "//pkg/lib:other",
+    ],
+)
+"#,
+                ),
+            ],
+        );
+
+        let mut checker = checker(&workspace);
+        let original_contents = checker.apply_fixes().unwrap();
+        assert_eq!(original_contents.len(), 1);
+        checker.write_fixes(original_contents).unwrap();
+
+        assert_eq!(
+            workspace.read("pkg/BUILD.bazel"),
+            r#"load("//:defs.bzl", "my_macro")
+
+my_macro(
+    name = "pkg",
+    deps = [
+        "//pkg/lib",
+        "//pkg/lib:other",
+    ],
+)
+"#
+        );
+        assert_eq!(
+            workspace.read("defs.bzl"),
+            "def my_macro(**kwargs):\n    pass\n\ndef unused_macro(**kwargs):\n    pass\n"
+        );
+    }
+}
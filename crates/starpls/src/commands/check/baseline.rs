@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::anyhow;
+use anyhow::bail;
+use serde::Deserialize;
+use serde::Serialize;
+use starpls_common::Diagnostic;
+use starpls_common::DiagnosticCode;
+
+use crate::utils::write_file_atomically;
+
+const BASELINE_VERSION: u32 = 1;
+
+/// The number of lines before and after a diagnostic's range that are included in its fingerprint.
+const FINGERPRINT_CONTEXT_LINES: usize = 1;
+
+#[derive(Serialize, Deserialize)]
+struct BaselineFile {
+    version: u32,
+    entries: Vec<BaselineEntry>,
+}
+
+/// A recorded diagnostic. Entries are matched by path, code and a fingerprint of the
+/// source text surrounding the diagnostic, so that they survive edits elsewhere in the file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct BaselineEntry {
+    pub(crate) path: String,
+    pub(crate) code: String,
+    pub(crate) fingerprint: String,
+    pub(crate) message: String,
+}
+
+impl BaselineEntry {
+    pub(crate) fn new(path: String, diagnostic: &Diagnostic, contents: &str) -> Self {
+        Self {
+            path,
+            code: diagnostic.code.as_str().to_string(),
+            fingerprint: fingerprint(diagnostic, contents),
+            message: diagnostic.message.clone(),
+        }
+    }
+
+    fn key(&self) -> (String, String, String) {
+        (
+            self.path.clone(),
+            self.code.clone(),
+            self.fingerprint.clone(),
+        )
+    }
+}
+
+/// A set of previously recorded diagnostics that shouldn't be reported again.
+pub(crate) struct Baseline {
+    entries: HashMap<(String, String, String), Vec<BaselineEntry>>,
+}
+
+impl Baseline {
+    pub(crate) fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| anyhow!("failed to read baseline file {:?}: {}", path, err))?;
+        let file: BaselineFile = serde_json::from_str(&contents)
+            .map_err(|err| anyhow!("failed to parse baseline file {:?}: {}", path, err))?;
+        if file.version != BASELINE_VERSION {
+            bail!(
+                "unsupported baseline file version {} in {:?}, expected {}",
+                file.version,
+                path,
+                BASELINE_VERSION
+            );
+        }
+
+        let mut entries: HashMap<_, Vec<_>> = HashMap::new();
+        for entry in file.entries {
+            if let Err(err) = entry.code.parse::<DiagnosticCode>() {
+                bail!("invalid baseline file {:?}: {}", path, err);
+            }
+            entries.entry(entry.key()).or_default().push(entry);
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Consumes a matching entry from the baseline, returning whether one was found. Each entry
+    /// suppresses at most one diagnostic.
+    pub(crate) fn take(&mut self, entry: &BaselineEntry) -> bool {
+        self.entries
+            .get_mut(&entry.key())
+            .and_then(|entries| entries.pop())
+            .is_some()
+    }
+
+    /// Returns the entries that didn't match any diagnostic in the given set of checked paths.
+    pub(crate) fn stale_entries(&self, checked_paths: &HashSet<String>) -> Vec<&BaselineEntry> {
+        let mut stale = self
+            .entries
+            .values()
+            .flatten()
+            .filter(|entry| checked_paths.contains(&entry.path))
+            .collect::<Vec<_>>();
+        stale.sort_by(|a, b| a.key().cmp(&b.key()));
+        stale
+    }
+}
+
+pub(crate) fn write_baseline(path: &Path, mut entries: Vec<BaselineEntry>) -> anyhow::Result<()> {
+    entries.sort_by(|a, b| a.key().cmp(&b.key()));
+    let mut contents = serde_json::to_string_pretty(&BaselineFile {
+        version: BASELINE_VERSION,
+        entries,
+    })?;
+    contents.push('\n');
+    write_file_atomically(path, contents.as_bytes())
+        .map_err(|err| anyhow!("failed to write baseline file {:?}: {}", path, err))
+}
+
+/// Computes a fingerprint of the lines spanned by a diagnostic, plus a few lines of context.
+/// Leading and trailing whitespace is ignored so that reindenting code doesn't invalidate entries.
+fn fingerprint(diagnostic: &Diagnostic, contents: &str) -> String {
+    let start: usize = diagnostic.range.range.start().into();
+    let end: usize = diagnostic.range.range.end().into();
+    let start_line = contents[..start.min(contents.len())].matches('\n').count();
+    let end_line = contents[..end.min(contents.len())].matches('\n').count();
+
+    let first_line = start_line.saturating_sub(FINGERPRINT_CONTEXT_LINES);
+    let last_line = end_line + FINGERPRINT_CONTEXT_LINES;
+
+    let mut hash = FNV_OFFSET_BASIS;
+    for line in contents
+        .lines()
+        .skip(first_line)
+        .take(last_line + 1 - first_line)
+    {
+        for byte in line.trim().bytes().chain([b'\n']) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+// 64-bit FNV-1a. A fixed hash function is used, rather than `DefaultHasher`, since fingerprints
+// are persisted and must be stable across builds.
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+#[cfg(test)]
+mod tests {
+    use starpls_common::FileId;
+    use starpls_common::FileRange;
+    use starpls_common::Severity;
+    use starpls_syntax::TextRange;
+    use starpls_syntax::TextSize;
+
+    use super::*;
+
+    /// Creates an entry for an unused load of `name`, which must occur in `contents`.
+    fn entry(path: &str, contents: &str, name: &str) -> BaselineEntry {
+        let start = contents.find(name).expect("name not found");
+        let diagnostic = Diagnostic {
+            message: format!("{} is not used", name),
+            severity: Severity::Warning,
+            range: FileRange {
+                file_id: FileId(0),
+                range: TextRange::at(
+                    TextSize::new(start as u32),
+                    TextSize::new(name.len() as u32),
+                ),
+            },
+            tags: None,
+            code: DiagnosticCode::UnusedLoad,
+            related_information: None,
+        };
+        BaselineEntry::new(path.to_string(), &diagnostic, contents)
+    }
+
+    fn baseline(entries: Vec<BaselineEntry>) -> Baseline {
+        let mut baseline = Baseline {
+            entries: HashMap::new(),
+        };
+        for entry in entries {
+            baseline.entries.entry(entry.key()).or_default().push(entry);
+        }
+        baseline
+    }
+
+    #[test]
+    fn test_fingerprint_survives_edits_elsewhere() {
+        let contents = "\"\"\"Docs.\"\"\"\n\nload(\":a.bzl\", \"a\")\nx = 1\n\ny = 2\n";
+        let original = entry("a.bzl", contents, "\"a\"");
+
+        // Lines added above the diagnostic, outside of its context, don't change the fingerprint.
+        let shifted = format!("# Header.\n# More.\n{}", contents);
+        assert_eq!(
+            original.fingerprint,
+            entry("a.bzl", &shifted, "\"a\"").fingerprint
+        );
+
+        // Neither does reindenting the surrounding lines.
+        let reindented = contents.replace("x = 1", "    x = 1  ");
+        assert_eq!(
+            original.fingerprint,
+            entry("a.bzl", &reindented, "\"a\"").fingerprint
+        );
+
+        // Editing the line of the diagnostic or its context does.
+        let edited = contents.replace("\"a\")", "\"a\", \"b\")");
+        assert_ne!(
+            original.fingerprint,
+            entry("a.bzl", &edited, "\"a\"").fingerprint
+        );
+        let edited = contents.replace("x = 1", "x = 2");
+        assert_ne!(
+            original.fingerprint,
+            entry("a.bzl", &edited, "\"a\"").fingerprint
+        );
+    }
+
+    #[test]
+    fn test_take_consumes_duplicate_entries_once_each() {
+        let contents = "load(\":a.bzl\", \"a\", \"a\")\n";
+        let entry = entry("a.bzl", contents, "\"a\"");
+        let mut baseline = baseline(vec![entry.clone(), entry.clone()]);
+        assert!(baseline.take(&entry));
+        assert!(baseline.take(&entry));
+        assert!(!baseline.take(&entry));
+    }
+
+    #[test]
+    fn test_stale_entries() {
+        let contents = "load(\":a.bzl\", \"a\", \"b\")\n\nload(\":c.bzl\", \"c\")\n";
+        let a = entry("a.bzl", contents, "\"a\"");
+        let c = entry("a.bzl", contents, "\"c\"");
+        let other = entry("b.bzl", contents, "\"a\"");
+        let mut baseline = baseline(vec![a.clone(), c.clone(), other]);
+        assert!(baseline.take(&a));
+
+        // Only entries for paths that were checked are stale, since the others may still match.
+        let checked_paths = HashSet::from(["a.bzl".to_string()]);
+        let stale = baseline
+            .stale_entries(&checked_paths)
+            .into_iter()
+            .map(|entry| entry.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(stale, ["\"c\" is not used"]);
+        assert!(baseline.stale_entries(&HashSet::new()).is_empty());
+    }
+
+    #[test]
+    fn test_write_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("starpls-baseline-{}.json", std::process::id()));
+        let contents = "load(\":a.bzl\", \"a\")\n\n\nload(\":b.bzl\", \"b\")\n";
+        let a = entry("a.bzl", contents, "\"a\"");
+        let b = entry("a.bzl", contents, "\"b\"");
+        write_baseline(&path, vec![b.clone(), a.clone(), a.clone()]).unwrap();
+
+        let loaded = Baseline::load(&path);
+        let written = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let mut loaded = loaded.unwrap();
+        assert!(written.starts_with("{\n  \"version\": 1,"));
+        assert!(written.ends_with("}\n"));
+        assert!(loaded.take(&a));
+        assert!(loaded.take(&a));
+        assert!(!loaded.take(&a));
+        assert!(loaded.take(&b));
+        assert!(loaded
+            .stale_entries(&HashSet::from(["a.bzl".to_string()]))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_load_rejects_unknown_codes() {
+        let path = std::env::temp_dir().join(format!(
+            "starpls-baseline-invalid-{}.json",
+            std::process::id()
+        ));
+        fs::write(
+            &path,
+            r#"{"version": 1, "entries": [{"path": "a.bzl", "code": "no-such-code", "fingerprint": "0", "message": ""}]}"#,
+        )
+        .unwrap();
+        let err = Baseline::load(&path).err().unwrap();
+        let _ = fs::remove_file(&path);
+        assert!(err
+            .to_string()
+            .contains("unknown diagnostic code \"no-such-code\""));
+    }
+}
@@ -0,0 +1,500 @@
+use std::io::Write;
+use std::io::{self};
+use std::path::Path;
+
+use clap::ValueEnum;
+use line_index::LineIndex;
+use line_index::WideEncoding;
+use serde::Serialize;
+use serde_json::json;
+use starpls_common::Diagnostic;
+use starpls_common::DiagnosticCode;
+use starpls_common::Severity;
+
+use crate::get_version;
+
+/// The format used to report diagnostics from `starpls check`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum OutputFormat {
+    /// Human-readable annotated source snippets.
+    #[default]
+    Human,
+    /// A JSON document containing every diagnostic and a summary.
+    Json,
+    /// A SARIF 2.1.0 log, suitable for code-scanning uploads.
+    Sarif,
+    /// GitHub Actions workflow commands, rendered as annotations.
+    Github,
+    /// Checkstyle XML.
+    Checkstyle,
+}
+
+/// The number of diagnostics reported at each severity.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub(crate) struct Summary {
+    pub(crate) errors: usize,
+    pub(crate) warnings: usize,
+    pub(crate) infos: usize,
+}
+
+impl Summary {
+    pub(crate) fn add(&mut self, severity: &Severity) {
+        match severity {
+            Severity::Hint | Severity::Info => self.infos += 1,
+            Severity::Warning => self.warnings += 1,
+            Severity::Error => self.errors += 1,
+        }
+    }
+}
+
+/// A 1-based source location. Columns are measured in UTF-16 code units.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub(crate) struct Location {
+    pub(crate) line: u32,
+    pub(crate) column: u32,
+    pub(crate) end_line: u32,
+    pub(crate) end_column: u32,
+}
+
+/// A single diagnostic, resolved to a path and a line/column location.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct Record {
+    pub(crate) path: String,
+    #[serde(flatten)]
+    pub(crate) location: Option<Location>,
+    #[serde(serialize_with = "serialize_severity")]
+    pub(crate) severity: Severity,
+    #[serde(serialize_with = "serialize_code")]
+    pub(crate) code: Option<DiagnosticCode>,
+    pub(crate) message: String,
+}
+
+impl Record {
+    pub(crate) fn from_diagnostic(
+        path: &Path,
+        line_index: &LineIndex,
+        diagnostic: &Diagnostic,
+    ) -> Self {
+        let location = (|| {
+            let start = line_index.to_wide(
+                WideEncoding::Utf16,
+                line_index.line_col(diagnostic.range.range.start()),
+            )?;
+            let end = line_index.to_wide(
+                WideEncoding::Utf16,
+                line_index.line_col(diagnostic.range.range.end()),
+            )?;
+            Some(Location {
+                line: start.line + 1,
+                column: start.col + 1,
+                end_line: end.line + 1,
+                end_column: end.col + 1,
+            })
+        })();
+        Self {
+            path: display_path(path),
+            location,
+            severity: diagnostic.severity.clone(),
+            code: Some(diagnostic.code),
+            message: diagnostic.message.clone(),
+        }
+    }
+
+    /// Creates a record that isn't associated with any particular location in a file.
+    pub(crate) fn for_path(path: &Path, severity: Severity, message: String) -> Self {
+        Self {
+            path: display_path(path),
+            location: None,
+            severity,
+            code: None,
+            message,
+        }
+    }
+}
+
+fn display_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn severity_as_str(severity: &Severity) -> &'static str {
+    match severity {
+        Severity::Hint => "hint",
+        Severity::Info => "info",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    }
+}
+
+fn serialize_severity<S: serde::Serializer>(
+    severity: &Severity,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(severity_as_str(severity))
+}
+
+fn serialize_code<S: serde::Serializer>(
+    code: &Option<DiagnosticCode>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match code {
+        Some(code) => serializer.serialize_some(code.as_str()),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Writes the given records and summary to `out` in the specified machine-readable format.
+pub(crate) fn write_records(
+    out: &mut impl Write,
+    format: OutputFormat,
+    records: &[Record],
+    summary: &Summary,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Human => unreachable!("human-readable output is rendered by the checker"),
+        OutputFormat::Json => write_json(out, records, summary),
+        OutputFormat::Sarif => write_sarif(out, records, summary),
+        OutputFormat::Github => write_github(out, records),
+        OutputFormat::Checkstyle => write_checkstyle(out, records),
+    }
+}
+
+fn write_json(out: &mut impl Write, records: &[Record], summary: &Summary) -> io::Result<()> {
+    serde_json::to_writer_pretty(
+        &mut *out,
+        &json!({
+            "diagnostics": records,
+            "summary": summary,
+        }),
+    )?;
+    writeln!(out)
+}
+
+fn write_sarif(out: &mut impl Write, records: &[Record], summary: &Summary) -> io::Result<()> {
+    let mut codes = records
+        .iter()
+        .filter_map(|record| record.code)
+        .collect::<Vec<_>>();
+    codes.sort();
+    codes.dedup();
+
+    let rules = codes
+        .iter()
+        .map(|code| json!({ "id": code.as_str(), "name": code.as_str() }))
+        .collect::<Vec<_>>();
+
+    let results = records
+        .iter()
+        .map(|record| {
+            let mut physical_location = json!({
+                "artifactLocation": { "uri": record.path },
+            });
+            if let Some(location) = record.location {
+                physical_location["region"] = json!({
+                    "startLine": location.line,
+                    "startColumn": location.column,
+                    "endLine": location.end_line,
+                    "endColumn": location.end_column,
+                });
+            }
+            let mut result = json!({
+                "level": match record.severity {
+                    Severity::Hint | Severity::Info => "note",
+                    Severity::Warning => "warning",
+                    Severity::Error => "error",
+                },
+                "message": { "text": record.message },
+                "locations": [{ "physicalLocation": physical_location }],
+            });
+            if let Some(code) = record.code {
+                result["ruleId"] = json!(code.as_str());
+            }
+            result
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_writer_pretty(
+        &mut *out,
+        &json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "starpls",
+                        "version": get_version(),
+                        "informationUri": "https://github.com/withered-magic/starpls",
+                        "rules": rules,
+                    },
+                },
+                "columnKind": "utf16CodeUnits",
+                "results": results,
+                "properties": { "summary": summary },
+            }],
+        }),
+    )?;
+    writeln!(out)
+}
+
+fn write_github(out: &mut impl Write, records: &[Record]) -> io::Result<()> {
+    for record in records {
+        let command = match record.severity {
+            Severity::Hint | Severity::Info => "notice",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        let mut properties = vec![format!("file={}", escape_github_property(&record.path))];
+        if let Some(location) = record.location {
+            properties.push(format!("line={}", location.line));
+            properties.push(format!("col={}", location.column));
+            properties.push(format!("endLine={}", location.end_line));
+            properties.push(format!("endColumn={}", location.end_column));
+        }
+        if let Some(code) = record.code {
+            properties.push(format!("title={}", escape_github_property(code.as_str())));
+        }
+        writeln!(
+            out,
+            "::{} {}::{}",
+            command,
+            properties.join(","),
+            escape_github_data(&record.message)
+        )?;
+    }
+    Ok(())
+}
+
+fn escape_github_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_github_property(s: &str) -> String {
+    escape_github_data(s)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+fn write_checkstyle(out: &mut impl Write, records: &[Record]) -> io::Result<()> {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<checkstyle version="4.3">"#)?;
+
+    // Records are already ordered by file, so consecutive records with the same path
+    // can be grouped under a single <file> element.
+    let mut current_path: Option<&str> = None;
+    for record in records {
+        if current_path != Some(record.path.as_str()) {
+            if current_path.is_some() {
+                writeln!(out, "  </file>")?;
+            }
+            writeln!(out, r#"  <file name="{}">"#, escape_xml(&record.path))?;
+            current_path = Some(&record.path);
+        }
+        let (line, column) = record
+            .location
+            .map(|location| (location.line, location.column))
+            .unwrap_or((0, 0));
+        let source = match record.code {
+            Some(code) => format!("starpls.{}", code.as_str()),
+            None => "starpls".to_string(),
+        };
+        writeln!(
+            out,
+            r#"    <error line="{}" column="{}" severity="{}" message="{}" source="{}"/>"#,
+            line,
+            column,
+            severity_as_str(&record.severity),
+            escape_xml(&record.message),
+            escape_xml(&source),
+        )?;
+    }
+    if current_path.is_some() {
+        writeln!(out, "  </file>")?;
+    }
+
+    writeln!(out, "</checkstyle>")
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\n' => escaped.push_str("&#10;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use expect_test::Expect;
+    use starpls_common::FileId;
+    use starpls_common::FileRange;
+    use starpls_syntax::TextRange;
+    use starpls_syntax::TextSize;
+
+    use super::*;
+
+    /// Returns a record located at `load` on the second line of a file whose path and message
+    /// need escaping in most formats, followed by a record without a location.
+    fn records() -> Vec<Record> {
+        let contents = "x = 1\nload(\"//:a.bzl\", \"a\")\n";
+        let diagnostic = Diagnostic {
+            message: "100% unused,\r\nreally: \"a\" <b> & 'c'".to_string(),
+            severity: Severity::Warning,
+            range: FileRange {
+                file_id: FileId(0),
+                range: TextRange::new(TextSize::new(6), TextSize::new(10)),
+            },
+            tags: None,
+            code: DiagnosticCode::UnusedLoad,
+            related_information: None,
+        };
+        vec![
+            Record::from_diagnostic(
+                Path::new("pkg/a,b:c.bzl"),
+                &LineIndex::new(contents),
+                &diagnostic,
+            ),
+            Record::for_path(
+                Path::new("pkg/<d>&e.bzl"),
+                Severity::Error,
+                "Failed to read file".to_string(),
+            ),
+        ]
+    }
+
+    fn check_output(format: OutputFormat, expect: Expect) {
+        let records = records();
+        let mut summary = Summary::default();
+        for record in &records {
+            summary.add(&record.severity);
+        }
+        let mut out = Vec::new();
+        write_records(&mut out, format, &records, &summary).unwrap();
+        expect.assert_eq(&String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn test_json() {
+        check_output(
+            OutputFormat::Json,
+            expect![[r#"
+            {
+              "diagnostics": [
+                {
+                  "code": "unused-load",
+                  "column": 1,
+                  "end_column": 5,
+                  "end_line": 2,
+                  "line": 2,
+                  "message": "100% unused,\r\nreally: \"a\" <b> & 'c'",
+                  "path": "pkg/a,b:c.bzl",
+                  "severity": "warning"
+                },
+                {
+                  "code": null,
+                  "message": "Failed to read file",
+                  "path": "pkg/<d>&e.bzl",
+                  "severity": "error"
+                }
+              ],
+              "summary": {
+                "errors": 1,
+                "infos": 0,
+                "warnings": 1
+              }
+            }
+        "#]],
+        );
+    }
+
+    #[test]
+    fn test_sarif() {
+        let records = records();
+        let mut out = Vec::new();
+        write_sarif(&mut out, &records, &Summary::default()).unwrap();
+        let log: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let run = &log["runs"][0];
+        assert_eq!(log["version"], "2.1.0");
+        assert_eq!(run["tool"]["driver"]["version"], get_version());
+        expect![[r#"
+            [
+              {
+                "level": "warning",
+                "locations": [
+                  {
+                    "physicalLocation": {
+                      "artifactLocation": {
+                        "uri": "pkg/a,b:c.bzl"
+                      },
+                      "region": {
+                        "endColumn": 5,
+                        "endLine": 2,
+                        "startColumn": 1,
+                        "startLine": 2
+                      }
+                    }
+                  }
+                ],
+                "message": {
+                  "text": "100% unused,\r\nreally: \"a\" <b> & 'c'"
+                },
+                "ruleId": "unused-load"
+              },
+              {
+                "level": "error",
+                "locations": [
+                  {
+                    "physicalLocation": {
+                      "artifactLocation": {
+                        "uri": "pkg/<d>&e.bzl"
+                      }
+                    }
+                  }
+                ],
+                "message": {
+                  "text": "Failed to read file"
+                }
+              }
+            ]"#]]
+        .assert_eq(&serde_json::to_string_pretty(&run["results"]).unwrap());
+    }
+
+    #[test]
+    fn test_github() {
+        check_output(
+            OutputFormat::Github,
+            expect![[r#"
+            ::warning file=pkg/a%2Cb%3Ac.bzl,line=2,col=1,endLine=2,endColumn=5,title=unused-load::100%25 unused,%0D%0Areally: "a" <b> & 'c'
+            ::error file=pkg/<d>&e.bzl::Failed to read file
+        "#]],
+        );
+    }
+
+    #[test]
+    fn test_checkstyle() {
+        check_output(
+            OutputFormat::Checkstyle,
+            expect![[r#"
+            <?xml version="1.0" encoding="UTF-8"?>
+            <checkstyle version="4.3">
+              <file name="pkg/a,b:c.bzl">
+                <error line="2" column="1" severity="warning" message="100% unused,&#13;&#10;really: &quot;a&quot; &lt;b&gt; &amp; &apos;c&apos;" source="starpls.unused-load"/>
+              </file>
+              <file name="pkg/&lt;d&gt;&amp;e.bzl">
+                <error line="0" column="0" severity="error" message="Failed to read file" source="starpls"/>
+              </file>
+            </checkstyle>
+        "#]],
+        );
+    }
+}
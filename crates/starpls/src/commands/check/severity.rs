@@ -0,0 +1,95 @@
+use clap::Args;
+use clap::ValueEnum;
+use starpls_common::Diagnostic;
+use starpls_common::DiagnosticCode;
+use starpls_common::Severity;
+
+/// The lowest severity of diagnostics that `starpls check` reports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub(crate) enum MinSeverity {
+    Hint,
+    #[default]
+    Info,
+    Warning,
+    Error,
+}
+
+impl MinSeverity {
+    fn allows(self, severity: &Severity) -> bool {
+        let severity = match severity {
+            Severity::Hint => MinSeverity::Hint,
+            Severity::Info => MinSeverity::Info,
+            Severity::Warning => MinSeverity::Warning,
+            Severity::Error => MinSeverity::Error,
+        };
+        severity >= self
+    }
+}
+
+/// Flags for adjusting the severity of reported diagnostics, and for deciding when a check fails.
+///
+/// Per-code flags are applied first, on top of any overrides from `starpls.toml`. If a code is
+/// passed to more than one of them, `--allow` takes precedence over `--error`, which takes
+/// precedence over `--warn`.
+#[derive(Args, Default)]
+pub(crate) struct SeverityOptions {
+    /// Report warnings as errors.
+    #[clap(long = "warnings_as_errors", default_value_t = false)]
+    pub(crate) warnings_as_errors: bool,
+
+    /// Don't report diagnostics below this severity. Hints, e.g. for unreachable code, aren't
+    /// reported by default.
+    #[clap(long = "min_severity", value_enum, default_value_t = MinSeverity::Info)]
+    pub(crate) min_severity: MinSeverity,
+
+    /// Report diagnostics with the given code as errors, e.g. `--error=unused-load`. This also
+    /// enables lints that are off by default.
+    #[clap(long = "error", value_name = "CODE")]
+    pub(crate) error: Vec<DiagnosticCode>,
+
+    /// Report diagnostics with the given code as warnings. This also enables lints that are off
+    /// by default.
+    #[clap(long = "warn", value_name = "CODE")]
+    pub(crate) warn: Vec<DiagnosticCode>,
+
+    /// Don't report diagnostics with the given code.
+    #[clap(long = "allow", value_name = "CODE")]
+    pub(crate) allow: Vec<DiagnosticCode>,
+
+    /// Fail if more than this many warnings are reported.
+    #[clap(long = "max_warnings", value_name = "N")]
+    pub(crate) max_warnings: Option<usize>,
+}
+
+impl SeverityOptions {
+    /// Returns the diagnostic with its severity adjusted, or `None` if it shouldn't be reported.
+    pub(crate) fn apply(&self, mut diagnostic: Diagnostic) -> Option<Diagnostic> {
+        if self.allow.contains(&diagnostic.code) {
+            return None;
+        }
+        if self.error.contains(&diagnostic.code) {
+            diagnostic.severity = Severity::Error;
+        } else if self.warn.contains(&diagnostic.code) {
+            diagnostic.severity = Severity::Warning;
+        }
+        diagnostic.severity = self.adjust_severity(diagnostic.severity)?;
+        Some(diagnostic)
+    }
+
+    /// Applies `--warnings_as_errors` and `--min_severity` to a severity. Returns `None` if
+    /// problems with the resulting severity shouldn't be reported.
+    pub(crate) fn adjust_severity(&self, severity: Severity) -> Option<Severity> {
+        let severity = match severity {
+            Severity::Warning if self.warnings_as_errors => Severity::Error,
+            severity => severity,
+        };
+        self.min_severity.allows(&severity).then_some(severity)
+    }
+
+    /// Returns whether the given number of warnings exceeds `--max_warnings`.
+    pub(crate) fn exceeds_max_warnings(&self, num_warnings: usize) -> bool {
+        self.max_warnings
+            .map(|max_warnings| num_warnings > max_warnings)
+            .unwrap_or(false)
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use annotate_snippets::Level;
+use annotate_snippets::Renderer;
+use notify::Event;
+use notify::EventKind;
+use notify::RecommendedWatcher;
+use notify::RecursiveMode;
+use notify::Watcher;
+use starpls_common::Diagnostic;
+use starpls_common::FileId;
+use starpls_ide::Change;
+
+use crate::commands::check::diagnostic_to_message;
+use crate::commands::check::output::Summary;
+use crate::commands::check::Checker;
+
+/// How often the checked paths are polled for changes if they can't be watched.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long to wait for more file system events after one arrives, so that e.g. saving several
+/// files at once only triggers a single re-check.
+const EVENT_BATCH_DELAY: Duration = Duration::from_millis(50);
+
+impl Checker {
+    /// Checks all files, then keeps watching the checked paths for changes and re-checks whenever
+    /// there are any. After the first check, only files whose diagnostics changed are reported.
+    ///
+    /// Files can change or disappear at any point while they're being watched, so errors after the
+    /// first check are reported as warnings instead of stopping the watch. A warning is printed
+    /// once for as long as the same error keeps occurring.
+    pub(super) fn watch(mut self) -> anyhow::Result<()> {
+        let mut modified_times = HashMap::new();
+        for file_id in self.files.keys() {
+            modified_times.insert(*file_id, self.modified_time(*file_id));
+        }
+        let mut reported = HashMap::new();
+        self.recheck(&mut reported)?;
+
+        let mut waiter = ChangeWaiter::new();
+        let mut last_warnings = HashSet::new();
+        loop {
+            let mut warnings = Vec::new();
+            waiter.watch_dirs(&self.walker.dirs, &mut warnings);
+            waiter.wait(&mut warnings);
+
+            let mut change = Change::default();
+            let mut has_changes = false;
+            let mut file_ids = self.files.keys().copied().collect::<Vec<_>>();
+            file_ids.sort();
+            for file_id in file_ids {
+                let modified_time = self.modified_time(file_id);
+                if modified_times.get(&file_id) == Some(&modified_time) {
+                    continue;
+                }
+                modified_times.insert(file_id, modified_time);
+
+                let path = self.interner.lookup_by_file_id(file_id);
+                match fs::read_to_string(&path) {
+                    Ok(contents) => {
+                        let metadata = self.files.get_mut(&file_id).expect("file metadata");
+                        if metadata.contents != contents {
+                            metadata.contents = contents.clone();
+                            change.update_file(file_id, contents);
+                            has_changes = true;
+                        }
+                    }
+                    Err(_) if !path.exists() => {
+                        // There's no way to remove a file from the analysis, so clear its contents
+                        // instead. Files that load it will then report errors for the missing
+                        // symbols.
+                        let metadata = self.files.remove(&file_id).expect("file metadata");
+                        modified_times.remove(&file_id);
+                        reported.remove(&file_id);
+                        change.update_file(file_id, String::new());
+                        has_changes = true;
+                        anstream::println!(
+                            "{}\n",
+                            Renderer::styled().render(
+                                Level::Info.title(&format!("{:?} was removed", metadata.path))
+                            )
+                        );
+                    }
+                    Err(err) => {
+                        // Try again after the next change.
+                        modified_times.remove(&file_id);
+                        warnings.push(format!("failed to read {:?}: {}", path, err));
+                    }
+                }
+            }
+
+            // Pick up files that were created since the last check.
+            match self.walker.walk() {
+                Ok(paths) => {
+                    for (path, is_explicit) in paths {
+                        match self.load_file(&mut change, &path, is_explicit) {
+                            Ok(Some(file_id)) => {
+                                modified_times.insert(file_id, self.modified_time(file_id));
+                                has_changes = true;
+                            }
+                            Ok(None) => {}
+                            Err(err) => {
+                                warnings.push(format!("failed to load {:?}: {}", path, err))
+                            }
+                        }
+                    }
+                }
+                Err(err) => warnings.push(format!("failed to find files to check: {}", err)),
+            }
+
+            if has_changes {
+                // Created or removed BUILD files can change which packages labels resolve to.
+                self.analysis.apply_change(change);
+                self.analysis.refresh_label_resolutions();
+                if let Err(err) = self.recheck(&mut reported) {
+                    warnings.push(format!("failed to check files: {}", err));
+                }
+            }
+
+            for warning in &warnings {
+                if !last_warnings.contains(warning) {
+                    anstream::eprintln!(
+                        "{}\n",
+                        Renderer::styled().render(Level::Warning.title(warning))
+                    );
+                }
+            }
+            last_warnings = warnings.into_iter().collect();
+        }
+    }
+
+    /// Re-checks all files, reporting the diagnostics for each file whose diagnostics differ from
+    /// the ones reported previously.
+    fn recheck(&mut self, reported: &mut HashMap<FileId, Vec<Diagnostic>>) -> anyhow::Result<()> {
+        let mut file_ids = self.files.keys().copied().collect::<Vec<_>>();
+        file_ids.sort();
+
+        let mut summary = Summary::default();
+        let mut num_changed_files = 0;
+        self.for_each_file_diagnostics(&file_ids, |checker, file_id, diagnostics| {
+            let diagnostics = checker.filter_diagnostics(file_id, diagnostics);
+            for diagnostic in &diagnostics {
+                summary.add(&diagnostic.severity);
+            }
+            if reported
+                .get(&file_id)
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+                == diagnostics
+            {
+                reported.insert(file_id, diagnostics);
+                return Ok(());
+            }
+
+            num_changed_files += 1;
+            let metadata = &checker.files[&file_id];
+            if diagnostics.is_empty() {
+                anstream::println!(
+                    "{}\n",
+                    Renderer::styled().render(
+                        Level::Info.title(&format!("{:?} has no more problems", metadata.path))
+                    )
+                );
+            }
+            for diagnostic in &diagnostics {
+                anstream::println!(
+                    "{}\n",
+                    Renderer::styled().render(diagnostic_to_message(diagnostic, metadata))
+                );
+            }
+            reported.insert(file_id, diagnostics);
+            Ok(())
+        })?;
+
+        let level = if summary.errors > 0 {
+            Level::Error
+        } else if summary.warnings > 0 {
+            Level::Warning
+        } else {
+            Level::Info
+        };
+        anstream::println!(
+            "{}\n",
+            Renderer::styled().render(level.title(&format!(
+                "{} errors and {} warnings in {} files ({} changed), watching for changes...",
+                summary.errors,
+                summary.warnings,
+                file_ids.len(),
+                num_changed_files
+            )))
+        );
+        Ok(())
+    }
+
+    fn modified_time(&self, file_id: FileId) -> Option<SystemTime> {
+        fs::metadata(self.interner.lookup_by_file_id(file_id))
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+}
+
+/// Blocks until the checked paths might have changed. File system notifications are used if
+/// possible, with polling as a fallback.
+enum ChangeWaiter {
+    Watch {
+        watcher: RecommendedWatcher,
+        watched_dirs: HashSet<PathBuf>,
+        events: Receiver<notify::Result<Event>>,
+    },
+    Poll,
+}
+
+impl ChangeWaiter {
+    fn new() -> Self {
+        let (sender, events) = mpsc::channel();
+        match notify::recommended_watcher(sender) {
+            Ok(watcher) => Self::Watch {
+                watcher,
+                watched_dirs: HashSet::new(),
+                events,
+            },
+            Err(err) => {
+                anstream::eprintln!(
+                    "{}\n",
+                    Renderer::styled().render(Level::Warning.title(&format!(
+                        "failed to watch for changes, polling instead: {}",
+                        err
+                    )))
+                );
+                Self::Poll
+            }
+        }
+    }
+
+    /// Updates the watched directories to the given ones. Each directory is watched on its own,
+    /// since recursive watches would follow symlinks, e.g. into Bazel's output base.
+    fn watch_dirs(&mut self, dirs: &[PathBuf], warnings: &mut Vec<String>) {
+        let Self::Watch {
+            watcher,
+            watched_dirs,
+            ..
+        } = self
+        else {
+            return;
+        };
+
+        let dirs = dirs.iter().cloned().collect::<HashSet<_>>();
+        for dir in watched_dirs.difference(&dirs) {
+            // Directories that were removed aren't watched anymore anyway.
+            let _ = watcher.unwatch(dir);
+        }
+        watched_dirs.retain(|dir| dirs.contains(dir));
+        for dir in dirs {
+            if watched_dirs.contains(&dir) {
+                continue;
+            }
+            match watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                Ok(()) => {
+                    watched_dirs.insert(dir);
+                }
+                // The directory was removed after the walk, which the next walk will notice.
+                Err(_) if !dir.exists() => {}
+                Err(err) => {
+                    warnings.push(format!(
+                        "failed to watch {:?} for changes, polling instead: {}",
+                        dir, err
+                    ));
+                    *self = Self::Poll;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Waits for the next change. Errors from the watcher mean that changes might have been
+    /// missed, so they're treated as changes too.
+    fn wait(&mut self, warnings: &mut Vec<String>) {
+        let Self::Watch { events, .. } = self else {
+            thread::sleep(POLL_INTERVAL);
+            return;
+        };
+
+        let mut has_changes = false;
+        loop {
+            let res = if has_changes {
+                match events.recv_timeout(EVENT_BATCH_DELAY) {
+                    Ok(res) => res,
+                    Err(RecvTimeoutError::Timeout) => return,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            } else {
+                match events.recv() {
+                    Ok(res) => res,
+                    Err(_) => break,
+                }
+            };
+            match res {
+                // Reading files doesn't change them, and this includes the reads for checking.
+                Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+                Ok(_) => has_changes = true,
+                Err(err) => {
+                    warnings.push(format!("failed to watch for changes: {}", err));
+                    has_changes = true;
+                }
+            }
+        }
+
+        // The watcher stopped, so fall back to polling from now on.
+        warnings.push("stopped watching for changes, polling instead".to_string());
+        *self = Self::Poll;
+    }
+}
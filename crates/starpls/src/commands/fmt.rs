@@ -0,0 +1,170 @@
+use std::fs;
+use std::io::Write;
+use std::io::{self};
+use std::path::Path;
+use std::path::PathBuf;
+
+use annotate_snippets::Level;
+use annotate_snippets::Renderer;
+use anyhow::anyhow;
+use clap::Args;
+use starpls_common::Dialect;
+use walkdir::WalkDir;
+
+use crate::commands::is_hidden;
+use crate::diff::unified_diff;
+use crate::document::{self};
+use crate::ignore::IgnoreFilter;
+use crate::utils::write_file_atomically;
+use crate::workspace_config::WorkspaceConfig;
+
+#[derive(Args, Default)]
+pub(crate) struct FmtCommand {
+    /// Paths to format. Directories are searched recursively.
+    pub(crate) paths: Vec<String>,
+
+    /// Specify gitignore-style patterns of files/directories to ignore, e.g. "third_party/**".
+    #[clap(long = "ignore_pattern")]
+    pub(crate) ignore_patterns: Vec<String>,
+
+    /// Additional extensions of plain Starlark files to format, besides ".star" and ".sky".
+    #[clap(long = "ext")]
+    pub(crate) extensions: Vec<String>,
+
+    /// Don't write any files. Instead, list the files that aren't formatted and exit with a
+    /// non-zero status if there are any.
+    #[clap(long = "check", default_value_t = false)]
+    pub(crate) check: bool,
+
+    /// Print the changes that formatting would make as a unified diff, without writing them.
+    #[clap(long = "diff", default_value_t = false, conflicts_with = "check")]
+    pub(crate) diff: bool,
+}
+
+impl FmtCommand {
+    pub(crate) fn run(self) -> anyhow::Result<()> {
+        let mut files = Vec::new();
+        for path in &self.paths {
+            let canonical_path = PathBuf::from(path).canonicalize()?;
+            let workspace = match starpls_bazel::resolve_workspace(&canonical_path)? {
+                Some((workspace, _)) => workspace,
+                None if canonical_path.is_dir() => canonical_path.clone(),
+                None => canonical_path
+                    .parent()
+                    .map(Path::to_path_buf)
+                    .unwrap_or_default(),
+            };
+            let workspace_config = WorkspaceConfig::load(&workspace)?;
+            let mut ignore_filter = IgnoreFilter::new(
+                &workspace,
+                workspace_config
+                    .ignore
+                    .with_patterns(self.ignore_patterns.iter().map(String::as_str))?,
+            );
+
+            // Strip off the leading "." from each of the specified extensions.
+            let extensions = self
+                .extensions
+                .iter()
+                .chain(&workspace_config.extensions)
+                .map(|ext| match ext.strip_prefix('.') {
+                    Some(ext) => ext,
+                    None => ext,
+                })
+                .chain(["star", "sky"])
+                .collect::<Vec<_>>();
+
+            for entry in WalkDir::new(path).into_iter().filter_entry(|e| {
+                e.depth() == 0
+                    || (!is_hidden(e)
+                        && !ignore_filter.is_ignored(
+                            &canonical_path.join(e.path().strip_prefix(path).unwrap_or(e.path())),
+                            e.file_type().is_dir(),
+                        ))
+            }) {
+                let entry = entry?;
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let Some((dialect, api_context)) =
+                    document::dialect_and_api_context_for_workspace_path(
+                        &workspace,
+                        entry.path().canonicalize()?,
+                    )
+                else {
+                    continue;
+                };
+
+                // Only format plain Starlark files that match one of the specified extensions.
+                if dialect == Dialect::Standard
+                    && !entry
+                        .path()
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .map(|ext| extensions.contains(&ext))
+                        .unwrap_or(false)
+                {
+                    continue;
+                }
+                files.push((entry.into_path(), dialect, api_context));
+            }
+        }
+
+        let mut stdout = io::stdout().lock();
+        let mut num_changed = 0;
+        let mut num_errors = 0;
+        for (path, dialect, api_context) in files {
+            let contents = fs::read_to_string(&path)?;
+            let formatted = match starpls_ide::format(&contents, dialect, api_context) {
+                Ok(formatted) => formatted,
+                Err(err) => {
+                    anstream::eprintln!(
+                        "{}\n",
+                        Renderer::styled().render(
+                            Level::Error.title(&format!("failed to format {:?}: {}", path, err))
+                        )
+                    );
+                    num_errors += 1;
+                    continue;
+                }
+            };
+            if formatted == contents {
+                continue;
+            }
+
+            num_changed += 1;
+            if self.check {
+                writeln!(stdout, "{}", path.display())?;
+            } else if self.diff {
+                let path = path.to_string_lossy().replace('\\', "/");
+                write!(
+                    stdout,
+                    "{}",
+                    unified_diff(
+                        &contents,
+                        &formatted,
+                        &format!("a/{}", path),
+                        &format!("b/{}", path),
+                    )
+                )?;
+            } else {
+                write_file_atomically(&path, formatted.as_bytes())
+                    .map_err(|err| anyhow!("failed to write {:?}: {}", path, err))?;
+            }
+        }
+        stdout.flush()?;
+
+        if !self.check && !self.diff && num_changed > 0 {
+            anstream::eprintln!(
+                "{}\n",
+                Renderer::styled()
+                    .render(Level::Info.title(&format!("formatted {} files", num_changed)))
+            );
+        }
+
+        if num_errors > 0 || ((self.check || self.diff) && num_changed > 0) {
+            std::process::exit(1);
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use clap::Args;
 use log::info;
 use lsp_server::Connection;
+use lsp_types::CodeActionProviderCapability;
 use lsp_types::CompletionOptions;
 use lsp_types::DeclarationCapability;
 use lsp_types::HoverProviderCapability;
@@ -18,7 +19,7 @@ use crate::make_trigger_characters;
 const COMPLETION_TRIGGER_CHARACTERS: &[char] = &['.', '"', '\'', '/', ':', '@'];
 const SIGNATURE_HELP_TRIGGER_CHARACTERS: &[char] = &['(', ',', ')'];
 
-#[derive(Args, Default)]
+#[derive(Args, Clone, Default)]
 pub(crate) struct ServerCommand {
     /// Path to the Bazel binary.
     #[clap(long = "bazel_path")]
@@ -56,12 +57,14 @@ impl ServerCommand {
         // Initialize the connection with server capabilities. For now, this consists
         // only of `TextDocumentSyncKind.Full`.
         let server_capabilities = serde_json::to_value(ServerCapabilities {
+            code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
             completion_provider: Some(CompletionOptions {
                 trigger_characters: Some(make_trigger_characters(COMPLETION_TRIGGER_CHARACTERS)),
                 ..Default::default()
             }),
             declaration_provider: Some(DeclarationCapability::Simple(true)),
             definition_provider: Some(OneOf::Left(true)),
+            document_formatting_provider: Some(OneOf::Left(true)),
             document_symbol_provider: Some(OneOf::Left(true)),
             hover_provider: Some(HoverProviderCapability::Simple(true)),
             references_provider: Some(OneOf::Left(true)),
@@ -1,11 +1,13 @@
 use lsp_types::ClientCapabilities;
 
 use crate::commands::server::ServerCommand;
+use crate::workspace_config::WorkspaceConfig;
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub(crate) struct ServerConfig {
     pub(crate) args: ServerCommand,
     pub(crate) caps: ClientCapabilities,
+    pub(crate) workspace_config: WorkspaceConfig,
 }
 
 macro_rules! try_or_default {
@@ -19,6 +21,16 @@ impl ServerConfig {
         try_or_default!(self.caps.text_document.as_ref()?.definition?.link_support)
     }
 
+    pub(crate) fn has_did_change_watched_files_dynamic_registration(&self) -> bool {
+        try_or_default!(
+            self.caps
+                .workspace
+                .as_ref()?
+                .did_change_watched_files?
+                .dynamic_registration
+        )
+    }
+
     pub(crate) fn has_insert_replace_support(&self) -> bool {
         try_or_default!(
             self.caps
@@ -22,17 +22,43 @@ pub(crate) fn path_buf_from_url(url: &lsp_types::Url) -> anyhow::Result<PathBuf>
 }
 
 pub(crate) fn lsp_diagnostic_from_native(
+    snapshot: &ServerSnapshot,
     diagnostic: Diagnostic,
     line_index: &LineIndex,
 ) -> Option<lsp_types::Diagnostic> {
     Some(lsp_types::Diagnostic {
         range: lsp_range_from_text_range(diagnostic.range.range, line_index)?,
         severity: Some(lsp_severity_from_native(diagnostic.severity)),
-        code: None,
+        code: Some(lsp_types::NumberOrString::String(
+            diagnostic.code.as_str().to_string(),
+        )),
         code_description: None,
         source: Some("starpls".to_string()),
         message: diagnostic.message,
-        related_information: None,
+        related_information: diagnostic.related_information.map(|related_information| {
+            related_information
+                .into_iter()
+                .filter_map(|related| {
+                    let line_index = snapshot
+                        .analysis_snapshot
+                        .line_index(related.range.file_id)
+                        .ok()??;
+                    Some(lsp_types::DiagnosticRelatedInformation {
+                        location: lsp_types::Location {
+                            uri: lsp_types::Url::from_file_path(
+                                snapshot
+                                    .document_manager
+                                    .read()
+                                    .lookup_by_file_id(related.range.file_id),
+                            )
+                            .ok()?,
+                            range: lsp_range_from_text_range(related.range.range, line_index)?,
+                        },
+                        message: related.message,
+                    })
+                })
+                .collect()
+        }),
         tags: diagnostic.tags.map(|tags| {
             tags.into_iter()
                 .map(|tag| match tag {
@@ -87,11 +113,28 @@ pub(crate) fn text_size_from_lsp_position(
     Ok(line_index.offset(line_col))
 }
 
+pub(crate) fn text_range_from_lsp_range(
+    snapshot: &ServerSnapshot,
+    file_id: FileId,
+    range: lsp_types::Range,
+) -> anyhow::Result<Option<TextRange>> {
+    let start = match text_size_from_lsp_position(snapshot, file_id, range.start)? {
+        Some(start) => start,
+        None => return Ok(None),
+    };
+    let end = match text_size_from_lsp_position(snapshot, file_id, range.end)? {
+        Some(end) => end,
+        None => return Ok(None),
+    };
+    Ok((start <= end).then(|| TextRange::new(start, end)))
+}
+
 fn lsp_severity_from_native(severity: Severity) -> lsp_types::DiagnosticSeverity {
     match severity {
         Severity::Error => lsp_types::DiagnosticSeverity::ERROR,
         Severity::Warning => lsp_types::DiagnosticSeverity::WARNING,
         Severity::Info => lsp_types::DiagnosticSeverity::INFORMATION,
+        Severity::Hint => lsp_types::DiagnosticSeverity::HINT,
     }
 }
 
@@ -0,0 +1,343 @@
+use std::fmt::Write;
+
+/// The number of unchanged lines shown before and after each change.
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DiffOp {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Renders a unified diff between `old` and `new`. Returns an empty string if the two are equal.
+pub(crate) fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    let old_lines = old.split_inclusive('\n').collect::<Vec<_>>();
+    let new_lines = new.split_inclusive('\n').collect::<Vec<_>>();
+    let ops = diff_lines(&old_lines, &new_lines);
+    if ops.iter().all(|op| matches!(op, DiffOp::Equal(..))) {
+        return String::new();
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "--- {}", old_label);
+    let _ = writeln!(out, "+++ {}", new_label);
+
+    let mut start = 0;
+    while let Some(first_change) = ops[start..]
+        .iter()
+        .position(|op| !matches!(op, DiffOp::Equal(..)))
+        .map(|pos| start + pos)
+    {
+        // Extend the hunk until there's a run of unchanged lines long enough to separate it from
+        // the next change.
+        let hunk_start = first_change.saturating_sub(CONTEXT_LINES);
+        let mut hunk_end = first_change;
+        let mut equal_run = 0;
+        for (index, op) in ops.iter().enumerate().skip(first_change) {
+            if matches!(op, DiffOp::Equal(..)) {
+                equal_run += 1;
+                if equal_run > 2 * CONTEXT_LINES {
+                    break;
+                }
+            } else {
+                equal_run = 0;
+                hunk_end = index;
+            }
+        }
+        let hunk_end = (hunk_end + CONTEXT_LINES + 1).min(ops.len());
+        write_hunk(&mut out, &ops[hunk_start..hunk_end], &old_lines, &new_lines);
+        start = hunk_end;
+    }
+
+    out
+}
+
+fn write_hunk(out: &mut String, ops: &[DiffOp], old_lines: &[&str], new_lines: &[&str]) {
+    let mut old_start = None;
+    let mut new_start = None;
+    let mut old_count = 0;
+    let mut new_count = 0;
+    let mut body = String::new();
+    for op in ops {
+        let (prefix, line) = match *op {
+            DiffOp::Equal(old, new) => {
+                old_start.get_or_insert(old);
+                new_start.get_or_insert(new);
+                old_count += 1;
+                new_count += 1;
+                (' ', old_lines[old])
+            }
+            DiffOp::Delete(old) => {
+                old_start.get_or_insert(old);
+                old_count += 1;
+                ('-', old_lines[old])
+            }
+            DiffOp::Insert(new) => {
+                new_start.get_or_insert(new);
+                new_count += 1;
+                ('+', new_lines[new])
+            }
+        };
+        body.push(prefix);
+        body.push_str(line);
+        if !line.ends_with('\n') {
+            body.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+
+    // Hunks always include surrounding context, so a side can only be missing from a hunk if
+    // that version of the file is empty.
+    let format_range = |start: Option<usize>, count: usize| match start {
+        Some(start) => format!("{},{}", start + 1, count),
+        None => "0,0".to_string(),
+    };
+    let _ = writeln!(
+        out,
+        "@@ -{} +{} @@",
+        format_range(old_start, old_count),
+        format_range(new_start, new_count)
+    );
+    out.push_str(&body);
+}
+
+/// Computes a shortest edit script between two sequences of lines using Myers' algorithm.
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<DiffOp> {
+    // Strip the common prefix and suffix, which are usually most of the file.
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max = n + m;
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * max as usize + 3];
+
+    // For each edit distance `d`, record the furthest reaching x-coordinates on diagonals
+    // `-d..=d` from the previous round, which is needed to recover the edit script.
+    let mut trace = Vec::new();
+    'outer: for d in 0..=max {
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let index = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+                v[index + 1]
+            } else {
+                v[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index] = x;
+            if x >= n && y >= m {
+                break 'outer;
+            }
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let get = |k: isize| v[(k + d) as usize];
+        let prev_k = if k == -d || (k != d && get(k - 1) < get(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = if d == 0 { 0 } else { get(prev_k) };
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            ops.push(DiffOp::Equal(x as usize + prefix, y as usize + prefix));
+        }
+        if d > 0 {
+            if x == prev_x {
+                ops.push(DiffOp::Insert(prev_y as usize + prefix));
+            } else {
+                ops.push(DiffOp::Delete(prev_x as usize + prefix));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+
+    (0..prefix)
+        .map(|index| DiffOp::Equal(index, index))
+        .chain(ops.into_iter().rev())
+        .chain(
+            (0..suffix)
+                .map(|index| DiffOp::Equal(old.len() - suffix + index, new.len() - suffix + index)),
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equal() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "old", "new"), "");
+    }
+
+    #[test]
+    fn test_single_change() {
+        assert_eq!(
+            unified_diff(
+                "a\nb\nc\nd\ne\nf\ng\nh\n",
+                "a\nb\nc\nd\nE\nf\ng\nh\n",
+                "old",
+                "new"
+            ),
+            "--- old
++++ new
+@@ -2,7 +2,7 @@
+ b
+ c
+ d
+-e
++E
+ f
+ g
+ h
+"
+        );
+    }
+
+    #[test]
+    fn test_separate_hunks() {
+        let old = (0..20).map(|i| format!("{}\n", i)).collect::<String>();
+        let new = old
+            .lines()
+            .map(|line| match line {
+                "2" => "two\n".to_string(),
+                "17" => "seventeen\n".to_string(),
+                _ => format!("{}\n", line),
+            })
+            .collect::<String>();
+        assert_eq!(
+            unified_diff(&old, &new, "old", "new"),
+            "--- old
++++ new
+@@ -1,6 +1,6 @@
+ 0
+ 1
+-2
++two
+ 3
+ 4
+ 5
+@@ -15,6 +15,6 @@
+ 14
+ 15
+ 16
+-17
++seventeen
+ 18
+ 19
+"
+        );
+    }
+
+    #[test]
+    fn test_empty_old() {
+        assert_eq!(
+            unified_diff("", "a\nb\n", "old", "new"),
+            "--- old
++++ new
+@@ -0,0 +1,2 @@
++a
++b
+"
+        );
+    }
+
+    #[test]
+    fn test_missing_newline_at_end() {
+        assert_eq!(
+            unified_diff("a\nb", "a\nb\n", "old", "new"),
+            "--- old
++++ new
+@@ -1,2 +1,2 @@
+ a
+-b
+\\ No newline at end of file
++b
+"
+        );
+    }
+
+    /// Checks that the edit script reproduces both inputs and is as short as possible, by
+    /// comparing against the length of the longest common subsequence.
+    #[test]
+    fn test_diff_lines_is_minimal() {
+        fn lcs_len(a: &[&str], b: &[&str]) -> usize {
+            let mut table = vec![vec![0; b.len() + 1]; a.len() + 1];
+            for i in (0..a.len()).rev() {
+                for j in (0..b.len()).rev() {
+                    table[i][j] = if a[i] == b[j] {
+                        table[i + 1][j + 1] + 1
+                    } else {
+                        table[i + 1][j].max(table[i][j + 1])
+                    };
+                }
+            }
+            table[0][0]
+        }
+
+        // A small linear congruential generator keeps the inputs deterministic.
+        let mut state = 12345u32;
+        let mut next = move || {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            (state >> 16) as usize
+        };
+        for _ in 0..500 {
+            let mut lines = || {
+                (0..next() % 10)
+                    .map(|_| ["a", "b", "c"][next() % 3])
+                    .collect::<Vec<_>>()
+            };
+            let old = lines();
+            let new = lines();
+
+            let ops = diff_lines(&old, &new);
+            let mut old_seen = Vec::new();
+            let mut new_seen = Vec::new();
+            for op in &ops {
+                match *op {
+                    DiffOp::Equal(x, y) => {
+                        assert_eq!(old[x], new[y]);
+                        old_seen.push(x);
+                        new_seen.push(y);
+                    }
+                    DiffOp::Delete(x) => old_seen.push(x),
+                    DiffOp::Insert(y) => new_seen.push(y),
+                }
+            }
+            assert_eq!(old_seen, (0..old.len()).collect::<Vec<_>>());
+            assert_eq!(new_seen, (0..new.len()).collect::<Vec<_>>());
+
+            let num_equal = ops
+                .iter()
+                .filter(|op| matches!(op, DiffOp::Equal(..)))
+                .count();
+            assert_eq!(num_equal, lcs_len(&old, &new), "{:?} {:?}", old, new);
+        }
+    }
+}
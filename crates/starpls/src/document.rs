@@ -24,9 +24,11 @@ use starpls_bazel::{self};
 use starpls_common::Dialect;
 use starpls_common::FileId;
 use starpls_common::FileInfo;
+use starpls_common::FilePackage;
 use starpls_common::LoadItemCandidate;
 use starpls_common::LoadItemCandidateKind;
 use starpls_common::ResolvedPath;
+use starpls_common::UnfetchedRepositoryError;
 use starpls_ide::FileLoader;
 use starpls_ide::LoadFileResult;
 
@@ -163,6 +165,18 @@ impl DocumentManager {
     pub(crate) fn lookup_by_path_buf(&self, path: &PathBuf) -> Option<FileId> {
         self.path_interner.lookup_by_path_buf(path)
     }
+
+    /// Returns the documents that are currently open in the editor.
+    pub(crate) fn open_documents(&self) -> impl Iterator<Item = FileId> + '_ {
+        self.documents
+            .iter()
+            .filter(|(_, document)| matches!(document.source, DocumentSource::Editor(_)))
+            .map(|(file_id, _)| *file_id)
+    }
+
+    pub(crate) fn workspace(&self) -> &Path {
+        &self.workspace
+    }
 }
 
 #[derive(Default, Debug)]
@@ -201,6 +215,7 @@ pub(crate) struct DefaultFileLoader {
     cached_load_results: DashMap<String, PathBuf>,
     fetch_repo_sender: Sender<Task>,
     bzlmod_enabled: bool,
+    standalone: bool,
 }
 
 impl DefaultFileLoader {
@@ -222,9 +237,17 @@ impl DefaultFileLoader {
             cached_load_results: Default::default(),
             fetch_repo_sender,
             bzlmod_enabled,
+            standalone: false,
         }
     }
 
+    /// Resolves labels purely from the filesystem, without invoking Bazel. Loads from external
+    /// repositories only succeed if the repository already exists under the external output base.
+    pub(crate) fn with_standalone_mode(mut self) -> Self {
+        self.standalone = true;
+        self
+    }
+
     fn make_cache_key(&self, repo_kind: &RepoKind, path: &str, from: FileId) -> String {
         format!("{:?}-{:?}-{:?}", repo_kind, path, from.0)
     }
@@ -258,7 +281,7 @@ impl DefaultFileLoader {
         let repo_kind = label.kind();
         let mut canonical_repo_res = None;
         let (root, package) = match &repo_kind {
-            RepoKind::Apparent if self.bzlmod_enabled => {
+            RepoKind::Apparent if self.bzlmod_enabled && !self.standalone => {
                 let from_path = self.interner.lookup_by_file_id(from);
                 let from_repo = try_opt!(self.repo_for_path(&from_path));
                 let canonical_repo = self
@@ -294,7 +317,16 @@ impl DefaultFileLoader {
                 if self.workspace_name.as_deref() == Some(label.repo()) || label.repo().is_empty() {
                     (self.workspace.clone(), PathBuf::new())
                 } else {
-                    (self.external_output_base.join(label.repo()), PathBuf::new())
+                    let repo_root = self.external_output_base.join(label.repo());
+                    if self.standalone
+                        && (self.external_output_base.as_os_str().is_empty() || !repo_root.is_dir())
+                    {
+                        bail!(
+                            "Cannot resolve external repository \"@{}\" without Bazel",
+                            label.repo()
+                        );
+                    }
+                    (repo_root, PathBuf::new())
                 }
             }
             RepoKind::Current => {
@@ -336,20 +368,19 @@ impl DefaultFileLoader {
                 let contents = match fs::read_to_string(&path) {
                     Ok(contents) => contents,
                     Err(err) => {
-                        if let Some(canonical_repo) = fetch_repo_on_err {
-                            if !self
-                                .external_output_base
-                                .join(&canonical_repo)
-                                .try_exists()
-                                .ok()
-                                .unwrap_or_default()
-                            {
+                        if let Some(canonical_repo) = fetch_repo_on_err.filter(|_| !self.standalone)
+                        {
+                            if !self.is_repo_fetched(&canonical_repo) {
                                 let _ = self.fetch_repo_sender.send(
                                     Task::FetchExternalRepoRequest(FetchExternalRepoRequest {
                                         file_id: from,
-                                        repo: canonical_repo,
+                                        repo: canonical_repo.clone(),
                                     }),
                                 );
+                                return Err(UnfetchedRepositoryError {
+                                    repo: canonical_repo,
+                                }
+                                .into());
                             }
                         }
                         return Err(err.into());
@@ -363,6 +394,14 @@ impl DefaultFileLoader {
         Ok((file_id, contents))
     }
 
+    fn is_repo_fetched(&self, canonical_repo: &str) -> bool {
+        self.external_output_base
+            .join(canonical_repo)
+            .try_exists()
+            .ok()
+            .unwrap_or_default()
+    }
+
     fn repo_for_path<'a>(&'a self, path: &'a Path) -> Option<&str> {
         match path.strip_prefix(&self.external_output_base) {
             Ok(stripped) => stripped
@@ -412,14 +451,27 @@ impl FileLoader for DefaultFileLoader {
                 return Ok(None);
             }
 
-            let parent = try_opt!(resolved_label.resolved_path.parent());
-            let build_file = try_opt!(fs::read_dir(parent)
+            // Targets can contain slashes, e.g. `//foo:bar/baz.txt`, so strip all of the target's
+            // components to find the package directory.
+            let mut parent = resolved_label.resolved_path.clone();
+            for _ in label.target().split('/') {
+                parent.pop();
+            }
+            let Some(build_file) = fs::read_dir(&parent)
                 .into_iter()
                 .flat_map(|entries| entries.into_iter())
                 .find_map(|entry| match entry.ok()?.file_name().to_str()? {
                     file_name @ ("BUILD" | "BUILD.bazel") => Some(file_name.to_string()),
                     _ => None,
+                })
+            else {
+                return Ok(Some(match resolved_label.canonical_repo {
+                    Some(repo) if !self.standalone && !self.is_repo_fetched(&repo) => {
+                        ResolvedPath::UnfetchedRepository { repo }
+                    }
+                    _ => ResolvedPath::MissingPackage,
                 }));
+            };
             let path = parent.join(build_file);
 
             // If we've already interned this file, then simply return the file id.
@@ -665,6 +717,29 @@ impl FileLoader for DefaultFileLoader {
             None
         }
     }
+
+    fn resolve_package(&self, file_id: FileId) -> Option<FilePackage> {
+        let path = self.interner.lookup_by_file_id(file_id);
+        let repo = self.repo_for_path(&path)?.to_string();
+        let repo_root = if repo.is_empty() {
+            self.workspace.clone()
+        } else {
+            self.external_output_base.join(&repo)
+        };
+
+        let mut dir = path.parent();
+        while let Some(current) = dir {
+            let package = current.strip_prefix(&repo_root).ok()?;
+            if current.join("BUILD.bazel").is_file() || current.join("BUILD").is_file() {
+                return Some(FilePackage {
+                    repo,
+                    package: package.to_string_lossy().to_string(),
+                });
+            }
+            dir = current.parent();
+        }
+        None
+    }
 }
 
 fn read_dir_packages_and_targets(
@@ -14,6 +14,7 @@ use crate::document::DocumentSource;
 use crate::extensions;
 use crate::handlers::notifications;
 use crate::handlers::requests;
+use crate::ignore::relative_path;
 use crate::server::Server;
 use crate::server::ServerSnapshot;
 
@@ -80,8 +81,10 @@ pub fn process_connection(
     let config = ServerConfig {
         args,
         caps: initialize_params.capabilities,
+        workspace_config: Default::default(),
     };
-    let server = Server::new(connection, config)?;
+    let mut server = Server::new(connection, config)?;
+    server.watch_workspace_config();
     server.run()
 }
 
@@ -211,8 +214,10 @@ impl Server {
         RequestDispatcher::new(req, self)
             .on::<extensions::ShowSyntaxTree>(requests::show_syntax_tree)
             .on::<extensions::ShowHir>(requests::show_hir)
+            .on::<lsp_types::request::CodeActionRequest>(requests::code_action)
             .on::<lsp_types::request::Completion>(requests::completion)
             .on::<lsp_types::request::DocumentSymbolRequest>(requests::document_symbols)
+            .on::<lsp_types::request::Formatting>(requests::formatting)
             .on::<lsp_types::request::GotoDefinition>(requests::goto_definition)
             .on::<lsp_types::request::GotoDeclaration>(requests::goto_declaration)
             .on::<lsp_types::request::HoverRequest>(requests::hover)
@@ -228,6 +233,7 @@ impl Server {
                 if lsp_types::notification::DidCloseTextDocument as params => notifications::did_close_text_document(self, params),
                 if lsp_types::notification::DidChangeTextDocument as params => notifications::did_change_text_document(self, params),
                 if lsp_types::notification::DidSaveTextDocument as params => notifications::did_save_text_document(self, params),
+                if lsp_types::notification::DidChangeWatchedFiles as params => notifications::did_change_watched_files(self, params),
                 _ => Ok(())
             }
         }
@@ -374,11 +380,28 @@ fn collect_diagnostics(
     // Get the diagnostics for the current path. If the operation was cancelled, simply continue to the next file.
     let diagnostics = snapshot.analysis_snapshot.diagnostics(file_id).ok()?;
 
+    // Apply the settings from the workspace's `starpls.toml`. Ignored files get no diagnostics.
+    let workspace_config = &snapshot.config.workspace_config;
+    let path = {
+        let document_manager = snapshot.document_manager.read();
+        relative_path(
+            document_manager.workspace(),
+            &document_manager.lookup_by_file_id(file_id),
+        )
+    };
+    let diagnostics = match path {
+        Some(path) if workspace_config.is_ignored(&path) => Vec::new(),
+        Some(path) => workspace_config.apply_severity_overrides(&path, diagnostics),
+        None => diagnostics,
+    };
+
     // Convert the diagnostics. This includes translating text offsets into `(line, column)` format.
     Some(
         diagnostics
             .into_iter()
-            .flat_map(|diagnostic| convert::lsp_diagnostic_from_native(diagnostic, line_index))
+            .flat_map(|diagnostic| {
+                convert::lsp_diagnostic_from_native(snapshot, diagnostic, line_index)
+            })
             .collect::<Vec<_>>(),
     )
 }
@@ -0,0 +1,295 @@
+use anyhow::anyhow;
+use globset::Glob;
+use globset::GlobBuilder;
+use globset::GlobSetBuilder;
+
+/// A single gitignore-style pattern, e.g. `third_party/**` or `!**/testdata/keep.bzl`.
+///
+/// Patterns are matched against `/`-separated paths relative to some base directory. As with
+/// `.gitignore` files, a pattern that doesn't contain a `/` (other than a trailing one) matches a
+/// file or directory name at any depth, while a pattern that does is anchored to the base
+/// directory. A trailing `/` restricts the pattern to directories. Everything else, i.e. the
+/// wildcards themselves, is handled by `globset`.
+#[derive(Clone, Debug)]
+pub(crate) struct GlobPattern {
+    glob: Glob,
+    is_negated: bool,
+    is_dir_only: bool,
+}
+
+impl GlobPattern {
+    /// Parses a single line of an ignore file. Returns `None` for blank lines and comments.
+    pub(crate) fn parse(line: &str) -> Option<Result<Self, globset::Error>> {
+        let mut pattern = line.trim_end_matches(['\n', '\r']);
+
+        // Trailing spaces are ignored unless they're escaped.
+        while pattern.ends_with(' ') && !pattern.ends_with("\\ ") {
+            pattern = &pattern[..pattern.len() - 1];
+        }
+        if pattern.is_empty() || pattern.starts_with('#') {
+            return None;
+        }
+
+        let is_negated = match pattern.strip_prefix('!') {
+            Some(rest) => {
+                pattern = rest;
+                true
+            }
+            None => false,
+        };
+        if let Some(rest) = pattern.strip_prefix('\\') {
+            if rest.starts_with(['!', '#']) {
+                pattern = rest;
+            }
+        }
+
+        let is_dir_only = match pattern.strip_suffix('/') {
+            Some(rest) => {
+                pattern = rest;
+                true
+            }
+            None => false,
+        };
+
+        let is_anchored = pattern.contains('/');
+        let pattern = normalize_path(pattern);
+        if pattern.is_empty() {
+            return None;
+        }
+
+        let pattern = if is_anchored {
+            pattern
+        } else {
+            format!("**/{}", pattern)
+        };
+        Some(
+            GlobBuilder::new(&pattern)
+                .literal_separator(true)
+                .backslash_escape(true)
+                .build()
+                .map(|glob| Self {
+                    glob,
+                    is_negated,
+                    is_dir_only,
+                }),
+        )
+    }
+
+    pub(crate) fn is_negated(&self) -> bool {
+        self.is_negated
+    }
+}
+
+/// An ordered list of patterns, where later patterns take precedence over earlier ones.
+#[derive(Clone, Debug)]
+pub(crate) struct GlobSet {
+    patterns: Vec<GlobPattern>,
+    set: globset::GlobSet,
+}
+
+impl Default for GlobSet {
+    fn default() -> Self {
+        Self {
+            patterns: Vec::new(),
+            set: globset::GlobSet::empty(),
+        }
+    }
+}
+
+impl GlobSet {
+    /// Parses the given lines, failing on the first one that isn't a valid pattern.
+    pub(crate) fn new<'a>(lines: impl IntoIterator<Item = &'a str>) -> anyhow::Result<Self> {
+        let patterns = lines
+            .into_iter()
+            .filter_map(|line| {
+                GlobPattern::parse(line).map(|res| {
+                    res.map_err(|err| anyhow!("invalid pattern \"{}\": {}", line, err.kind()))
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Self::from_patterns(patterns)
+    }
+
+    /// Parses the given lines, skipping the ones that aren't valid patterns. This is how Git
+    /// treats `.gitignore` files.
+    pub(crate) fn new_lenient<'a>(lines: impl IntoIterator<Item = &'a str>) -> Self {
+        Self::from_patterns(
+            lines
+                .into_iter()
+                .filter_map(|line| GlobPattern::parse(line)?.ok())
+                .collect(),
+        )
+        .unwrap_or_default()
+    }
+
+    /// Returns a set containing the patterns of this set followed by the given ones, which take
+    /// precedence over them.
+    pub(crate) fn with_patterns<'a>(
+        &self,
+        lines: impl IntoIterator<Item = &'a str>,
+    ) -> anyhow::Result<Self> {
+        let mut patterns = self.patterns.clone();
+        patterns.extend(Self::new(lines)?.patterns);
+        Self::from_patterns(patterns)
+    }
+
+    fn from_patterns(patterns: Vec<GlobPattern>) -> anyhow::Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns.iter() {
+            builder.add(pattern.glob.clone());
+        }
+        Ok(Self {
+            set: builder.build()?,
+            patterns,
+        })
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Returns `Some(true)` if the path is matched by this set, `Some(false)` if it is explicitly
+    /// excluded by a negated pattern, and `None` if no pattern applies to it.
+    pub(crate) fn matched(&self, path: &str, is_dir: bool) -> Option<bool> {
+        if self.is_empty() {
+            return None;
+        }
+        self.set
+            .matches(normalize_path(path))
+            .into_iter()
+            .filter(|index| is_dir || !self.patterns[*index].is_dir_only)
+            .max()
+            .map(|index| !self.patterns[index].is_negated())
+    }
+
+    /// Returns whether the given file, or any of the directories containing it, is matched by
+    /// this set.
+    pub(crate) fn matches_path_or_ancestor(&self, path: &str) -> bool {
+        let mut end = path.len();
+        let mut is_dir = false;
+        loop {
+            match self.matched(&path[..end], is_dir) {
+                Some(matched) => return matched,
+                None => match path[..end].rfind('/') {
+                    Some(pos) => {
+                        end = pos;
+                        is_dir = true;
+                    }
+                    None => return false,
+                },
+            }
+        }
+    }
+}
+
+/// Removes empty and `.` components from a `/`-separated path.
+fn normalize_path(path: &str) -> String {
+    path.split('/')
+        .filter(|component| !component.is_empty() && *component != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_match(pattern: &str, path: &str) -> bool {
+        GlobSet::new([pattern]).unwrap().matched(path, false) == Some(true)
+    }
+
+    fn is_dir_match(pattern: &str, path: &str) -> bool {
+        GlobSet::new([pattern]).unwrap().matched(path, true) == Some(true)
+    }
+
+    #[test]
+    fn test_parse_skips_blank_lines_and_comments() {
+        assert!(GlobPattern::parse("").is_none());
+        assert!(GlobPattern::parse("   ").is_none());
+        assert!(GlobPattern::parse("# comment").is_none());
+        assert!(GlobPattern::parse("/").is_none());
+        assert!(is_match("\\#foo", "#foo"));
+        assert!(is_match("foo.bzl  ", "foo.bzl"));
+    }
+
+    #[test]
+    fn test_unanchored_patterns() {
+        assert!(is_match("foo.bzl", "foo.bzl"));
+        assert!(is_match("foo.bzl", "a/b/foo.bzl"));
+        assert!(!is_match("foo.bzl", "a/b/foo.bzl.txt"));
+        assert!(is_match("*.bzl", "a/b/c.bzl"));
+        assert!(!is_match("*.bzl", "a/b/c.star"));
+    }
+
+    #[test]
+    fn test_anchored_patterns() {
+        assert!(is_match("a/foo.bzl", "a/foo.bzl"));
+        assert!(!is_match("a/foo.bzl", "b/a/foo.bzl"));
+        assert!(is_match("/foo.bzl", "foo.bzl"));
+        assert!(!is_match("/foo.bzl", "a/foo.bzl"));
+        assert!(is_match("a/*/c.bzl", "a/b/c.bzl"));
+        assert!(!is_match("a/*/c.bzl", "a/b/b/c.bzl"));
+        assert!(is_match("./a/b.bzl", "./a/b.bzl"));
+    }
+
+    #[test]
+    fn test_double_star() {
+        assert!(is_match("**/testdata/*.bzl", "testdata/a.bzl"));
+        assert!(is_match("**/testdata/*.bzl", "x/y/testdata/a.bzl"));
+        assert!(!is_match("**/testdata/*.bzl", "x/testdata/y/a.bzl"));
+        assert!(is_match("a/**/b", "a/b"));
+        assert!(is_match("a/**/b", "a/x/y/b"));
+        assert!(!is_match("a/**/b", "a/x/y/c"));
+        assert!(is_match("third_party/**", "third_party/foo"));
+        assert!(is_match("third_party/**", "third_party/foo/bar.bzl"));
+        assert!(!is_match("third_party/**", "third_party"));
+        assert!(!is_dir_match("third_party/**", "third_party"));
+    }
+
+    #[test]
+    fn test_dir_only() {
+        assert!(is_dir_match("build/", "build"));
+        assert!(is_dir_match("build/", "a/build"));
+        assert!(!is_match("build/", "build"));
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert!(is_match("f?o.bzl", "foo.bzl"));
+        assert!(is_match("[a-c].bzl", "b.bzl"));
+        assert!(!is_match("[!a-c].bzl", "b.bzl"));
+        assert!(is_match("\\*.bzl", "*.bzl"));
+        assert!(!is_match("\\*.bzl", "a.bzl"));
+        assert!(GlobSet::new(["[a"]).is_err());
+    }
+
+    #[test]
+    fn test_glob_set_precedence() {
+        let set = GlobSet::new(["*.bzl", "!keep.bzl", "# comment", ""]).unwrap();
+        assert_eq!(set.matched("a/b.bzl", false), Some(true));
+        assert_eq!(set.matched("a/keep.bzl", false), Some(false));
+        assert_eq!(set.matched("a/b.star", false), None);
+
+        let set = GlobSet::new(["!keep.bzl", "*.bzl"]).unwrap();
+        assert_eq!(set.matched("a/keep.bzl", false), Some(true));
+        assert!(GlobSet::new(["", "# comment"]).unwrap().is_empty());
+
+        let set = set.with_patterns(["!keep.bzl"]).unwrap();
+        assert_eq!(set.matched("a/keep.bzl", false), Some(false));
+    }
+
+    #[test]
+    fn test_glob_set_skips_invalid_patterns_leniently() {
+        let set = GlobSet::new_lenient(["[a", "*.bzl"]);
+        assert_eq!(set.matched("a/b.bzl", false), Some(true));
+    }
+
+    #[test]
+    fn test_glob_set_matches_path_or_ancestor() {
+        let set = GlobSet::new(["third_party/", "!third_party/keep/"]).unwrap();
+        assert!(set.matches_path_or_ancestor("third_party/foo/bar.bzl"));
+        assert!(!set.matches_path_or_ancestor("third_party/keep/bar.bzl"));
+        assert!(!set.matches_path_or_ancestor("third_party"));
+        assert!(!set.matches_path_or_ancestor("src/bar.bzl"));
+    }
+}
@@ -1,6 +1,9 @@
+use std::path::Path;
+
 use crate::convert;
 use crate::server::Server;
 use crate::utils::apply_document_content_changes;
+use crate::workspace_config::WORKSPACE_CONFIG_FILE_NAME;
 
 pub(crate) fn did_open_text_document(
     server: &mut Server,
@@ -46,6 +49,17 @@ pub(crate) fn did_save_text_document(
     params: lsp_types::DidSaveTextDocumentParams,
 ) -> anyhow::Result<()> {
     let path = convert::path_buf_from_url(&params.text_document.uri)?;
+
+    // Clients that can't watch files for us at least let us know when `starpls.toml` is saved.
+    if !server
+        .config
+        .has_did_change_watched_files_dynamic_registration()
+        && is_workspace_config(server, &path)
+    {
+        server.reload_workspace_config();
+        return Ok(());
+    }
+
     if server
         .document_manager
         .read()
@@ -56,6 +70,7 @@ pub(crate) fn did_save_text_document(
             Some("MODULE.bazel" | "WORKSPACE" | "WORKSPACE.bazel" | "WORKSPACE.bzlmod") => {}
             Some(file_name) if file_name.ends_with(".MODULE.bazel") => {}
             Some("BUILD" | "BUILD.bazel") => {
+                server.refresh_label_resolutions();
                 server.refresh_all_workspace_targets();
                 return Ok(());
             }
@@ -66,3 +81,25 @@ pub(crate) fn did_save_text_document(
     }
     Ok(())
 }
+
+pub(crate) fn did_change_watched_files(
+    server: &mut Server,
+    params: lsp_types::DidChangeWatchedFilesParams,
+) -> anyhow::Result<()> {
+    for change in params.changes {
+        let path = convert::path_buf_from_url(&change.uri)?;
+        if is_workspace_config(server, &path) {
+            server.reload_workspace_config();
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn is_workspace_config(server: &Server, path: &Path) -> bool {
+    path == server
+        .document_manager
+        .read()
+        .workspace()
+        .join(WORKSPACE_CONFIG_FILE_NAME)
+}
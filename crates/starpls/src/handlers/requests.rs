@@ -1,4 +1,7 @@
+use std::collections::HashMap;
+
 use anyhow::Ok;
+use starpls_ide::Applicability;
 use starpls_ide::CompletionItemKind;
 use starpls_ide::CompletionMode::InsertText;
 use starpls_ide::CompletionMode::TextEdit;
@@ -120,6 +123,55 @@ pub(crate) fn find_references(
     Ok(Some(resp.collect()))
 }
 
+pub(crate) fn code_action(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::CodeActionParams,
+) -> anyhow::Result<Option<lsp_types::CodeActionResponse>> {
+    let path = path_buf_from_url(&params.text_document.uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let line_index = try_opt!(snapshot.analysis_snapshot.line_index(file_id)?);
+    let range = try_opt!(convert::text_range_from_lsp_range(
+        snapshot,
+        file_id,
+        params.range
+    )?);
+    Ok(Some(
+        snapshot
+            .analysis_snapshot
+            .fixes(file_id)?
+            .into_iter()
+            .filter(|fix| fix.range.intersect(range).is_some())
+            .filter_map(|fix| {
+                let edits = fix
+                    .edits
+                    .into_iter()
+                    .map(|edit| {
+                        Some(lsp_types::TextEdit {
+                            range: convert::lsp_range_from_text_range(edit.range, line_index)?,
+                            new_text: edit.new_text,
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(lsp_types::CodeActionOrCommand::CodeAction(
+                    lsp_types::CodeAction {
+                        title: fix.label,
+                        kind: Some(lsp_types::CodeActionKind::QUICKFIX),
+                        edit: Some(lsp_types::WorkspaceEdit {
+                            changes: Some(HashMap::from([(
+                                params.text_document.uri.clone(),
+                                edits,
+                            )])),
+                            ..Default::default()
+                        }),
+                        is_preferred: Some(fix.applicability == Applicability::Safe),
+                        ..Default::default()
+                    },
+                ))
+            })
+            .collect(),
+    ))
+}
+
 pub(crate) fn completion(
     snapshot: &ServerSnapshot,
     params: lsp_types::CompletionParams,
@@ -287,6 +339,25 @@ pub(crate) fn document_symbols(
         }))
 }
 
+pub(crate) fn formatting(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::DocumentFormattingParams,
+) -> anyhow::Result<Option<Vec<lsp_types::TextEdit>>> {
+    let path = path_buf_from_url(&params.text_document.uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let line_index = try_opt!(snapshot.analysis_snapshot.line_index(file_id)?);
+    let edits = try_opt!(snapshot.analysis_snapshot.format(file_id)?);
+    Ok(edits
+        .into_iter()
+        .map(|edit| {
+            Some(lsp_types::TextEdit {
+                range: convert::lsp_range_from_text_range(edit.range, line_index)?,
+                new_text: edit.new_text,
+            })
+        })
+        .collect())
+}
+
 fn to_markup_doc(doc: String) -> lsp_types::Documentation {
     lsp_types::Documentation::MarkupContent(lsp_types::MarkupContent {
         kind: lsp_types::MarkupKind::Markdown,
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::glob::GlobSet;
+
+/// Decides which files under a workspace are skipped when collecting files to check or format.
+///
+/// Paths are ignored if they match any of the patterns from the command line or `starpls.toml`,
+/// a `.gitignore` file in the workspace, or an entry in the workspace's `.bazelignore`.
+pub(crate) struct IgnoreFilter {
+    workspace: PathBuf,
+    patterns: GlobSet,
+    bazelignore: Vec<String>,
+    gitignores: HashMap<PathBuf, GlobSet>,
+}
+
+impl IgnoreFilter {
+    pub(crate) fn new(workspace: &Path, patterns: GlobSet) -> Self {
+        // Each line of a `.bazelignore` file is a path relative to the workspace root, rather than
+        // a pattern.
+        let bazelignore = fs::read_to_string(workspace.join(".bazelignore"))
+            .map(|contents| {
+                contents
+                    .lines()
+                    .map(|line| line.trim().trim_matches('/').to_string())
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            workspace: workspace.to_path_buf(),
+            patterns,
+            bazelignore,
+            gitignores: HashMap::new(),
+        }
+    }
+
+    /// Returns whether the given path should be skipped. The path must be absolute. Directories
+    /// are expected to be checked before any of their contents, since a file inside an ignored
+    /// directory isn't necessarily ignored by itself.
+    pub(crate) fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        let Some(workspace_path) = relative_path(&self.workspace, path) else {
+            return false;
+        };
+        if workspace_path.is_empty() {
+            return false;
+        }
+
+        if self.patterns.matched(&workspace_path, is_dir) == Some(true) {
+            return true;
+        }
+
+        if self.bazelignore.iter().any(|entry| {
+            workspace_path
+                .strip_prefix(entry.as_str())
+                .map(|rest| rest.is_empty() || rest.starts_with('/'))
+                .unwrap_or(false)
+        }) {
+            return true;
+        }
+
+        // Rules in a `.gitignore` file apply to paths relative to the directory containing it,
+        // and rules in deeper directories take precedence.
+        let mut dir = path.parent();
+        while let Some(current_dir) = dir.filter(|dir| dir.starts_with(&self.workspace)) {
+            let gitignore = self
+                .gitignores
+                .entry(current_dir.to_path_buf())
+                .or_insert_with(|| {
+                    fs::read_to_string(current_dir.join(".gitignore"))
+                        .map(|contents| GlobSet::new_lenient(contents.lines()))
+                        .unwrap_or_default()
+                });
+            if !gitignore.is_empty() {
+                if let Some(matched) = relative_path(current_dir, path)
+                    .and_then(|path| gitignore.matched(&path, is_dir))
+                {
+                    return matched;
+                }
+            }
+            dir = current_dir.parent();
+        }
+
+        false
+    }
+}
+
+/// Returns the `/`-separated path of `path` relative to `base`, or `None` if `path` isn't inside
+/// `base`.
+pub(crate) fn relative_path(base: &Path, path: &Path) -> Option<String> {
+    path.strip_prefix(base)
+        .ok()
+        .map(|path| path.to_string_lossy().replace('\\', "/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A temporary workspace that is removed when dropped.
+    struct TestWorkspace(PathBuf);
+
+    impl TestWorkspace {
+        fn new(name: &str, files: &[(&str, &str)]) -> Self {
+            let root = std::env::temp_dir().join(format!(
+                "starpls-ignore-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&root);
+            for (path, contents) in files {
+                let path = root.join(path);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, contents).unwrap();
+            }
+            fs::create_dir_all(&root).unwrap();
+            Self(root)
+        }
+    }
+
+    impl Drop for TestWorkspace {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_patterns() {
+        let workspace = TestWorkspace::new("patterns", &[]);
+        let mut filter = IgnoreFilter::new(
+            &workspace.0,
+            GlobSet::new(["third_party/", "*.gen.bzl"]).unwrap(),
+        );
+        assert!(filter.is_ignored(&workspace.0.join("third_party"), true));
+        assert!(!filter.is_ignored(&workspace.0.join("third_party"), false));
+        assert!(filter.is_ignored(&workspace.0.join("a/b.gen.bzl"), false));
+        assert!(!filter.is_ignored(&workspace.0.join("a/b.bzl"), false));
+        assert!(!filter.is_ignored(&workspace.0, true));
+        assert!(!filter.is_ignored(Path::new("/elsewhere/b.gen.bzl"), false));
+    }
+
+    #[test]
+    fn test_bazelignore() {
+        let workspace = TestWorkspace::new(
+            "bazelignore",
+            &[(".bazelignore", "# comment\nnode_modules\n/out/\n\n")],
+        );
+        let mut filter = IgnoreFilter::new(&workspace.0, GlobSet::default());
+        assert!(filter.is_ignored(&workspace.0.join("node_modules"), true));
+        assert!(filter.is_ignored(&workspace.0.join("node_modules/a/b.bzl"), false));
+        assert!(filter.is_ignored(&workspace.0.join("out"), true));
+        assert!(!filter.is_ignored(&workspace.0.join("node_modules2"), true));
+        assert!(!filter.is_ignored(&workspace.0.join("a/node_modules"), true));
+    }
+
+    #[test]
+    fn test_gitignore() {
+        let workspace = TestWorkspace::new(
+            "gitignore",
+            &[
+                (".gitignore", "*.gen.bzl\n/build/\n"),
+                ("a/.gitignore", "!keep.gen.bzl\nlocal.bzl\n"),
+            ],
+        );
+        let mut filter = IgnoreFilter::new(&workspace.0, GlobSet::default());
+        assert!(filter.is_ignored(&workspace.0.join("b.gen.bzl"), false));
+        assert!(filter.is_ignored(&workspace.0.join("a/b.gen.bzl"), false));
+        assert!(!filter.is_ignored(&workspace.0.join("a/keep.gen.bzl"), false));
+        assert!(filter.is_ignored(&workspace.0.join("keep.gen.bzl"), false));
+        assert!(filter.is_ignored(&workspace.0.join("a/local.bzl"), false));
+        assert!(!filter.is_ignored(&workspace.0.join("local.bzl"), false));
+        assert!(filter.is_ignored(&workspace.0.join("build"), true));
+        assert!(!filter.is_ignored(&workspace.0.join("a/build"), true));
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            relative_path(Path::new("/ws"), Path::new("/ws/a/b.bzl")),
+            Some("a/b.bzl".to_string())
+        );
+        assert_eq!(
+            relative_path(Path::new("/ws"), Path::new("/ws")),
+            Some(String::new())
+        );
+        assert_eq!(
+            relative_path(Path::new("/ws"), Path::new("/other/a.bzl")),
+            None
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use clap::Parser;
 use clap::Subcommand;
 use commands::check::CheckCommand;
+use commands::fmt::FmtCommand;
 use commands::server::ServerCommand;
 
 mod bazel;
@@ -9,14 +10,18 @@ mod config;
 mod convert;
 mod debouncer;
 mod diagnostics;
+mod diff;
 mod dispatcher;
 mod document;
 mod event_loop;
 mod extensions;
+mod glob;
 mod handlers;
+mod ignore;
 mod server;
 mod task_pool;
 mod utils;
+mod workspace_config;
 
 #[derive(Parser)]
 struct Cli {
@@ -30,6 +35,9 @@ enum Commands {
     /// Analyze the specified Starlark files and report errors.
     Check(CheckCommand),
 
+    /// Format the specified Starlark files.
+    Fmt(FmtCommand),
+
     /// Start the language server.
     Server(ServerCommand),
 
@@ -51,6 +59,7 @@ fn main() -> anyhow::Result<()> {
 
     match cli.command {
         Some(Commands::Check(cmd)) => cmd.run(),
+        Some(Commands::Fmt(cmd)) => cmd.run(),
         Some(Commands::Server(cmd)) => cmd.run(),
         Some(Commands::Version) => run_version(),
         None => ServerCommand::default().run(),
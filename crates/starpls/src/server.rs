@@ -11,6 +11,7 @@ use log::error;
 use log::info;
 use lsp_server::Connection;
 use lsp_server::ReqQueue;
+use lsp_types::notification::Notification as _;
 use parking_lot::RwLock;
 use rustc_hash::FxHashSet;
 use starpls_bazel::build_language::decode_rules;
@@ -40,8 +41,11 @@ use crate::event_loop::RefreshAllWorkspaceTargetsProgress;
 use crate::event_loop::Task;
 use crate::task_pool::TaskPool;
 use crate::task_pool::TaskPoolHandle;
+use crate::workspace_config::WorkspaceConfig;
+use crate::workspace_config::WORKSPACE_CONFIG_FILE_NAME;
 
 const BAZEL_INIT_ERR_MESSAGE: &str = "Failed to fetch Bazel configuration! Please check the language server logs for more details. Certain features may not work correctly until the underlying issue is fixed.";
+const WORKSPACE_CONFIG_ERR_MESSAGE: &str = "Failed to load starpls.toml! Please check the language server logs for more details. The default settings will be used until the underlying issue is fixed.";
 
 pub(crate) struct Server {
     pub(crate) config: Arc<ServerConfig>,
@@ -70,7 +74,7 @@ pub(crate) struct ServerSnapshot {
 }
 
 impl Server {
-    pub(crate) fn new(connection: Connection, config: ServerConfig) -> anyhow::Result<Self> {
+    pub(crate) fn new(connection: Connection, mut config: ServerConfig) -> anyhow::Result<Self> {
         // Create the task pool for processing incoming requests.
         let (task_pool_sender, task_pool_receiver) = crossbeam_channel::unbounded();
         let task_pool = TaskPool::with_num_threads(task_pool_sender.clone(), 4)?;
@@ -100,6 +104,17 @@ impl Server {
             }
         };
 
+        // Load project-wide settings from the workspace's `starpls.toml`, if there is one.
+        let mut has_workspace_config_err = false;
+        config.workspace_config = match WorkspaceConfig::load(&bazel_cx.info.workspace) {
+            Ok(workspace_config) => workspace_config,
+            Err(err) => {
+                error!("failed to load workspace config: {}", err);
+                has_workspace_config_err = true;
+                Default::default()
+            }
+        };
+
         // Query for all targets in the current workspace, to use for label completion.
         let targets = if config.args.enable_label_completions {
             debug!("querying for all targets in the current workspace");
@@ -128,16 +143,10 @@ impl Server {
             task_pool_sender.clone(),
             bazel_cx.bzlmod_enabled,
         );
-        let mut analysis = Analysis::new(
-            Arc::new(loader),
-            InferenceOptions {
-                infer_ctx_attributes: config.args.inference_options.infer_ctx_attributes,
-                use_code_flow_analysis: config.args.inference_options.use_code_flow_analysis,
-                ..Default::default()
-            },
-        );
+        let mut analysis = Analysis::new(Arc::new(loader), inference_options(&config));
 
         analysis.set_all_workspace_targets(targets);
+        analysis.set_enabled_lints(config.workspace_config.enabled_lints.iter().copied());
         analysis.set_builtin_defs(load_bazel_builtins(), bazel_cx.rules);
 
         // Check for a prelude file. We skip verifying that `//tools/build_tools` is actually a package (i.e.
@@ -189,10 +198,77 @@ impl Server {
         if has_bazel_init_err {
             server.send_error_message(BAZEL_INIT_ERR_MESSAGE);
         }
+        if has_workspace_config_err {
+            server.send_error_message(WORKSPACE_CONFIG_ERR_MESSAGE);
+        }
 
         Ok(server)
     }
 
+    /// Asks the client to notify us of changes to `starpls.toml`, so that the workspace settings
+    /// can be reloaded without restarting the server.
+    pub(crate) fn watch_workspace_config(&mut self) {
+        if !self
+            .config
+            .has_did_change_watched_files_dynamic_registration()
+        {
+            return;
+        }
+
+        let register_options =
+            serde_json::to_value(lsp_types::DidChangeWatchedFilesRegistrationOptions {
+                watchers: vec![lsp_types::FileSystemWatcher {
+                    glob_pattern: lsp_types::GlobPattern::String(format!(
+                        "**/{}",
+                        WORKSPACE_CONFIG_FILE_NAME
+                    )),
+                    kind: None,
+                }],
+            })
+            .expect("bug: failed to serialize registration options");
+        self.send_request::<lsp_types::request::RegisterCapability>(
+            lsp_types::RegistrationParams {
+                registrations: vec![lsp_types::Registration {
+                    id: WORKSPACE_CONFIG_FILE_NAME.to_string(),
+                    method: lsp_types::notification::DidChangeWatchedFiles::METHOD.to_string(),
+                    register_options: Some(register_options),
+                }],
+            },
+        );
+    }
+
+    /// Reloads the workspace's `starpls.toml` and recomputes diagnostics for all open documents
+    /// with the new settings.
+    pub(crate) fn reload_workspace_config(&mut self) {
+        let workspace = self.document_manager.read().workspace().to_path_buf();
+        debug!("reloading workspace config for {:?}", workspace);
+        let workspace_config = match WorkspaceConfig::load(&workspace) {
+            Ok(workspace_config) => workspace_config,
+            Err(err) => {
+                error!("failed to load workspace config: {}", err);
+                self.send_error_message(WORKSPACE_CONFIG_ERR_MESSAGE);
+                Default::default()
+            }
+        };
+
+        let mut config = ServerConfig::clone(&self.config);
+        config.workspace_config = workspace_config;
+        self.analysis
+            .set_inference_options(inference_options(&config));
+        self.analysis
+            .set_enabled_lints(config.workspace_config.enabled_lints.iter().copied());
+        self.config = Arc::new(config);
+        self.force_analysis_for_files
+            .extend(self.document_manager.read().open_documents());
+    }
+
+    /// Resolves labels again, since saving a BUILD file might have created or removed a package.
+    pub(crate) fn refresh_label_resolutions(&mut self) {
+        self.analysis.refresh_label_resolutions();
+        self.force_analysis_for_files
+            .extend(self.document_manager.read().open_documents());
+    }
+
     pub(crate) fn snapshot(&self) -> ServerSnapshot {
         ServerSnapshot {
             config: self.config.clone(),
@@ -374,6 +450,19 @@ pub(crate) fn load_bazel_build_language(client: &dyn BazelClient) -> anyhow::Res
     decode_rules(&build_language_output)
 }
 
+/// Combines the inference options from the command line with the ones from `starpls.toml`.
+fn inference_options(config: &ServerConfig) -> InferenceOptions {
+    InferenceOptions {
+        infer_ctx_attributes: config.args.inference_options.infer_ctx_attributes
+            || config.workspace_config.infer_ctx_attributes,
+        use_code_flow_analysis: config.args.inference_options.use_code_flow_analysis
+            || config.workspace_config.use_code_flow_analysis,
+        strict_type_checking: config.args.inference_options.strict_type_checking
+            || config.workspace_config.strict_type_checking,
+        ..Default::default()
+    }
+}
+
 fn load_bazel_prelude(workspace: impl AsRef<Path>) -> anyhow::Result<(PathBuf, String)> {
     let prelude = workspace.as_ref().join("tools/build_rules/prelude_bazel");
     let contents = fs::read_to_string(&prelude)?;
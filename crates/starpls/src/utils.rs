@@ -1,4 +1,9 @@
+use std::ffi::OsString;
+use std::fs;
+use std::io;
 use std::ops::Range;
+use std::path::Path;
+use std::process;
 
 use anyhow::format_err;
 use line_index::LineIndex;
@@ -158,3 +163,28 @@ where
             .into()
     }
 }
+
+/// Writes `contents` to `path` by first writing to a temporary file in the same directory and
+/// then renaming it over the destination, so that readers never observe a partially written file.
+pub(crate) fn write_file_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let mut tmp_file_name = OsString::from(".");
+    tmp_file_name.push(file_name);
+    tmp_file_name.push(format!(".starpls-{}.tmp", process::id()));
+    let tmp_path = path.with_file_name(tmp_file_name);
+
+    if let Err(err) = fs::write(&tmp_path, contents).and_then(|_| {
+        // Preserve the permissions of the file being replaced, if it exists.
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&tmp_path, metadata.permissions())?;
+        }
+        fs::rename(&tmp_path, path)
+    }) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(err);
+    }
+
+    Ok(())
+}
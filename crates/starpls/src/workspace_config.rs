@@ -0,0 +1,397 @@
+//! Project-wide settings read from a `starpls.toml` file at the root of the workspace, e.g.
+//!
+//! ```toml
+//! extensions = ["star", "sky"]
+//! ignore = ["third_party/**", "**/testdata/*.bzl"]
+//! enabled_lints = ["out-of-order-load", "unsorted-dict-items"]
+//!
+//! [inference]
+//! infer_ctx_attributes = true
+//! use_code_flow_analysis = true
+//! strict_type_checking = true
+//!
+//! [severity]
+//! unused-load = "error"
+//!
+//! [[overrides]]
+//! paths = ["third_party/"]
+//! severity = { unused-definition = "off", undefined-name = "warning" }
+//! ```
+//!
+//! Settings from the command line are combined with the ones in this file.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use anyhow::bail;
+use starpls_common::Diagnostic;
+use starpls_common::DiagnosticCode;
+use starpls_common::Severity;
+use toml::Table;
+use toml::Value;
+
+use crate::glob::GlobSet;
+
+pub(crate) const WORKSPACE_CONFIG_FILE_NAME: &str = "starpls.toml";
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct WorkspaceConfig {
+    /// Additional extensions of plain Starlark files to check.
+    pub(crate) extensions: Vec<String>,
+    /// Gitignore-style patterns for files and directories to skip. `IgnoreFilter` matches files
+    /// against these when collecting files to check or format, and so does the server when
+    /// reporting diagnostics for open files.
+    pub(crate) ignore: GlobSet,
+    /// Lints to run in addition to the ones that are on by default.
+    pub(crate) enabled_lints: Vec<DiagnosticCode>,
+    pub(crate) infer_ctx_attributes: bool,
+    pub(crate) use_code_flow_analysis: bool,
+    pub(crate) strict_type_checking: bool,
+    severity_overrides: Vec<SeverityOverride>,
+}
+
+/// Changes the severity of diagnostics with the given codes, optionally only for files matching
+/// a set of patterns. A severity of `None` suppresses the diagnostic entirely.
+#[derive(Clone, Debug)]
+struct SeverityOverride {
+    paths: Option<GlobSet>,
+    severities: Vec<(DiagnosticCode, Option<Severity>)>,
+}
+
+impl WorkspaceConfig {
+    /// Loads the config file for the given workspace. Returns the default config if the
+    /// workspace doesn't have one.
+    pub(crate) fn load(workspace: &Path) -> anyhow::Result<Self> {
+        let path = workspace.join(WORKSPACE_CONFIG_FILE_NAME);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Default::default()),
+            Err(err) => bail!("failed to read {:?}: {}", path, err),
+        };
+        contents
+            .parse::<Table>()
+            .map_err(|err| anyhow!("{}", err))
+            .and_then(Self::from_table)
+            .map_err(|err| anyhow!("failed to parse {:?}: {}", path, err))
+    }
+
+    fn from_table(table: Table) -> anyhow::Result<Self> {
+        let mut config = Self::default();
+        for (key, value) in table {
+            match key.as_str() {
+                "extensions" => config.extensions = string_array(&key, value)?,
+                "ignore" => {
+                    let patterns = string_array(&key, value)?;
+                    config.ignore = GlobSet::new(patterns.iter().map(String::as_str))?;
+                }
+                "enabled_lints" => {
+                    config.enabled_lints = string_array(&key, value)?
+                        .iter()
+                        .map(|code| DiagnosticCode::from_str(code))
+                        .collect::<Result<_, _>>()?
+                }
+                "inference" => {
+                    for (key, value) in table_value(&key, value)? {
+                        match (key.as_str(), value) {
+                            ("infer_ctx_attributes", Value::Boolean(value)) => {
+                                config.infer_ctx_attributes = value
+                            }
+                            ("use_code_flow_analysis", Value::Boolean(value)) => {
+                                config.use_code_flow_analysis = value
+                            }
+                            ("strict_type_checking", Value::Boolean(value)) => {
+                                config.strict_type_checking = value
+                            }
+                            (
+                                "infer_ctx_attributes"
+                                | "use_code_flow_analysis"
+                                | "strict_type_checking",
+                                value,
+                            ) => {
+                                bail!(
+                                    "expected a boolean for \"inference.{}\", found {}",
+                                    key,
+                                    value.type_str()
+                                )
+                            }
+                            _ => bail!("unknown key \"inference.{}\"", key),
+                        }
+                    }
+                }
+                "severity" => {
+                    // The workspace-wide severities apply before any of the overrides.
+                    config.severity_overrides.insert(
+                        0,
+                        SeverityOverride {
+                            paths: None,
+                            severities: parse_severities(&key, value)?,
+                        },
+                    )
+                }
+                "overrides" => {
+                    let Value::Array(overrides) = value else {
+                        bail!("expected an array of tables for \"overrides\"");
+                    };
+                    for value in overrides {
+                        let mut paths = None;
+                        let mut severities = Vec::new();
+                        for (key, value) in table_value("overrides", value)? {
+                            match key.as_str() {
+                                "paths" => {
+                                    let patterns = string_array("overrides.paths", value)?;
+                                    paths =
+                                        Some(GlobSet::new(patterns.iter().map(String::as_str))?);
+                                }
+                                "severity" => {
+                                    severities = parse_severities("overrides.severity", value)?
+                                }
+                                _ => bail!("unknown key \"overrides.{}\"", key),
+                            }
+                        }
+                        if paths.is_none() {
+                            bail!("missing key \"overrides.paths\"");
+                        }
+                        config
+                            .severity_overrides
+                            .push(SeverityOverride { paths, severities });
+                    }
+                }
+                _ => bail!("unknown key \"{}\"", key),
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Returns whether the file at the given workspace-relative path is ignored.
+    pub(crate) fn is_ignored(&self, path: &str) -> bool {
+        self.ignore.matches_path_or_ancestor(path)
+    }
+
+    /// Applies the severity overrides for the file at the given workspace-relative path.
+    /// Diagnostics that are turned off are removed.
+    pub(crate) fn apply_severity_overrides(
+        &self,
+        path: &str,
+        diagnostics: Vec<Diagnostic>,
+    ) -> Vec<Diagnostic> {
+        if self.severity_overrides.is_empty() {
+            return diagnostics;
+        }
+
+        diagnostics
+            .into_iter()
+            .filter_map(|mut diagnostic| {
+                match self.severity_override(path, diagnostic.code) {
+                    Some(Some(severity)) => diagnostic.severity = severity.clone(),
+                    Some(None) => return None,
+                    None => {}
+                }
+                Some(diagnostic)
+            })
+            .collect()
+    }
+
+    /// Returns whether diagnostics with the given code are reported for the file at the given
+    /// workspace-relative path.
+    pub(crate) fn is_enabled(&self, path: &str, code: DiagnosticCode) -> bool {
+        !matches!(self.severity_override(path, code), Some(None))
+    }
+
+    /// Returns the overridden severity for a diagnostic code in the given file, if any. Later
+    /// overrides take precedence over earlier ones.
+    fn severity_override(&self, path: &str, code: DiagnosticCode) -> Option<&Option<Severity>> {
+        self.severity_overrides
+            .iter()
+            .rev()
+            .filter(|severity_override| {
+                severity_override
+                    .paths
+                    .as_ref()
+                    .map(|paths| paths.matches_path_or_ancestor(path))
+                    .unwrap_or(true)
+            })
+            .find_map(|severity_override| {
+                severity_override
+                    .severities
+                    .iter()
+                    .find(|(other, _)| *other == code)
+                    .map(|(_, severity)| severity)
+            })
+    }
+}
+
+fn string_array(key: &str, value: Value) -> anyhow::Result<Vec<String>> {
+    let Value::Array(values) = value else {
+        bail!(
+            "expected an array of strings for \"{}\", found {}",
+            key,
+            value.type_str()
+        );
+    };
+    values
+        .into_iter()
+        .map(|value| match value {
+            Value::String(value) => Ok(value),
+            value => Err(anyhow!(
+                "expected an array of strings for \"{}\", found an element of type {}",
+                key,
+                value.type_str()
+            )),
+        })
+        .collect()
+}
+
+fn table_value(key: &str, value: Value) -> anyhow::Result<Table> {
+    match value {
+        Value::Table(table) => Ok(table),
+        value => bail!(
+            "expected a table for \"{}\", found {}",
+            key,
+            value.type_str()
+        ),
+    }
+}
+
+fn parse_severities(
+    key: &str,
+    value: Value,
+) -> anyhow::Result<Vec<(DiagnosticCode, Option<Severity>)>> {
+    table_value(key, value)?
+        .into_iter()
+        .map(|(code, value)| {
+            let code = DiagnosticCode::from_str(&code)?;
+            let severity = match value {
+                Value::String(severity) => match severity.as_str() {
+                    "error" => Some(Severity::Error),
+                    "warning" => Some(Severity::Warning),
+                    "info" => Some(Severity::Info),
+                    "hint" => Some(Severity::Hint),
+                    "off" => None,
+                    _ => bail!(
+                        "invalid severity \"{}\" for \"{}\", expected one of \"error\", \"warning\", \"info\", \"hint\", or \"off\"",
+                        severity,
+                        code
+                    ),
+                },
+                value => bail!(
+                    "expected a string for \"{}.{}\", found {}",
+                    key,
+                    code,
+                    value.type_str()
+                ),
+            };
+            Ok((code, severity))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> anyhow::Result<WorkspaceConfig> {
+        WorkspaceConfig::from_table(text.parse()?)
+    }
+
+    fn parse_error(text: &str) -> String {
+        parse(text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_settings() {
+        let config = parse(
+            r#"
+extensions = ["star", "sky"]
+ignore = ["third_party/", "**/testdata/*.bzl"]
+enabled_lints = ["unsorted-dict-items"]
+
+[inference]
+infer_ctx_attributes = true
+strict_type_checking = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.extensions, ["star", "sky"]);
+        assert_eq!(config.enabled_lints, [DiagnosticCode::UnsortedDictItems]);
+        assert!(config.infer_ctx_attributes);
+        assert!(!config.use_code_flow_analysis);
+        assert!(config.strict_type_checking);
+        assert!(config.is_ignored("third_party/foo/bar.bzl"));
+        assert!(config.is_ignored("a/testdata/b.bzl"));
+        assert!(!config.is_ignored("a/b.bzl"));
+    }
+
+    #[test]
+    fn test_severity_overrides() {
+        let config = parse(
+            r#"
+[severity]
+unused-load = "error"
+unused-definition = "warning"
+
+[[overrides]]
+paths = ["third_party/"]
+severity = { unused-definition = "off", unused-load = "hint" }
+
+[[overrides]]
+paths = ["third_party/keep/"]
+severity = { unused-definition = "info" }
+"#,
+        )
+        .unwrap();
+
+        let severity = |path, code| config.severity_override(path, code).cloned().flatten();
+        assert_eq!(
+            severity("a.bzl", DiagnosticCode::UnusedLoad),
+            Some(Severity::Error)
+        );
+        assert_eq!(
+            severity("third_party/a.bzl", DiagnosticCode::UnusedLoad),
+            Some(Severity::Hint)
+        );
+        assert_eq!(
+            severity("third_party/keep/a.bzl", DiagnosticCode::UnusedDefinition),
+            Some(Severity::Info)
+        );
+        assert!(config.is_enabled("a.bzl", DiagnosticCode::UnusedDefinition));
+        assert!(!config.is_enabled("third_party/a.bzl", DiagnosticCode::UnusedDefinition));
+        assert!(config.is_enabled("third_party/a.bzl", DiagnosticCode::UndefinedName));
+        assert!(config
+            .severity_override("a.bzl", DiagnosticCode::UndefinedName)
+            .is_none());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse_error("foo = 1"), "unknown key \"foo\"");
+        assert_eq!(
+            parse_error("enabled_lints = [\"sorted-everything\"]"),
+            "unknown diagnostic code \"sorted-everything\""
+        );
+        assert_eq!(
+            parse_error("extensions = \"star\""),
+            "expected an array of strings for \"extensions\", found string"
+        );
+        assert_eq!(
+            parse_error("[inference]\nstrict_type_checking = 1"),
+            "expected a boolean for \"inference.strict_type_checking\", found integer"
+        );
+        assert_eq!(
+            parse_error("[severity]\nunused-load = \"loud\""),
+            "invalid severity \"loud\" for \"unused-load\", expected one of \"error\", \"warning\", \"info\", \"hint\", or \"off\""
+        );
+        assert_eq!(
+            parse_error("[[overrides]]\nseverity = {}"),
+            "missing key \"overrides.paths\""
+        );
+        assert!(parse_error("ignore = [\"a\"").starts_with("TOML parse error at line 1"));
+        assert_eq!(
+            parse_error("ignore = [\"[a\"]"),
+            "invalid pattern \"[a\": unclosed character class; missing ']'"
+        );
+    }
+}
@@ -44,6 +44,8 @@ pub struct Attribute {
     pub doc: String,
     pub default_value: String,
     pub is_mandatory: bool,
+    #[serde(default)]
+    pub is_nonconfigurable: bool,
 }
 
 pub fn make_common_attributes() -> CommonAttributes {
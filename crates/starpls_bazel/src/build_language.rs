@@ -32,11 +32,13 @@ pub fn decode_rules(build_language_output: &[u8]) -> anyhow::Result<Builtins> {
                                     let doc = attr.documentation().to_string();
                                     let r#type =
                                         attribute_type_string_from_discriminator(attr.r#type());
+                                    let is_nonconfigurable = attr.configurable == Some(false);
                                     Param {
                                         name: attr.name,
                                         r#type,
                                         doc,
                                         is_mandatory: false,
+                                        is_nonconfigurable,
                                         ..Default::default()
                                     }
                                 })
@@ -50,6 +50,7 @@ impl From<ValueJson> for Value {
                         is_mandatory: param.is_mandatory,
                         is_star_arg: param.is_star_arg,
                         is_star_star_arg: param.is_star_star_arg,
+                        is_nonconfigurable: false,
                     })
                     .collect(),
                 return_type: callable.return_type,
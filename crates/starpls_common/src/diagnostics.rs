@@ -1,3 +1,6 @@
+use std::fmt;
+use std::str::FromStr;
+
 use starpls_syntax::TextRange;
 
 use crate::FileId;
@@ -9,6 +12,15 @@ pub struct Diagnostic {
     pub severity: Severity,
     pub range: FileRange,
     pub tags: Option<Vec<DiagnosticTag>>,
+    pub code: DiagnosticCode,
+    /// Other locations that help explain the diagnostic, e.g. the calls that make up a cycle.
+    pub related_information: Option<Vec<DiagnosticRelatedInformation>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiagnosticRelatedInformation {
+    pub range: FileRange,
+    pub message: String,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -20,6 +32,8 @@ pub struct FileRange {
 /// A severity level for diagnostic messages.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Severity {
+    /// A hint, e.g. for code that editors should display as faded instead of underlining it.
+    Hint,
     Info,
     Warning,
     Error,
@@ -31,5 +45,94 @@ pub enum DiagnosticTag {
     Deprecated,
 }
 
+macro_rules! diagnostic_codes {
+    ($($variant:ident => $code:literal,)*) => {
+        /// A stable identifier for the kind of a diagnostic. Codes are reported to editors and
+        /// in `starpls check` output, and can be used to select diagnostics by kind.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum DiagnosticCode {
+            $($variant,)*
+        }
+
+        impl DiagnosticCode {
+            pub const ALL: &'static [DiagnosticCode] = &[$(DiagnosticCode::$variant,)*];
+
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $(DiagnosticCode::$variant => $code,)*
+                }
+            }
+        }
+
+        impl FromStr for DiagnosticCode {
+            type Err = UnknownDiagnosticCode;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($code => Ok(DiagnosticCode::$variant),)*
+                    _ => Err(UnknownDiagnosticCode(s.to_string())),
+                }
+            }
+        }
+    };
+}
+
+diagnostic_codes! {
+    SyntaxError => "syntax-error",
+    TopLevelStatement => "top-level-statement",
+    BuildSyntax => "build-syntax",
+    InvalidParameter => "invalid-parameter",
+    InvalidAssignment => "invalid-assignment",
+    FrozenMutation => "frozen-mutation",
+    UndefinedName => "undefined-name",
+    UsedBeforeAssignment => "used-before-assignment",
+    GlobalReassignment => "global-reassignment",
+    PossiblyUnbound => "possibly-unbound",
+    UnusedDefinition => "unused-definition",
+    UnusedLoad => "unused-load",
+    UnreachableCode => "unreachable-code",
+    TypeMismatch => "type-mismatch",
+    UnsupportedOperation => "unsupported-operation",
+    InvalidIndex => "invalid-index",
+    DuplicateKey => "duplicate-key",
+    UnknownField => "unknown-field",
+    InvalidArgument => "invalid-argument",
+    MissingArgument => "missing-argument",
+    DeprecatedArgument => "deprecated-argument",
+    InvalidAttributeValue => "invalid-attribute-value",
+    UnresolvedModule => "unresolved-module",
+    UnresolvedSymbol => "unresolved-symbol",
+    UnresolvedLabel => "unresolved-label",
+    PrivateSymbol => "private-symbol",
+    LoadVisibility => "load-visibility",
+    CircularLoad => "circular-load",
+    Recursion => "recursion",
+    InvalidType => "invalid-type",
+    NativeBuild => "native-build",
+    PositionalArgs => "positional-args",
+    LoadOnTop => "load-on-top",
+    OutOfOrderLoad => "out-of-order-load",
+    UnsortedDictItems => "unsorted-dict-items",
+    DuplicatedName => "duplicated-name",
+    UnnormalizedLabel => "unnormalized-label",
+}
+
+impl fmt::Display for DiagnosticCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownDiagnosticCode(pub String);
+
+impl fmt::Display for UnknownDiagnosticCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown diagnostic code \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for UnknownDiagnosticCode {}
+
 #[salsa::accumulator]
 pub struct Diagnostics(Diagnostic);
@@ -1,4 +1,5 @@
 use std::fmt::Debug;
+use std::fmt::{self};
 use std::path::PathBuf;
 
 use starpls_bazel::APIContext;
@@ -7,13 +8,18 @@ use starpls_syntax::parse_module;
 use starpls_syntax::LineIndex;
 use starpls_syntax::Module;
 use starpls_syntax::ParseTree;
+use starpls_syntax::SyntaxKind;
 use starpls_syntax::SyntaxNode;
+use starpls_syntax::TextRange;
 
 pub use crate::diagnostics::Diagnostic;
+pub use crate::diagnostics::DiagnosticCode;
+pub use crate::diagnostics::DiagnosticRelatedInformation;
 pub use crate::diagnostics::DiagnosticTag;
 pub use crate::diagnostics::Diagnostics;
 pub use crate::diagnostics::FileRange;
 pub use crate::diagnostics::Severity;
+pub use crate::diagnostics::UnknownDiagnosticCode;
 
 mod diagnostics;
 mod util;
@@ -22,7 +28,9 @@ mod util;
 pub struct Jar(
     Diagnostics,
     File,
+    LabelResolution,
     LineIndexResult,
+    PackageResolution,
     Parse,
     parse,
     line_index_query,
@@ -34,6 +42,14 @@ pub enum Dialect {
     Bazel,
 }
 
+impl Dialect {
+    /// Whether inline type annotations, e.g. `def f(x: int) -> int`, are allowed. Bazel doesn't
+    /// support them, so they're reported as syntax errors in Bazel files.
+    pub fn allows_type_annotations(&self) -> bool {
+        matches!(self, Dialect::Standard)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum LoadItemCandidateKind {
     Directory,
@@ -51,6 +67,7 @@ pub struct LoadItemCandidate {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct FileId(pub u32);
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ResolvedPath {
     Source {
         path: PathBuf,
@@ -60,6 +77,41 @@ pub enum ResolvedPath {
         target: String,
         contents: Option<String>,
     },
+    /// The label's package doesn't exist, i.e. it has no BUILD file.
+    MissingPackage,
+    /// The label belongs to an external repository that hasn't been fetched yet, so whether it
+    /// exists isn't known.
+    UnfetchedRepository {
+        repo: String,
+    },
+}
+
+/// Returned by a `FileLoader` when a file belongs to an external repository that hasn't been
+/// fetched yet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnfetchedRepositoryError {
+    pub repo: String,
+}
+
+impl fmt::Display for UnfetchedRepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "external repository \"{}\" has not been fetched yet",
+            self.repo
+        )
+    }
+}
+
+impl std::error::Error for UnfetchedRepositoryError {}
+
+/// The Bazel package that contains a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FilePackage {
+    /// The canonical name of the package's repository, or the empty string for the main
+    /// repository.
+    pub repo: String,
+    pub package: String,
 }
 
 /// The base Salsa database. Supports file-related operations, like getting/setting file contents.
@@ -98,7 +150,16 @@ pub trait Db: salsa::DbWithJar<Jar> {
         from: FileId,
     ) -> anyhow::Result<Option<ResolvedPath>>;
 
+    /// Resolves a label like [`Db::resolve_path`], but stores the result in a [`LabelResolution`]
+    /// input so that it can be refreshed when the filesystem changes.
+    fn resolve_label(&self, label: &str, from: FileId) -> LabelResolution;
+
     fn resolve_build_file(&self, file_id: FileId) -> Option<String>;
+
+    /// Returns the package that contains the given file, i.e. the one declared by the closest
+    /// BUILD file in the file's directory or its parents. Like [`Db::resolve_label`], the result
+    /// is stored in an input so that it can be refreshed when BUILD files are added or removed.
+    fn resolve_package(&self, file_id: FileId) -> PackageResolution;
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -132,6 +193,20 @@ impl File {
     }
 }
 
+/// The result of resolving a label from a file.
+#[salsa::input]
+pub struct LabelResolution {
+    #[return_ref]
+    pub resolved_path: Option<ResolvedPath>,
+}
+
+/// The result of resolving the package that contains a file.
+#[salsa::input]
+pub struct PackageResolution {
+    #[return_ref]
+    pub package: Option<FilePackage>,
+}
+
 #[salsa::tracked]
 pub struct Parse {
     pub file: File,
@@ -150,20 +225,42 @@ impl Parse {
 
 #[salsa::tracked]
 pub fn parse(db: &dyn Db, file: File) -> Parse {
-    let parse = parse_module(file.contents(db), &mut |err| {
+    let push_error = |message: String, range: TextRange| {
         Diagnostics::push(
             db,
             Diagnostic {
-                message: err.message,
+                message,
                 range: FileRange {
                     file_id: file.id(db),
-                    range: err.range,
+                    range,
                 },
                 severity: Severity::Error,
                 tags: None,
+                code: DiagnosticCode::SyntaxError,
+                related_information: None,
             },
         )
+    };
+    let parse = parse_module(file.contents(db), &mut |err| {
+        push_error(err.message, err.range)
     });
+
+    // The parser always accepts type annotations, so reject them here for dialects that don't
+    // support them.
+    if !file.dialect(db).allows_type_annotations() {
+        for node in parse.syntax().descendants().filter(|node| {
+            matches!(
+                node.kind(),
+                SyntaxKind::TYPE_ANNOTATION | SyntaxKind::RETURN_TYPE
+            )
+        }) {
+            push_error(
+                "Type annotations are not supported in this dialect".to_string(),
+                node.text_range(),
+            );
+        }
+    }
+
     Parse::new(db, file, parse)
 }
 
@@ -17,13 +17,17 @@ use starpls_syntax::ast::UnaryOp;
 use starpls_syntax::ast::{self};
 use starpls_syntax::TextRange;
 
+use crate::module;
 use crate::typeck::TypeRef;
 use crate::Db;
 
 pub(crate) mod codeflow;
 mod lower;
+pub(crate) mod recursion;
 pub(crate) mod resolver;
 pub(crate) mod scope;
+pub(crate) mod targets;
+pub(crate) mod visibility;
 
 #[cfg(test)]
 mod tests;
@@ -52,6 +56,9 @@ pub(crate) struct Module {
     pub(crate) type_ignore_comment_lines: HashSet<u32>,
     pub(crate) call_expr_with_impl_fn: FxHashMap<Name, ExprId>,
     pub(crate) param_to_def_stmt: FxHashMap<ParamId, (StmtId, usize)>,
+    pub(crate) param_to_lambda_expr: FxHashMap<ParamId, (ExprId, usize)>,
+    /// Maps lambdas that are passed directly as arguments to the call and the argument's index.
+    pub(crate) lambda_expr_to_call_arg: FxHashMap<ExprId, (ExprId, usize)>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -491,4 +498,20 @@ impl Function {
             value: self.ptr(db),
         }
     }
+
+    /// Returns the type variables used in the function's type comment, e.g. `T` in
+    /// `# type: (list[T]) -> T`.
+    pub(crate) fn type_vars(&self, db: &dyn Db) -> Vec<Name> {
+        let module = module(db, self.file(db));
+        let mut type_vars = Vec::new();
+        for type_ref in self
+            .params(db)
+            .iter()
+            .filter_map(|param| module[*param].type_ref())
+            .chain(self.ret_type_ref(db))
+        {
+            type_ref.collect_type_vars(&mut type_vars);
+        }
+        type_vars
+    }
 }
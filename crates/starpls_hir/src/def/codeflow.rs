@@ -3,13 +3,20 @@ use id_arena::Arena;
 use id_arena::Id;
 use rustc_hash::FxHashMap;
 use starpls_common::File;
+use starpls_syntax::ast::BinaryOp;
+use starpls_syntax::ast::CmpOp;
+use starpls_syntax::ast::LogicOp;
+use starpls_syntax::ast::UnaryOp;
 
 use crate::def::scope::module_scopes;
 use crate::def::scope::ExecutionScopeId;
 use crate::def::scope::ScopeHirId;
 use crate::def::scope::Scopes;
+use crate::def::Argument;
 use crate::def::CompClause;
 use crate::def::Expr;
+use crate::def::InternedString;
+use crate::def::Literal;
 use crate::def::Stmt;
 use crate::def::StmtId;
 use crate::lower;
@@ -43,9 +50,73 @@ pub(crate) enum FlowNode {
         expr: ExprId,
         antecedent: FlowNodeId,
     },
+    /// A point at which `expr` is known to have evaluated to a truthy value, e.g. the start of
+    /// an `if` statement's body.
+    TrueCondition {
+        expr: ExprId,
+        antecedent: FlowNodeId,
+    },
+    /// A point at which `expr` is known to have evaluated to a falsy value, e.g. the start of
+    /// an `else` clause.
+    FalseCondition {
+        expr: ExprId,
+        antecedent: FlowNodeId,
+    },
     Unreachable,
 }
 
+/// A comparison that narrows the type of a name, e.g. `x == None` or `type(x) == "list"`.
+pub(crate) enum NarrowingComparison<'a> {
+    None {
+        name: &'a Name,
+    },
+    Type {
+        name: &'a Name,
+        type_name: InternedString,
+    },
+}
+
+impl<'a> NarrowingComparison<'a> {
+    /// Matches the operands of an `==` or `!=` comparison, in either order.
+    pub(crate) fn from_operands(module: &'a Module, lhs: ExprId, rhs: ExprId) -> Option<Self> {
+        Self::from_operands_ordered(module, lhs, rhs)
+            .or_else(|| Self::from_operands_ordered(module, rhs, lhs))
+    }
+
+    fn from_operands_ordered(module: &'a Module, lhs: ExprId, rhs: ExprId) -> Option<Self> {
+        match (&module[lhs], &module[rhs]) {
+            (
+                Expr::Name { name },
+                Expr::Literal {
+                    literal: Literal::None,
+                },
+            ) => Some(NarrowingComparison::None { name }),
+            (
+                Expr::Call { callee, args },
+                Expr::Literal {
+                    literal: Literal::String(type_name),
+                },
+            ) => {
+                match &module[*callee] {
+                    Expr::Name { name } if name.as_str() == "type" => {}
+                    _ => return None,
+                }
+                match &args[..] {
+                    [Argument::Simple { expr }] => match &module[*expr] {
+                        Expr::Name { name } => Some(NarrowingComparison::Type {
+                            name,
+                            type_name: *type_name,
+                        }),
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct CodeFlowGraph {
     pub(crate) flow_nodes: Arena<FlowNode>,
@@ -125,29 +196,34 @@ impl<'a> CodeFlowLoweringContext<'a> {
                 let post_if_node = self.new_flow_node(FlowNode::Branch {
                     antecedents: Vec::new(),
                 });
+                self.curr_node = self.new_condition_node(*test, pre_if_node, true);
                 self.lower_stmts(if_stmts);
                 self.push_antecedent(post_if_node, self.curr_node);
+                let false_node = self.new_condition_node(*test, pre_if_node, false);
                 match elif_or_else_stmts {
                     Some(Either::Left(elif_stmt)) => {
-                        self.curr_node = pre_if_node;
+                        self.curr_node = false_node;
                         self.lower_stmt(*elif_stmt);
                         self.push_antecedent(post_if_node, self.curr_node);
                     }
                     Some(Either::Right(else_stmts)) => {
-                        self.curr_node = pre_if_node;
+                        self.curr_node = false_node;
                         self.lower_stmts(else_stmts);
                         self.push_antecedent(post_if_node, self.curr_node);
                     }
                     _ => {
-                        self.push_antecedent(post_if_node, pre_if_node);
+                        self.push_antecedent(post_if_node, false_node);
                     }
                 }
 
                 self.curr_node = self.finish_branch_or_loop_node(post_if_node);
             }
 
-            Stmt::Return { expr: Some(expr) } => {
-                self.lower_expr(*expr);
+            Stmt::Return { expr } => {
+                if let Some(expr) = expr {
+                    self.lower_expr(*expr);
+                }
+                self.curr_node = self.unreachable_node;
             }
 
             Stmt::Expr { expr } => {
@@ -160,10 +236,6 @@ impl<'a> CodeFlowLoweringContext<'a> {
                 stmts,
             } => {
                 self.lower_expr(*iterable);
-                for target in targets.iter() {
-                    self.lower_assignment_target(*target, *iterable);
-                }
-
                 let pre_for_node = self.new_flow_node(FlowNode::Loop {
                     antecedents: Vec::new(),
                 });
@@ -178,9 +250,13 @@ impl<'a> CodeFlowLoweringContext<'a> {
                 self.curr_break_target = Some(post_for_node);
                 self.curr_continue_target = Some(pre_for_node);
 
-                // Lower the actual `for` statement body.
+                // Lower the actual `for` statement body. The targets are assigned at the start
+                // of each iteration, so they might be unbound after the loop.
                 self.push_antecedent(pre_for_node, self.curr_node);
                 self.curr_node = pre_for_node;
+                for target in targets.iter() {
+                    self.lower_assignment_target(*target, *iterable);
+                }
                 self.lower_stmts(stmts);
 
                 // Wire up the pre-`for` and post-`for` nodes.
@@ -222,13 +298,54 @@ impl<'a> CodeFlowLoweringContext<'a> {
                 entry,
                 comp_clauses,
             } => {
-                self.lower_comp_clauses(comp_clauses);
+                let pre_comp_node = self.curr_node;
+                let narrowed = self.lower_comp_clauses(comp_clauses);
                 self.lower_expr(entry.key);
                 self.lower_expr(entry.value);
+                if narrowed {
+                    self.join_flow_nodes(pre_comp_node);
+                }
             }
             Expr::ListComp { expr, comp_clauses } => {
-                self.lower_comp_clauses(comp_clauses);
+                let pre_comp_node = self.curr_node;
+                let narrowed = self.lower_comp_clauses(comp_clauses);
                 self.lower_expr(*expr);
+                if narrowed {
+                    self.join_flow_nodes(pre_comp_node);
+                }
+            }
+            Expr::Binary {
+                lhs,
+                rhs,
+                op: Some(BinaryOp::Logic(op)),
+            } => {
+                // The right-hand side is only evaluated if the left-hand side is truthy (for
+                // `and`) or falsy (for `or`).
+                let is_and = matches!(op, LogicOp::And);
+                self.lower_expr(*lhs);
+                let pre_rhs_node = self.curr_node;
+                self.curr_node = self.new_condition_node(*lhs, pre_rhs_node, is_and);
+                self.lower_expr(*rhs);
+                if self.curr_node != pre_rhs_node {
+                    let short_circuit_node = self.new_condition_node(*lhs, pre_rhs_node, !is_and);
+                    self.join_flow_nodes(short_circuit_node);
+                }
+            }
+            Expr::If {
+                if_expr,
+                test,
+                else_expr,
+            } => {
+                self.lower_expr(*test);
+                let pre_if_node = self.curr_node;
+                self.curr_node = self.new_condition_node(*test, pre_if_node, true);
+                self.lower_expr(*if_expr);
+                let post_if_expr_node = self.curr_node;
+                self.curr_node = self.new_condition_node(*test, pre_if_node, false);
+                self.lower_expr(*else_expr);
+                if post_if_expr_node != pre_if_node || self.curr_node != pre_if_node {
+                    self.join_flow_nodes(post_if_expr_node);
+                }
             }
             node @ Expr::Call { .. } => {
                 node.walk_child_exprs(|expr| {
@@ -273,7 +390,11 @@ impl<'a> CodeFlowLoweringContext<'a> {
         }
     }
 
-    fn lower_comp_clauses(&mut self, comp_clauses: &[CompClause]) {
+    /// Lowers the clauses of a comprehension, returning whether any of its filters narrow the
+    /// types of names referenced in later clauses. Since the filters only apply within the
+    /// comprehension itself, callers must then join the resulting flow with the flow before it.
+    fn lower_comp_clauses(&mut self, comp_clauses: &[CompClause]) -> bool {
+        let mut narrowed = false;
         for comp_clause in comp_clauses.iter() {
             match comp_clause {
                 CompClause::For { iterable, targets } => {
@@ -284,9 +405,63 @@ impl<'a> CodeFlowLoweringContext<'a> {
                 }
                 CompClause::If { test } => {
                     self.lower_expr(*test);
+                    let pre_test_node = self.curr_node;
+                    self.curr_node = self.new_condition_node(*test, pre_test_node, true);
+                    narrowed |= self.curr_node != pre_test_node;
                 }
             }
         }
+        narrowed
+    }
+
+    /// Joins the current flow node with `other` into a new branch node.
+    fn join_flow_nodes(&mut self, other: FlowNodeId) {
+        let post_node = self.new_flow_node(FlowNode::Branch {
+            antecedents: Vec::new(),
+        });
+        self.push_antecedent(post_node, self.curr_node);
+        self.push_antecedent(post_node, other);
+        self.curr_node = self.finish_branch_or_loop_node(post_node);
+    }
+
+    /// Creates a flow node indicating that `expr` evaluated to a truthy or falsy value. If the
+    /// expression can't narrow the types of any names, then `antecedent` is returned as-is.
+    fn new_condition_node(
+        &mut self,
+        expr: ExprId,
+        antecedent: FlowNodeId,
+        is_true: bool,
+    ) -> FlowNodeId {
+        if antecedent == self.unreachable_node || !self.is_narrowing_expr(expr) {
+            return antecedent;
+        }
+        self.new_flow_node(if is_true {
+            FlowNode::TrueCondition { expr, antecedent }
+        } else {
+            FlowNode::FalseCondition { expr, antecedent }
+        })
+    }
+
+    fn is_narrowing_expr(&self, expr: ExprId) -> bool {
+        match &self.module[expr] {
+            Expr::Name { .. } => true,
+            Expr::Paren { expr }
+            | Expr::Unary {
+                op: Some(UnaryOp::Not),
+                expr,
+            } => self.is_narrowing_expr(*expr),
+            Expr::Binary {
+                lhs,
+                rhs,
+                op: Some(BinaryOp::Logic(_)),
+            } => self.is_narrowing_expr(*lhs) || self.is_narrowing_expr(*rhs),
+            Expr::Binary {
+                lhs,
+                rhs,
+                op: Some(BinaryOp::Cmp(CmpOp::Eq | CmpOp::Ne)),
+            } => NarrowingComparison::from_operands(self.module, *lhs, *rhs).is_some(),
+            _ => false,
+        }
     }
 
     fn new_flow_node(&mut self, data: FlowNode) -> FlowNodeId {
@@ -563,28 +738,28 @@ for x, y in [[1, 2, 3], [3, 4]]:
                     }
 
                     'bb2: {
-                        data: Assign { expr: Id { idx: 8 }, name: Name("x"), execution_scope: Module, source: Id { idx: 7 }, antecedent: Id { idx: 1 } }
-                        antecedents: ['bb1]
+                        data: Loop { antecedents: [Id { idx: 1 }, Id { idx: 7 }] }
+                        antecedents: ['bb1, 'bb7]
                     }
 
                     'bb3: {
-                        data: Assign { expr: Id { idx: 9 }, name: Name("y"), execution_scope: Module, source: Id { idx: 7 }, antecedent: Id { idx: 2 } }
+                        data: Branch { antecedents: [Id { idx: 2 }] }
                         antecedents: ['bb2]
                     }
 
                     'bb4: {
-                        data: Loop { antecedents: [Id { idx: 3 }, Id { idx: 7 }] }
-                        antecedents: ['bb3, 'bb7]
+                        data: Assign { expr: Id { idx: 8 }, name: Name("x"), execution_scope: Module, source: Id { idx: 7 }, antecedent: Id { idx: 2 } }
+                        antecedents: ['bb2]
                     }
 
                     'bb5: {
-                        data: Branch { antecedents: [Id { idx: 4 }] }
+                        data: Assign { expr: Id { idx: 9 }, name: Name("y"), execution_scope: Module, source: Id { idx: 7 }, antecedent: Id { idx: 4 } }
                         antecedents: ['bb4]
                     }
 
                     'bb6: {
-                        data: Assign { expr: Id { idx: 21 }, name: Name("i"), execution_scope: Comp(Id { idx: 22 }), source: Id { idx: 20 }, antecedent: Id { idx: 4 } }
-                        antecedents: ['bb4]
+                        data: Assign { expr: Id { idx: 21 }, name: Name("i"), execution_scope: Comp(Id { idx: 22 }), source: Id { idx: 20 }, antecedent: Id { idx: 5 } }
+                        antecedents: ['bb5]
                     }
 
                     'bb7: {
@@ -616,18 +791,18 @@ for x in [1, 2, 3]:
                     }
 
                     'bb2: {
-                        data: Assign { expr: Id { idx: 4 }, name: Name("x"), execution_scope: Module, source: Id { idx: 3 }, antecedent: Id { idx: 1 } }
-                        antecedents: ['bb1]
+                        data: Loop { antecedents: [Id { idx: 1 }, Id { idx: 4 }] }
+                        antecedents: ['bb1, 'bb4]
                     }
 
                     'bb3: {
-                        data: Loop { antecedents: [Id { idx: 2 }, Id { idx: 3 }] }
-                        antecedents: ['bb2, 'bb3]
+                        data: Branch { antecedents: [Id { idx: 2 }] }
+                        antecedents: ['bb2]
                     }
 
                     'bb4: {
-                        data: Branch { antecedents: [Id { idx: 3 }] }
-                        antecedents: ['bb3]
+                        data: Assign { expr: Id { idx: 4 }, name: Name("x"), execution_scope: Module, source: Id { idx: 3 }, antecedent: Id { idx: 2 } }
+                        antecedents: ['bb2]
                     }
 
             "#]],
@@ -654,18 +829,18 @@ for x in [1, 2, 3]:
                     }
 
                     'bb2: {
-                        data: Assign { expr: Id { idx: 4 }, name: Name("x"), execution_scope: Module, source: Id { idx: 3 }, antecedent: Id { idx: 1 } }
+                        data: Loop { antecedents: [Id { idx: 1 }] }
                         antecedents: ['bb1]
                     }
 
                     'bb3: {
-                        data: Loop { antecedents: [Id { idx: 2 }] }
-                        antecedents: ['bb2]
+                        data: Branch { antecedents: [Id { idx: 4 }, Id { idx: 2 }] }
+                        antecedents: ['bb4, 'bb2]
                     }
 
                     'bb4: {
-                        data: Branch { antecedents: [Id { idx: 3 }] }
-                        antecedents: ['bb3]
+                        data: Assign { expr: Id { idx: 4 }, name: Name("x"), execution_scope: Module, source: Id { idx: 3 }, antecedent: Id { idx: 2 } }
+                        antecedents: ['bb2]
                     }
 
             "#]],
@@ -696,28 +871,28 @@ a = 1
                     }
 
                     'bb2: {
-                        data: Assign { expr: Id { idx: 4 }, name: Name("x"), execution_scope: Module, source: Id { idx: 3 }, antecedent: Id { idx: 1 } }
+                        data: Loop { antecedents: [Id { idx: 1 }] }
                         antecedents: ['bb1]
                     }
 
                     'bb3: {
-                        data: Loop { antecedents: [Id { idx: 2 }] }
-                        antecedents: ['bb2]
+                        data: Branch { antecedents: [Id { idx: 5 }, Id { idx: 2 }] }
+                        antecedents: ['bb5, 'bb2]
                     }
 
                     'bb4: {
-                        data: Branch { antecedents: [Id { idx: 5 }, Id { idx: 3 }] }
-                        antecedents: ['bb5, 'bb3]
+                        data: Assign { expr: Id { idx: 4 }, name: Name("x"), execution_scope: Module, source: Id { idx: 3 }, antecedent: Id { idx: 2 } }
+                        antecedents: ['bb2]
                     }
 
                     'bb5: {
-                        data: Assign { expr: Id { idx: 5 }, name: Name("y"), execution_scope: Module, source: Id { idx: 6 }, antecedent: Id { idx: 3 } }
-                        antecedents: ['bb3]
+                        data: Assign { expr: Id { idx: 5 }, name: Name("y"), execution_scope: Module, source: Id { idx: 6 }, antecedent: Id { idx: 4 } }
+                        antecedents: ['bb4]
                     }
 
                     'bb6: {
-                        data: Assign { expr: Id { idx: 9 }, name: Name("a"), execution_scope: Module, source: Id { idx: 10 }, antecedent: Id { idx: 4 } }
-                        antecedents: ['bb4]
+                        data: Assign { expr: Id { idx: 9 }, name: Name("a"), execution_scope: Module, source: Id { idx: 10 }, antecedent: Id { idx: 3 } }
+                        antecedents: ['bb3]
                     }
 
             "#]],
@@ -748,33 +923,33 @@ for x in [1, 2, 3]:
                     }
 
                     'bb2: {
-                        data: Assign { expr: Id { idx: 4 }, name: Name("x"), execution_scope: Module, source: Id { idx: 3 }, antecedent: Id { idx: 1 } }
+                        data: Loop { antecedents: [Id { idx: 1 }] }
                         antecedents: ['bb1]
                     }
 
                     'bb3: {
-                        data: Loop { antecedents: [Id { idx: 2 }] }
-                        antecedents: ['bb2]
+                        data: Branch { antecedents: [Id { idx: 6 }, Id { idx: 2 }] }
+                        antecedents: ['bb6, 'bb2]
                     }
 
                     'bb4: {
-                        data: Branch { antecedents: [Id { idx: 7 }, Id { idx: 3 }] }
-                        antecedents: ['bb7, 'bb3]
+                        data: Assign { expr: Id { idx: 4 }, name: Name("x"), execution_scope: Module, source: Id { idx: 3 }, antecedent: Id { idx: 2 } }
+                        antecedents: ['bb2]
                     }
 
                     'bb5: {
-                        data: Assign { expr: Id { idx: 9 }, name: Name("y"), execution_scope: Module, source: Id { idx: 8 }, antecedent: Id { idx: 3 } }
-                        antecedents: ['bb3]
+                        data: Loop { antecedents: [Id { idx: 4 }] }
+                        antecedents: ['bb4]
                     }
 
                     'bb6: {
-                        data: Loop { antecedents: [Id { idx: 5 }] }
-                        antecedents: ['bb5]
+                        data: Branch { antecedents: [Id { idx: 7 }, Id { idx: 5 }] }
+                        antecedents: ['bb7, 'bb5]
                     }
 
                     'bb7: {
-                        data: Branch { antecedents: [Id { idx: 6 }] }
-                        antecedents: ['bb6]
+                        data: Assign { expr: Id { idx: 9 }, name: Name("y"), execution_scope: Module, source: Id { idx: 8 }, antecedent: Id { idx: 5 } }
+                        antecedents: ['bb5]
                     }
 
             "#]],
@@ -803,23 +978,23 @@ for x in [1, 2, 3]:
                     }
 
                     'bb2: {
-                        data: Assign { expr: Id { idx: 4 }, name: Name("x"), execution_scope: Module, source: Id { idx: 3 }, antecedent: Id { idx: 1 } }
-                        antecedents: ['bb1]
+                        data: Loop { antecedents: [Id { idx: 1 }, Id { idx: 5 }] }
+                        antecedents: ['bb1, 'bb5]
                     }
 
                     'bb3: {
-                        data: Loop { antecedents: [Id { idx: 2 }, Id { idx: 5 }] }
-                        antecedents: ['bb2, 'bb5]
+                        data: Branch { antecedents: [Id { idx: 2 }] }
+                        antecedents: ['bb2]
                     }
 
                     'bb4: {
-                        data: Branch { antecedents: [Id { idx: 3 }] }
-                        antecedents: ['bb3]
+                        data: Assign { expr: Id { idx: 4 }, name: Name("x"), execution_scope: Module, source: Id { idx: 3 }, antecedent: Id { idx: 2 } }
+                        antecedents: ['bb2]
                     }
 
                     'bb5: {
-                        data: Assign { expr: Id { idx: 5 }, name: Name("y"), execution_scope: Module, source: Id { idx: 6 }, antecedent: Id { idx: 3 } }
-                        antecedents: ['bb3]
+                        data: Assign { expr: Id { idx: 5 }, name: Name("y"), execution_scope: Module, source: Id { idx: 6 }, antecedent: Id { idx: 4 } }
+                        antecedents: ['bb4]
                     }
 
             "#]],
@@ -850,18 +1025,18 @@ for i in [1, 2, 3]:
                     }
 
                     'bb2: {
-                        data: Assign { expr: Id { idx: 4 }, name: Name("i"), execution_scope: Module, source: Id { idx: 3 }, antecedent: Id { idx: 1 } }
-                        antecedents: ['bb1]
+                        data: Loop { antecedents: [Id { idx: 1 }, Id { idx: 4 }] }
+                        antecedents: ['bb1, 'bb4]
                     }
 
                     'bb3: {
-                        data: Loop { antecedents: [Id { idx: 2 }, Id { idx: 3 }] }
-                        antecedents: ['bb2, 'bb3]
+                        data: Branch { antecedents: [Id { idx: 2 }] }
+                        antecedents: ['bb2]
                     }
 
                     'bb4: {
-                        data: Branch { antecedents: [Id { idx: 3 }] }
-                        antecedents: ['bb3]
+                        data: Assign { expr: Id { idx: 4 }, name: Name("i"), execution_scope: Module, source: Id { idx: 3 }, antecedent: Id { idx: 2 } }
+                        antecedents: ['bb2]
                     }
 
                     'bb5: {
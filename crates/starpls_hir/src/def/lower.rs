@@ -1,6 +1,9 @@
 use either::Either;
+use rustc_hash::FxHashSet;
+use starpls_bazel::APIContext;
 use starpls_common::line_index;
 use starpls_common::Diagnostic;
+use starpls_common::DiagnosticCode;
 use starpls_common::Diagnostics;
 use starpls_common::File;
 use starpls_common::FileRange;
@@ -48,6 +51,8 @@ pub(super) fn lower_module(
     LoweringContext {
         db,
         file,
+        is_build_file: file.api_context(db) == Some(APIContext::Build),
+        allows_type_annotations: file.dialect(db).allows_type_annotations(),
         module: Default::default(),
         source_map: ModuleSourceMap {
             root,
@@ -67,6 +72,10 @@ pub(super) fn lower_module(
 struct LoweringContext<'a> {
     db: &'a dyn Db,
     file: File,
+    /// BUILD files are restricted to a subset of Starlark, e.g. they can't define functions.
+    is_build_file: bool,
+    /// Inline annotations like `def f(x: int) -> str` are only honored in standard Starlark files.
+    allows_type_annotations: bool,
     module: Module,
     source_map: ModuleSourceMap,
 }
@@ -80,15 +89,37 @@ impl<'a> LoweringContext<'a> {
             .collect();
 
         let mut top_level = Vec::new();
-        for statement in syntax.statements() {
+        let mut seen_non_load_stmt = false;
+        for (index, statement) in syntax.statements().enumerate() {
             let stmt = self.lower_stmt(statement.clone());
             top_level.push(stmt);
+            match &self.module.stmts[stmt] {
+                Stmt::Load { .. } if seen_non_load_stmt && self.is_build_file => self
+                    .add_error_diagnostic(
+                        DiagnosticCode::LoadOnTop,
+                        "Load statements must come before any other statement in BUILD files",
+                        statement.syntax(),
+                    ),
+                Stmt::Load { .. } => {}
+                // The module docstring may precede `load` statements.
+                Stmt::Expr { expr }
+                    if index == 0
+                        && matches!(
+                            &self.module.exprs[*expr],
+                            Expr::Literal {
+                                literal: Literal::String(_)
+                            }
+                        ) => {}
+                _ => seen_non_load_stmt = true,
+            }
             match &self.module.stmts[stmt] {
                 Stmt::If { .. } => self.add_error_diagnostic(
+                    DiagnosticCode::TopLevelStatement,
                     "Starlark does not allow top-level if statements",
                     statement.syntax(),
                 ),
                 Stmt::For { .. } => self.add_error_diagnostic(
+                    DiagnosticCode::TopLevelStatement,
                     "Starlark does not allow top-level for statements",
                     statement.syntax(),
                 ),
@@ -103,6 +134,16 @@ impl<'a> LoweringContext<'a> {
         let ptr = AstPtr::new(&stmt);
         let statement = match stmt {
             ast::Statement::Def(node) => {
+                if self.is_build_file {
+                    self.add_error_diagnostic(
+                        DiagnosticCode::BuildSyntax,
+                        "Functions may not be defined in BUILD files",
+                        node.name()
+                            .as_ref()
+                            .map(|name| name.syntax())
+                            .unwrap_or_else(|| node.syntax()),
+                    );
+                }
                 let name = self.lower_name_opt(node.name());
                 let spec = self.lower_func_type_opt(node.spec());
                 let doc = node.doc().and_then(|doc| doc.value());
@@ -111,12 +152,15 @@ impl<'a> LoweringContext<'a> {
                     spec.as_ref().map(|spec| &spec.0[..]).unwrap_or(&[]),
                     &doc,
                 );
+                let ret_type_ref = self
+                    .lower_annotation_opt(node.return_type().and_then(|ret| ret.type_()))
+                    .or(spec.map(|spec| spec.1));
                 let stmts = self.lower_suite_opt(node.suite());
                 let func = Function::new(
                     self.db,
                     self.file,
                     name,
-                    spec.map(|spec| spec.1),
+                    ret_type_ref,
                     doc,
                     ptr.syntax_node_ptr(),
                     params,
@@ -236,6 +280,13 @@ impl<'a> LoweringContext<'a> {
                 Expr::Binary { lhs, rhs, op }
             }
             ast::Expression::Lambda(node) => {
+                if self.is_build_file {
+                    self.add_error_diagnostic(
+                        DiagnosticCode::BuildSyntax,
+                        "Functions may not be defined in BUILD files",
+                        node.syntax(),
+                    );
+                }
                 let params = self.lower_params_opt(node.parameters(), &[], &None);
                 let func = Function::new(
                     self.db,
@@ -247,7 +298,11 @@ impl<'a> LoweringContext<'a> {
                     params,
                 );
                 let body = self.lower_expr_opt(node.body());
-                Expr::Lambda { func, body }
+                let expr = self.alloc_expr(Expr::Lambda { func, body }, ptr);
+                for (i, param) in func.params(self.db).iter().enumerate() {
+                    self.module.param_to_lambda_expr.insert(*param, (expr, i));
+                }
+                return expr;
             }
             ast::Expression::List(node) => {
                 let exprs = node
@@ -314,10 +369,27 @@ impl<'a> LoweringContext<'a> {
                     }
                     _ => None,
                 });
+                let lambda_args = args
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, arg)| match arg {
+                        Argument::Simple { expr } | Argument::Keyword { expr, .. }
+                            if matches!(self.module.exprs[*expr], Expr::Lambda { .. }) =>
+                        {
+                            Some((*expr, i))
+                        }
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
                 let expr = self.alloc_expr(Expr::Call { callee, args }, ptr);
                 if let Some(name) = impl_fn_name {
                     self.module.call_expr_with_impl_fn.insert(name, expr);
                 }
+                for (lambda, i) in lambda_args {
+                    self.module
+                        .lambda_expr_to_call_arg
+                        .insert(lambda, (expr, i));
+                }
                 return expr;
             }
             ast::Expression::Index(node) => {
@@ -373,7 +445,11 @@ impl<'a> LoweringContext<'a> {
         let mut saw_names = vec![];
         let mut check_duplicate_param = |cx: &mut Self, name: &Name, syntax: &SyntaxNode| {
             if !name.is_missing() && saw_names.contains(name) {
-                cx.add_error_diagnostic(&format!("Duplicate parameter {}", name.as_str()), syntax);
+                cx.add_error_diagnostic(
+                    DiagnosticCode::InvalidParameter,
+                    &format!("Duplicate parameter {}", name.as_str()),
+                    syntax,
+                );
             } else {
                 saw_names.push(name.clone());
             }
@@ -386,8 +462,15 @@ impl<'a> LoweringContext<'a> {
         {
             let ptr = AstPtr::new(&param);
             let type_ref = self
-                .lower_type_comment_opt(param.type_comment())
-                .map(|res| res.0)
+                .lower_annotation_opt(
+                    param
+                        .type_annotation()
+                        .and_then(|annotation| annotation.type_()),
+                )
+                .or_else(|| {
+                    self.lower_type_comment_opt(param.type_comment())
+                        .map(|res| res.0)
+                })
                 .or(spec_type_refs.get(i).cloned());
             let param = match param {
                 ast::Parameter::Simple(param) => {
@@ -398,6 +481,7 @@ impl<'a> LoweringContext<'a> {
                     check_duplicate_param(self, &name, param.syntax());
                     if saw_default_param && !saw_star_arg && default.is_none() {
                         self.add_error_diagnostic(
+                            DiagnosticCode::InvalidParameter,
                             "Non-default parameter cannot follow default parameter",
                             param.syntax(),
                         );
@@ -407,6 +491,7 @@ impl<'a> LoweringContext<'a> {
                     }
                     if saw_star_star_arg {
                         self.add_error_diagnostic(
+                            DiagnosticCode::InvalidParameter,
                             "Parameter cannot follow \"**\" parameter",
                             param.syntax(),
                         );
@@ -426,12 +511,14 @@ impl<'a> LoweringContext<'a> {
                     check_duplicate_param(self, &name, param.syntax());
                     if saw_star_arg {
                         self.add_error_diagnostic(
+                            DiagnosticCode::InvalidParameter,
                             "Only one \"*\" parameter is allowed",
                             param.syntax(),
                         );
                     }
                     if saw_star_star_arg {
                         self.add_error_diagnostic(
+                            DiagnosticCode::InvalidParameter,
                             "Parameter cannot follow \"**\" parameter",
                             param.syntax(),
                         );
@@ -451,6 +538,7 @@ impl<'a> LoweringContext<'a> {
                     check_duplicate_param(self, &name, param.syntax());
                     if saw_star_star_arg {
                         self.add_error_diagnostic(
+                            DiagnosticCode::InvalidParameter,
                             "Only one \"**\" parameter is allowed",
                             param.syntax(),
                         );
@@ -471,6 +559,7 @@ impl<'a> LoweringContext<'a> {
     }
 
     fn lower_args_opt(&mut self, syntax: Option<ast::Arguments>) -> Box<[Argument]> {
+        let mut keywords = FxHashSet::default();
         let args = syntax
             .iter()
             .flat_map(|arguments| arguments.arguments())
@@ -481,14 +570,35 @@ impl<'a> LoweringContext<'a> {
                 }
                 ast::Argument::Keyword(arg) => {
                     let name = self.lower_name_opt(arg.name());
+                    if !name.is_missing() && !keywords.insert(name.clone()) {
+                        self.add_error_diagnostic(
+                            DiagnosticCode::InvalidArgument,
+                            &format!("Keyword argument \"{}\" is repeated", name.as_str()),
+                            arg.syntax(),
+                        );
+                    }
                     let expr = self.lower_expr_opt(arg.expr());
                     Argument::Keyword { name, expr }
                 }
                 ast::Argument::UnpackedList(arg) => {
+                    if self.is_build_file {
+                        self.add_error_diagnostic(
+                            DiagnosticCode::BuildSyntax,
+                            "*args arguments are not allowed in BUILD files",
+                            arg.syntax(),
+                        );
+                    }
                     let expr = self.lower_expr_opt(arg.expr());
                     Argument::UnpackedList { expr }
                 }
                 ast::Argument::UnpackedDict(arg) => {
+                    if self.is_build_file {
+                        self.add_error_diagnostic(
+                            DiagnosticCode::BuildSyntax,
+                            "**kwargs arguments are not allowed in BUILD files",
+                            arg.syntax(),
+                        );
+                    }
                     let expr = self.lower_expr_opt(arg.expr());
                     Argument::UnpackedDict { expr }
                 }
@@ -658,6 +768,13 @@ impl<'a> LoweringContext<'a> {
         .unwrap_or_else(|| TypeRef::Unknown)
     }
 
+    /// Lowers an inline type annotation. Annotations take precedence over type comments, but are
+    /// ignored in dialects that don't support them, where they're reported as syntax errors.
+    fn lower_annotation_opt(&self, node: Option<ast::Type>) -> Option<TypeRef> {
+        node.filter(|_| self.allows_type_annotations)
+            .map(Self::lower_type)
+    }
+
     fn lower_type_opt(&self, node: Option<ast::Type>) -> TypeRef {
         node.map(Self::lower_type).unwrap_or(TypeRef::Unknown)
     }
@@ -690,7 +807,7 @@ impl<'a> LoweringContext<'a> {
         id
     }
 
-    fn add_error_diagnostic(&self, message: &str, syntax: &SyntaxNode) {
+    fn add_error_diagnostic(&self, code: DiagnosticCode, message: &str, syntax: &SyntaxNode) {
         Diagnostics::push(
             self.db,
             Diagnostic {
@@ -701,6 +818,8 @@ impl<'a> LoweringContext<'a> {
                     range: syntax.text_range(),
                 },
                 tags: None,
+                code,
+                related_information: None,
             },
         );
     }
@@ -0,0 +1,316 @@
+//! Detects recursion, which Starlark doesn't allow. Bazel only reports recursion when the
+//! recursive call is actually evaluated, so we instead build a call graph from the calls in each
+//! function body and report the calls that close a cycle, including cycles that span multiple
+//! files through `load()` statements.
+
+use std::collections::VecDeque;
+
+use either::Either;
+use rustc_hash::FxHashMap;
+use starpls_common::Diagnostic;
+use starpls_common::DiagnosticCode;
+use starpls_common::DiagnosticRelatedInformation;
+use starpls_common::File;
+use starpls_common::FileRange;
+use starpls_common::InFile;
+use starpls_common::Severity;
+use starpls_syntax::TextRange;
+
+use crate::def::resolver::Export;
+use crate::def::resolver::Resolver;
+use crate::def::scope::FunctionDef;
+use crate::def::scope::LoadItemDef;
+use crate::def::scope::ScopeDef;
+use crate::def::Expr;
+use crate::def::ExprId;
+use crate::def::LoadItem;
+use crate::def::Module;
+use crate::def::Name;
+use crate::def::Stmt;
+use crate::def::StmtId;
+use crate::module;
+use crate::source_map;
+use crate::Db;
+
+/// A call from the body of one function to another user-defined function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct CallEdge {
+    /// The range of the call expression.
+    range: TextRange,
+    callee: InFile<StmtId>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct FunctionNode {
+    name: Name,
+    calls: Vec<CallEdge>,
+}
+
+/// The calls to user-defined functions made by each `def` statement in a file.
+#[salsa::tracked]
+pub(crate) struct FunctionCalls {
+    #[return_ref]
+    pub(crate) functions: FxHashMap<StmtId, FunctionNode>,
+}
+
+#[salsa::tracked]
+pub(crate) fn function_calls(db: &dyn Db, file: File) -> FunctionCalls {
+    let module = module(db, file);
+    let source_map = source_map(db, file);
+    let functions = module
+        .stmts
+        .iter()
+        .filter_map(|(stmt, data)| match data {
+            Stmt::Def { func, stmts } => Some((
+                stmt,
+                FunctionNode {
+                    name: func.name(db),
+                    calls: collect_calls(module, stmts)
+                        .into_iter()
+                        .filter_map(|(call, callee, name)| {
+                            Some(CallEdge {
+                                range: source_map
+                                    .expr_map_back
+                                    .get(&call)?
+                                    .syntax_node_ptr()
+                                    .text_range(),
+                                callee: resolve_callee(db, file, callee, name)?,
+                            })
+                        })
+                        .collect(),
+                },
+            )),
+            _ => None,
+        })
+        .collect();
+    FunctionCalls::new(db, functions)
+}
+
+/// Collects the calls to named functions in the given function body, returning the call
+/// expression, the callee expression and the callee's name for each.
+///
+/// Only calls made when the function itself is called count, so calls in nested functions and
+/// lambdas are skipped.
+fn collect_calls<'a>(module: &'a Module, stmts: &[StmtId]) -> Vec<(ExprId, ExprId, &'a Name)> {
+    let mut calls = Vec::new();
+    let mut stack = stmts
+        .iter()
+        .rev()
+        .copied()
+        .map(Either::Left)
+        .collect::<Vec<_>>();
+    while let Some(next) = stack.pop() {
+        let mut children = Vec::new();
+        match next {
+            Either::Left(stmt) => match &module[stmt] {
+                Stmt::If {
+                    test,
+                    if_stmts,
+                    elif_or_else_stmts,
+                } => {
+                    children.push(Either::Right(*test));
+                    children.extend(if_stmts.iter().copied().map(Either::Left));
+                    match elif_or_else_stmts {
+                        Some(Either::Left(stmt)) => children.push(Either::Left(*stmt)),
+                        Some(Either::Right(stmts)) => {
+                            children.extend(stmts.iter().copied().map(Either::Left))
+                        }
+                        None => {}
+                    }
+                }
+                Stmt::For {
+                    iterable,
+                    targets,
+                    stmts,
+                } => {
+                    children.push(Either::Right(*iterable));
+                    children.extend(targets.iter().copied().map(Either::Right));
+                    children.extend(stmts.iter().copied().map(Either::Left));
+                }
+                Stmt::Return { expr: Some(expr) } | Stmt::Expr { expr } => {
+                    children.push(Either::Right(*expr))
+                }
+                Stmt::Assign { lhs, rhs, .. } => {
+                    children.push(Either::Right(*lhs));
+                    children.push(Either::Right(*rhs));
+                }
+                _ => {}
+            },
+            Either::Right(expr) => match &module[expr] {
+                Expr::Lambda { .. } => {}
+                data => {
+                    if let Expr::Call { callee, .. } = data {
+                        if let Expr::Name { name } = &module[*callee] {
+                            calls.push((expr, *callee, name));
+                        }
+                    }
+                    data.walk_child_exprs(|expr| children.push(Either::Right(expr)));
+                }
+            },
+        }
+        stack.extend(children.into_iter().rev());
+    }
+    calls
+}
+
+/// Resolves the `def` statement of a function called by name, following `load()` statements.
+fn resolve_callee(db: &dyn Db, file: File, callee: ExprId, name: &Name) -> Option<InFile<StmtId>> {
+    let resolver = Resolver::new_for_expr(db, file, callee);
+    let (_, defs) = resolver.resolve_name(name)?;
+    match defs.last()?.def {
+        ScopeDef::Function(FunctionDef::Def { stmt, .. }) => Some(stmt.clone()),
+        ScopeDef::LoadItem(LoadItemDef { file, load_item }) => {
+            let (name, load_stmt) = match &module(db, *file)[*load_item] {
+                LoadItem::Direct { name, load_stmt } => (name, load_stmt),
+                LoadItem::Aliased {
+                    name, load_stmt, ..
+                } => (name, load_stmt),
+            };
+            let loaded_file = db
+                .load_file(load_stmt.module(db), file.dialect(db), file.id(db))
+                .ok()??;
+            match Resolver::resolve_export_in_file(db, loaded_file, &Name::from_str(name))? {
+                Export::Function(FunctionDef::Def { stmt, .. }) => Some(stmt),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The call graph of the functions reachable from a file.
+struct CallGraph<'a> {
+    functions: FxHashMap<InFile<StmtId>, &'a FunctionNode>,
+}
+
+impl<'a> CallGraph<'a> {
+    fn new(db: &'a dyn Db, roots: impl Iterator<Item = InFile<StmtId>>) -> Self {
+        let mut functions = FxHashMap::default();
+        let mut stack = roots.collect::<Vec<_>>();
+        while let Some(key) = stack.pop() {
+            if functions.contains_key(&key) {
+                continue;
+            }
+            let Some(node) = function_calls(db, key.file).functions(db).get(&key.value) else {
+                continue;
+            };
+            stack.extend(node.calls.iter().map(|edge| edge.callee.clone()));
+            functions.insert(key, node);
+        }
+        Self { functions }
+    }
+
+    fn calls(&self, key: &InFile<StmtId>) -> &'a [CallEdge] {
+        self.functions
+            .get(key)
+            .map(|node| &node.calls[..])
+            .unwrap_or_default()
+    }
+
+    fn name(&self, key: &InFile<StmtId>) -> &'a str {
+        self.functions
+            .get(key)
+            .map(|node| node.name.as_str())
+            .unwrap_or("<unknown>")
+    }
+
+    /// Returns the shortest sequence of calls leading from `from` to `to`, if there is one.
+    fn find_path(
+        &self,
+        from: &InFile<StmtId>,
+        to: &InFile<StmtId>,
+    ) -> Option<Vec<(InFile<StmtId>, &'a CallEdge)>> {
+        let mut predecessors: FxHashMap<InFile<StmtId>, (InFile<StmtId>, &CallEdge)> =
+            FxHashMap::default();
+        let mut queue = VecDeque::from([from.clone()]);
+        while let Some(key) = queue.pop_front() {
+            if &key == to {
+                let mut path = Vec::new();
+                let mut current = key;
+                while &current != from {
+                    let (caller, edge) = predecessors[&current].clone();
+                    path.push((caller.clone(), edge));
+                    current = caller;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for edge in self.calls(&key) {
+                if &edge.callee != from && !predecessors.contains_key(&edge.callee) {
+                    predecessors.insert(edge.callee.clone(), (key.clone(), edge));
+                    queue.push_back(edge.callee.clone());
+                }
+            }
+        }
+        None
+    }
+}
+
+#[salsa::tracked]
+pub(crate) struct RecursionDiagnostics {
+    #[return_ref]
+    pub(crate) diagnostics: Vec<Diagnostic>,
+}
+
+/// Reports every call in the given file that closes a cycle in the call graph.
+#[salsa::tracked]
+pub(crate) fn recursion_diagnostics(db: &dyn Db, file: File) -> RecursionDiagnostics {
+    let source_map = source_map(db, file);
+    let mut callers = function_calls(db, file)
+        .functions(db)
+        .keys()
+        .map(|stmt| InFile { file, value: *stmt })
+        .collect::<Vec<_>>();
+    callers.sort_by_key(|caller| {
+        source_map
+            .stmt_map_back
+            .get(&caller.value)
+            .map(|ptr| ptr.syntax_node_ptr().text_range().start())
+    });
+    let graph = CallGraph::new(db, callers.iter().cloned());
+
+    let mut diagnostics = Vec::new();
+    for caller in callers.iter() {
+        for edge in graph.calls(caller) {
+            let Some(path) = graph.find_path(&edge.callee, caller) else {
+                continue;
+            };
+
+            let cycle = std::iter::once(graph.name(caller))
+                .chain(path.iter().map(|(caller, _)| graph.name(caller)))
+                .chain(std::iter::once(graph.name(caller)))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            diagnostics.push(Diagnostic {
+                message: format!(
+                    "Recursive call to \"{}\", which is not allowed in Starlark: {}",
+                    graph.name(&edge.callee),
+                    cycle
+                ),
+                severity: Severity::Error,
+                range: FileRange {
+                    file_id: file.id(db),
+                    range: edge.range,
+                },
+                tags: None,
+                code: DiagnosticCode::Recursion,
+                related_information: (!path.is_empty()).then(|| {
+                    path.iter()
+                        .map(|(caller, edge)| DiagnosticRelatedInformation {
+                            range: FileRange {
+                                file_id: caller.file.id(db),
+                                range: edge.range,
+                            },
+                            message: format!(
+                                "\"{}\" calls \"{}\"",
+                                graph.name(caller),
+                                graph.name(&edge.callee)
+                            ),
+                        })
+                        .collect()
+                }),
+            });
+        }
+    }
+    RecursionDiagnostics::new(db, diagnostics)
+}
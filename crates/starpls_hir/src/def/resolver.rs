@@ -18,6 +18,7 @@ use crate::def::scope::Scopes;
 use crate::def::scope::VariableDef;
 use crate::def::ExprId;
 use crate::def::ModuleSourceMap;
+use crate::def::StmtId;
 use crate::source_map;
 use crate::typeck::builtins::builtin_globals;
 use crate::typeck::builtins::APIGlobals;
@@ -120,6 +121,37 @@ impl<'a> Resolver<'a> {
         Some((first_execution_scope, defs))
     }
 
+    /// Resolves a name to its latest definition at the top level of the module, skipping any
+    /// definitions in enclosing functions.
+    pub(crate) fn resolve_module_level_name(&self, name: &Name) -> Option<ScopeDef> {
+        self.scopes()
+            .filter(|scope| scope.execution_scope == ExecutionScopeId::Module)
+            .find_map(|scope| scope.defs.get(name)?.first().cloned())
+    }
+
+    /// Whether the current scope is in the body of a function or lambda, i.e. in code that runs
+    /// after the module has been loaded.
+    pub(crate) fn is_in_function(&self) -> bool {
+        self.scopes().any(|scope| {
+            matches!(
+                scope.execution_scope,
+                ExecutionScopeId::Def(_) | ExecutionScopeId::Lambda(_)
+            )
+        })
+    }
+
+    /// Returns the `def` statement whose body contains the current scope. Returns `None` at the
+    /// top level of the module and in lambdas, which might be called from anywhere.
+    pub(crate) fn enclosing_def_stmt(&self) -> Option<StmtId> {
+        self.scopes()
+            .find_map(|scope| match scope.execution_scope {
+                ExecutionScopeId::Def(stmt) => Some(Some(stmt)),
+                ExecutionScopeId::Lambda(_) => Some(None),
+                _ => None,
+            })
+            .flatten()
+    }
+
     pub(crate) fn resolve_name_in_prelude_or_builtins(&self, name: &Name) -> Option<ScopeDef> {
         let mut def = None;
 
@@ -5,12 +5,19 @@ use either::Either;
 use id_arena::Arena;
 use id_arena::Id;
 use rustc_hash::FxHashMap;
+use rustc_hash::FxHashSet;
+use starpls_bazel::APIContext;
+use starpls_common::parse;
 use starpls_common::Diagnostic;
+use starpls_common::DiagnosticCode;
 use starpls_common::Diagnostics;
 use starpls_common::File;
 use starpls_common::FileRange;
 use starpls_common::InFile;
 use starpls_common::Severity;
+use starpls_syntax::ast::AstNode;
+use starpls_syntax::ast::{self};
+use starpls_syntax::TextRange;
 
 use crate::def::CompClause;
 use crate::def::Expr;
@@ -98,6 +105,7 @@ pub(crate) enum FunctionDef {
     },
     Lambda {
         func: Function,
+        expr: InFile<ExprId>,
     },
 }
 
@@ -105,7 +113,7 @@ impl FunctionDef {
     pub(crate) fn func(&self) -> Function {
         match self {
             FunctionDef::Def { func, .. } => *func,
-            FunctionDef::Lambda { func } => *func,
+            FunctionDef::Lambda { func, .. } => *func,
         }
     }
 
@@ -148,6 +156,9 @@ pub(crate) struct Scopes {
     pub(crate) scopes: Arena<Scope>,
     pub(crate) scopes_by_hir_id: FxHashMap<ScopeHirId, ScopeId>,
     pub(crate) scopes_by_execution_scope_id: FxHashMap<ExecutionScopeId, ScopeId>,
+    /// Names that are referenced at the top level of the module before the global variable
+    /// that they refer to is assigned.
+    pub(crate) used_before_assignment: FxHashSet<ExprId>,
 }
 
 struct DeferredScope {
@@ -174,8 +185,11 @@ impl Scopes {
                 scopes: Default::default(),
                 scopes_by_hir_id: Default::default(),
                 scopes_by_execution_scope_id: Default::default(),
+                used_before_assignment: Default::default(),
             },
             curr_execution_scope: ExecutionScopeId::Module,
+            is_deferred: false,
+            module_name_refs: Vec::new(),
         }
         .collect()
     }
@@ -234,6 +248,11 @@ struct ScopeCollector<'a> {
     source_map: &'a ModuleSourceMap,
     scopes: Scopes,
     curr_execution_scope: ExecutionScopeId,
+    /// Whether the code being collected runs after the module has been evaluated, i.e. it is in
+    /// the body of a function.
+    is_deferred: bool,
+    /// Names referenced while the module is evaluated, along with the scopes they're in.
+    module_name_refs: Vec<(ExprId, ScopeId)>,
 }
 
 impl ScopeCollector<'_> {
@@ -250,8 +269,16 @@ impl ScopeCollector<'_> {
         self.scopes
             .scopes_by_hir_id
             .insert(ScopeHirId::Module, root);
+        self.check_module_name_refs(root);
+
+        // Bazel allows rebinding globals in BUILD, WORKSPACE, and MODULE.bazel files, but the
+        // Starlark specification doesn't.
+        if matches!(self.file.api_context(self.db), None | Some(APIContext::Bzl)) {
+            self.check_global_reassignments(root);
+        }
 
         // Compute deferred scopes. This mainly applies to function definitions.
+        self.is_deferred = true;
         while let Some(DeferredScope { parent, data }) = self.deferred.pop_front() {
             self.curr_execution_scope = ExecutionScopeId::Def(data.def_stmt);
             let scope = self.alloc_scope(parent);
@@ -426,33 +453,25 @@ impl ScopeCollector<'_> {
                     self.record_expr_scope(expr, current);
                 }
                 Expr::Missing => {}
-                _ => Diagnostics::push(
-                    self.db,
-                    Diagnostic {
-                        message: "Expression is not assignable".to_string(),
-                        severity: Severity::Error,
-                        range: FileRange {
-                            file_id: self.file.id(self.db),
-                            range: self
-                                .source_map
-                                .expr_map_back
-                                .get(&expr)
-                                .expect("expected expr to exist in source map")
-                                .syntax_node_ptr()
-                                .text_range(),
-                        },
-                        tags: None,
-                    },
+                _ => self.add_error_diagnostic(
+                    DiagnosticCode::InvalidAssignment,
+                    "Expression is not assignable".to_string(),
+                    self.expr_range(expr),
                 ),
             }
         } else {
             match &self.module[expr] {
                 Expr::Missing => {}
                 Expr::Name { .. } => {
+                    if !self.is_deferred {
+                        self.module_name_refs.push((expr, current));
+                    }
                     self.record_expr_scope(expr, current);
                 }
                 Expr::Lambda { func, body } => {
                     self.with_execution_scope(ExecutionScopeId::Lambda(expr), |this| {
+                        let prev_is_deferred = this.is_deferred;
+                        this.is_deferred = true;
                         let scope = this.alloc_scope(current);
                         for (index, param) in func.params(self.db).iter().copied().enumerate() {
                             match &this.module.params[param] {
@@ -470,6 +489,7 @@ impl ScopeCollector<'_> {
                         this.collect_expr(*body, scope, None);
                         this.finish_execution_scope(scope);
                         this.record_expr_scope(expr, current);
+                        this.is_deferred = prev_is_deferred;
                     });
                 }
                 Expr::Tuple { exprs } => {
@@ -517,6 +537,105 @@ impl ScopeCollector<'_> {
         }
     }
 
+    /// Reports names that are referenced while the module is evaluated, but that refer to a
+    /// global variable which is only assigned later on. Globals are visible throughout the
+    /// module, so these references don't fall back to builtins.
+    fn check_module_name_refs(&mut self, module_scope: ScopeId) {
+        for (expr, scope) in std::mem::take(&mut self.module_name_refs) {
+            let Expr::Name { name } = &self.module[expr] else {
+                continue;
+            };
+            let is_defined = |scope| {
+                self.scopes
+                    .scope_chain(Some(scope))
+                    .any(|scope| self.scopes.scopes[scope].defs.contains_key(name))
+            };
+            if !is_defined(scope) && is_defined(module_scope) {
+                self.add_error_diagnostic(
+                    DiagnosticCode::UsedBeforeAssignment,
+                    format!(
+                        "Global variable \"{}\" is referenced before assignment",
+                        name.as_str()
+                    ),
+                    self.expr_range(expr),
+                );
+                self.scopes.used_before_assignment.insert(expr);
+            }
+        }
+    }
+
+    /// Reports globals that are bound more than once, e.g. by two assignments or by a `load`
+    /// statement and an assignment.
+    fn check_global_reassignments(&self, module_scope: ScopeId) {
+        let mut bindings = self
+            .scopes
+            .scope_chain(Some(module_scope))
+            .flat_map(|scope| self.scopes.scopes[scope].defs.iter())
+            .flat_map(|(name, defs)| defs.iter().map(move |def| (name, def)))
+            .filter_map(|(name, def)| Some((name, self.def_range(def)?)))
+            .collect::<Vec<_>>();
+        bindings.sort_by_key(|(_, range)| range.start());
+
+        let mut seen = FxHashSet::default();
+        for (name, range) in bindings {
+            if !seen.insert(name) {
+                self.add_error_diagnostic(
+                    DiagnosticCode::GlobalReassignment,
+                    format!("Cannot reassign global \"{}\"", name.as_str()),
+                    range,
+                );
+            }
+        }
+    }
+
+    /// Returns the range of the name bound by the given definition.
+    fn def_range(&self, def: &ScopeDef) -> Option<TextRange> {
+        match def {
+            ScopeDef::Variable(def) => Some(self.expr_range(def.expr)),
+            ScopeDef::Function(FunctionDef::Def { stmt, .. }) => {
+                let node = self
+                    .source_map
+                    .stmt_map_back
+                    .get(&stmt.value)?
+                    .syntax_node_ptr()
+                    .try_to_node(&parse(self.db, self.file).syntax(self.db))?;
+                Some(ast::DefStmt::cast(node)?.name()?.syntax().text_range())
+            }
+            ScopeDef::LoadItem(def) => self
+                .source_map
+                .load_item_map_back
+                .get(&def.load_item)
+                .map(|ptr| ptr.syntax_node_ptr().text_range()),
+            _ => None,
+        }
+    }
+
+    fn expr_range(&self, expr: ExprId) -> TextRange {
+        self.source_map
+            .expr_map_back
+            .get(&expr)
+            .expect("expected expr to exist in source map")
+            .syntax_node_ptr()
+            .text_range()
+    }
+
+    fn add_error_diagnostic(&self, code: DiagnosticCode, message: String, range: TextRange) {
+        Diagnostics::push(
+            self.db,
+            Diagnostic {
+                message,
+                severity: Severity::Error,
+                range: FileRange {
+                    file_id: self.file.id(self.db),
+                    range,
+                },
+                tags: None,
+                code,
+                related_information: None,
+            },
+        );
+    }
+
     fn record_expr_scope(&mut self, expr: ExprId, scope: ScopeId) {
         self.scopes.scopes_by_hir_id.insert(expr.into(), scope);
     }
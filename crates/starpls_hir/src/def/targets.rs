@@ -0,0 +1,133 @@
+use rustc_hash::FxHashSet;
+use starpls_common::File;
+
+use crate::def::resolver::Export;
+use crate::def::resolver::Resolver;
+use crate::def::scope::ScopeDef;
+use crate::def::scope::VariableDef;
+use crate::def::Argument;
+use crate::def::Expr;
+use crate::def::ExprId;
+use crate::def::Literal;
+use crate::def::LoadItem;
+use crate::def::Name;
+use crate::def::Stmt;
+use crate::module;
+use crate::Db;
+
+/// The names of the targets and output files declared in a BUILD file.
+#[salsa::tracked]
+pub(crate) struct DeclaredTargets {
+    /// `None` if the declared names can't be determined statically, e.g. because targets are
+    /// declared in a list comprehension, with a computed name or by a macro.
+    #[return_ref]
+    pub(crate) names: Option<FxHashSet<String>>,
+}
+
+#[salsa::tracked]
+pub(crate) fn declared_targets(db: &dyn Db, file: File) -> DeclaredTargets {
+    let names = collect_declared_targets(db, file);
+    DeclaredTargets::new(db, names)
+}
+
+fn collect_declared_targets(db: &dyn Db, file: File) -> Option<FxHashSet<String>> {
+    let module = module(db, file);
+    let string_literal = |expr: ExprId| match &module[expr] {
+        Expr::Literal {
+            literal: Literal::String(s),
+        } => Some(s.value(db).to_string()),
+        _ => None,
+    };
+
+    let mut names = FxHashSet::default();
+    for stmt in module.top_level.iter() {
+        let expr = match &module[*stmt] {
+            Stmt::Expr { expr } => *expr,
+            Stmt::Assign { .. } | Stmt::Load { .. } | Stmt::Pass => continue,
+            _ => return None,
+        };
+        let Expr::Call { callee, args } = &module[expr] else {
+            return None;
+        };
+        let Expr::Name { name: callee } = &module[*callee] else {
+            return None;
+        };
+
+        // Calls without a `name` might still declare targets, e.g. `npm_link_all_packages()`.
+        let has_name = args
+            .iter()
+            .any(|arg| matches!(arg, Argument::Keyword { name, .. } if name.as_str() == "name"));
+        if !has_name {
+            if matches!(callee.as_str(), "package" | "licenses" | "exports_files") {
+                continue;
+            }
+            return None;
+        }
+        if !declares_only_named_target(db, file, callee) {
+            return None;
+        }
+
+        for arg in args.iter() {
+            match arg {
+                Argument::Keyword { name, expr } if name.as_str() == "name" => {
+                    names.insert(string_literal(*expr)?);
+                }
+                // Output files can also be referenced by label.
+                Argument::Keyword { name, expr } if matches!(name.as_str(), "out" | "outs") => {
+                    match &module[*expr] {
+                        Expr::List { exprs } => {
+                            for expr in exprs.iter() {
+                                names.insert(string_literal(*expr)?);
+                            }
+                        }
+                        _ => {
+                            names.insert(string_literal(*expr)?);
+                        }
+                    }
+                }
+                // The unpacked dict might set `name`.
+                Argument::UnpackedDict { .. } => return None,
+                _ => {}
+            }
+        }
+    }
+    Some(names)
+}
+
+/// Whether calling the given function only declares the target named by its `name` argument and
+/// its declared outputs. This holds for native rules and rules defined with `rule()`, but macros
+/// can declare any number of targets.
+fn declares_only_named_target(db: &dyn Db, file: File, callee: &Name) -> bool {
+    let def = match Resolver::new_for_module(db, file).resolve_module_level_name(callee) {
+        Some(ScopeDef::LoadItem(def)) => def,
+        // Native rules aren't defined in the BUILD file itself.
+        Some(_) => return false,
+        None => return true,
+    };
+    let (name, load_stmt) = match &module(db, def.file)[def.load_item] {
+        LoadItem::Direct { name, load_stmt } => (name, load_stmt),
+        LoadItem::Aliased {
+            name, load_stmt, ..
+        } => (name, load_stmt),
+    };
+    let Ok(Some(loaded_file)) = db.load_file(load_stmt.module(db), file.dialect(db), file.id(db))
+    else {
+        return false;
+    };
+    let Some(Export::Variable(VariableDef {
+        file: loaded_file,
+        source: Some(source),
+        ..
+    })) = Resolver::resolve_export_in_file(db, loaded_file, &Name::from_str(name))
+    else {
+        return false;
+    };
+    let loaded_module = module(db, loaded_file);
+    match &loaded_module[source] {
+        Expr::Call { callee, .. } => matches!(
+            &loaded_module[*callee],
+            Expr::Name { name } if name.as_str() == "rule"
+        ),
+        _ => false,
+    }
+}
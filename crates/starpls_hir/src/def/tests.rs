@@ -1,5 +1,9 @@
 use std::collections::HashSet;
+use std::fmt::Write;
 
+use expect_test::expect;
+use expect_test::Expect;
+use itertools::Itertools;
 use starpls_bazel::env::make_build_builtins;
 use starpls_bazel::env::make_bzl_builtins;
 use starpls_bazel::APIContext;
@@ -10,6 +14,7 @@ use starpls_common::FileInfo;
 use starpls_test_util::FixtureFile;
 
 use crate::def::resolver::Resolver;
+use crate::diagnostics_for_file;
 use crate::test_database::TestDatabase;
 use crate::typeck::intrinsics::intrinsic_functions;
 use crate::Db as _;
@@ -77,6 +82,35 @@ fn check_scope_full(fixture: &str, expected: &[&str], prelude: Option<&str>) {
     assert_eq!(expected, &actual[..]);
 }
 
+fn check_diagnostics(api_context: APIContext, input: &str, expect: Expect) {
+    let mut test_db: TestDatabase = Default::default();
+    let file = test_db.create_file(
+        FileId(0),
+        Dialect::Bazel,
+        Some(FileInfo::Bazel {
+            api_context,
+            is_external: false,
+        }),
+        input.to_string(),
+    );
+
+    let mut actual = String::new();
+    for diagnostic in diagnostics_for_file(&test_db, file)
+        .sorted_by_key(|diagnostic| diagnostic.range.range.start())
+    {
+        writeln!(
+            actual,
+            "{:?}..{:?} {}: {}",
+            diagnostic.range.range.start(),
+            diagnostic.range.range.end(),
+            diagnostic.code,
+            diagnostic.message
+        )
+        .unwrap();
+    }
+    expect.assert_eq(&actual);
+}
+
 #[test]
 fn smoke_test() {
     check_scope(
@@ -235,3 +269,50 @@ def f():
         ),
     )
 }
+
+#[test]
+fn test_build_file_restrictions() {
+    check_diagnostics(
+        APIContext::Build,
+        r#"
+load(":a.bzl", "a")
+a(*[1], **{})
+def f():
+    pass
+g = lambda: 1
+x = 1
+x = 2
+load(":b.bzl", "b")
+"#,
+        expect![[r#"
+            23..27 build-syntax: *args arguments are not allowed in BUILD files
+            29..33 build-syntax: **kwargs arguments are not allowed in BUILD files
+            39..40 build-syntax: Functions may not be defined in BUILD files
+            57..66 build-syntax: Functions may not be defined in BUILD files
+            79..98 load-on-top: Load statements must come before any other statement in BUILD files
+        "#]],
+    )
+}
+
+#[test]
+fn test_global_reassignment_and_use_before_assignment() {
+    check_diagnostics(
+        APIContext::Bzl,
+        r#"
+load(":a.bzl", "a")
+a = 1
+x = y
+y = 2
+
+def y():
+    return z
+
+z = 3
+"#,
+        expect![[r#"
+            21..22 global-reassignment: Cannot reassign global "a"
+            31..32 used-before-assignment: Global variable "y" is referenced before assignment
+            44..45 global-reassignment: Cannot reassign global "y"
+        "#]],
+    )
+}
@@ -0,0 +1,141 @@
+use starpls_common::File;
+
+use crate::def::Argument;
+use crate::def::Expr;
+use crate::def::ExprId;
+use crate::def::Literal;
+use crate::def::Module;
+use crate::def::Stmt;
+use crate::module;
+use crate::Db;
+
+/// A package specification accepted by `visibility()`, e.g. `//foo` or `//foo/...`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum PackageSpec {
+    Public,
+    Private,
+    Package { package: String, negated: bool },
+    Recursive { package: String, negated: bool },
+}
+
+impl PackageSpec {
+    fn parse(spec: &str) -> Option<PackageSpec> {
+        match spec {
+            "public" => return Some(PackageSpec::Public),
+            "private" => return Some(PackageSpec::Private),
+            _ => {}
+        }
+
+        let (spec, negated) = match spec.strip_prefix('-') {
+            Some(spec) => (spec, true),
+            None => (spec, false),
+        };
+        let package = spec.strip_prefix("//")?;
+        Some(if package == "..." {
+            PackageSpec::Recursive {
+                package: String::new(),
+                negated,
+            }
+        } else if let Some(package) = package.strip_suffix("/...") {
+            PackageSpec::Recursive {
+                package: package.to_string(),
+                negated,
+            }
+        } else {
+            PackageSpec::Package {
+                package: package.to_string(),
+                negated,
+            }
+        })
+    }
+}
+
+/// The load visibility of a `.bzl` file, as declared by a top-level `visibility()` call.
+#[salsa::tracked]
+pub(crate) struct LoadVisibility {
+    /// `None` if the file doesn't declare a visibility, or if it can't be determined statically.
+    #[return_ref]
+    pub(crate) specs: Option<Box<[PackageSpec]>>,
+}
+
+impl LoadVisibility {
+    /// Returns whether a file in `package` may load the `.bzl` file in `bzl_package`. Both
+    /// packages must belong to the same repository.
+    pub(crate) fn is_visible_to(&self, db: &dyn Db, bzl_package: &str, package: &str) -> bool {
+        let Some(specs) = self.specs(db) else {
+            return true;
+        };
+        if bzl_package == package {
+            return true;
+        }
+
+        let mut visible = false;
+        for spec in specs.iter() {
+            let (matches, negated) = match spec {
+                PackageSpec::Public => (true, false),
+                PackageSpec::Private => (false, false),
+                PackageSpec::Package {
+                    package: spec_package,
+                    negated,
+                } => (spec_package == package, *negated),
+                PackageSpec::Recursive {
+                    package: spec_package,
+                    negated,
+                } => (
+                    spec_package.is_empty()
+                        || package == spec_package
+                        || package
+                            .strip_prefix(spec_package.as_str())
+                            .is_some_and(|rest| rest.starts_with('/')),
+                    *negated,
+                ),
+            };
+
+            // Negated specs take precedence over everything else.
+            if matches && negated {
+                return false;
+            }
+            visible |= matches;
+        }
+        visible
+    }
+}
+
+#[salsa::tracked]
+pub(crate) fn load_visibility(db: &dyn Db, file: File) -> LoadVisibility {
+    let specs = collect_visibility_specs(db, module(db, file));
+    LoadVisibility::new(db, specs)
+}
+
+fn collect_visibility_specs(db: &dyn Db, module: &Module) -> Option<Box<[PackageSpec]>> {
+    let string_literal = |expr: ExprId| match &module[expr] {
+        Expr::Literal {
+            literal: Literal::String(s),
+        } => PackageSpec::parse(s.value(db)),
+        _ => None,
+    };
+
+    let args = module.top_level.iter().find_map(|stmt| {
+        let Stmt::Expr { expr } = &module[*stmt] else {
+            return None;
+        };
+        let Expr::Call { callee, args } = &module[*expr] else {
+            return None;
+        };
+        match &module[*callee] {
+            Expr::Name { name } if name.as_str() == "visibility" => Some(args),
+            _ => None,
+        }
+    })?;
+
+    let [Argument::Simple { expr }] = &args[..] else {
+        return None;
+    };
+    match &module[*expr] {
+        Expr::List { exprs } => exprs
+            .iter()
+            .map(|expr| string_literal(*expr))
+            .collect::<Option<_>>(),
+        _ => Some(vec![string_literal(*expr)?].into_boxed_slice()),
+    }
+}
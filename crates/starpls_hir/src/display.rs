@@ -142,8 +142,12 @@ impl DisplayWithDb for TyKind {
                 return f.write_char(']');
             }
             TyKind::Range => "range",
-            TyKind::Function(def) => {
+            TyKind::Function(def) | TyKind::Lambda(def, _, _) => {
                 let module = module(db, def.func().file(db));
+                let lambda_tys = match self {
+                    TyKind::Lambda(_, param_tys, ret_ty) => Some((param_tys, ret_ty)),
+                    _ => None,
+                };
                 write!(f, "def {}(", def.func().name(db).as_str())?;
                 for (i, param) in def
                     .func()
@@ -156,7 +160,11 @@ impl DisplayWithDb for TyKind {
                         f.write_str(", ")?;
                     }
 
-                    let format_type_ref = |f, type_ref| {
+                    let format_type_ref = |f: &mut fmt::Formatter<'_>, type_ref: &TypeRef| {
+                        // Type variables are shown by name, since they're only bound at call sites.
+                        if type_ref.has_type_vars() {
+                            return write!(f, "{}", type_ref);
+                        }
                         with_tcx(db, |tcx| resolve_type_ref(tcx, type_ref, def.stmt()).0).fmt(db, f)
                     };
 
@@ -166,6 +174,12 @@ impl DisplayWithDb for TyKind {
                             if let Some(type_ref) = type_ref.as_ref() {
                                 f.write_str(": ")?;
                                 format_type_ref(f, type_ref)?;
+                            } else if let Some((param_tys, _)) = lambda_tys {
+                                // Lambda parameters may have types inferred from their usage.
+                                if !param_tys[i].is_unknown() {
+                                    f.write_str(": ")?;
+                                    param_tys[i].fmt(db, f)?;
+                                }
                             }
                         }
                         HirDefParam::ArgsList { name, type_ref, .. } => {
@@ -192,6 +206,10 @@ impl DisplayWithDb for TyKind {
                         }
                     }
                 }
+                if let Some((_, ret_ty)) = lambda_tys {
+                    f.write_str(") -> ")?;
+                    return ret_ty.fmt(db, f);
+                }
                 return write!(
                     f,
                     ") -> {}",
@@ -280,6 +298,16 @@ impl DisplayWithDb for TyKind {
                 let (name, ty) = match proto {
                     Protocol::Iterable(ty) => ("Iterable", ty),
                     Protocol::Sequence(ty) => ("Sequence", ty),
+                    Protocol::Callable(param_tys, ret_ty) => {
+                        f.write_str("Callable[[")?;
+                        for (i, ty) in param_tys.iter().enumerate() {
+                            if i > 0 {
+                                f.write_str(", ")?;
+                            }
+                            write!(f, "{}", ty.display(db).alt())?;
+                        }
+                        return write!(f, "], {}]", ret_ty.display(db).alt());
+                    }
                 };
                 return write!(f, "{}[{}]", name, ty.display(db).alt());
             }
@@ -314,7 +342,7 @@ impl DisplayWithDb for TyKind {
 
     fn fmt_alt(&self, db: &dyn Db, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            TyKind::Function(_) => f.write_str("function"),
+            TyKind::Function(_) | TyKind::Lambda(_, _, _) => f.write_str("function"),
             TyKind::IntrinsicFunction(_, _) | TyKind::BuiltinFunction(_) => {
                 f.write_str("builtin_function_or_method")
             }
@@ -5,6 +5,7 @@ use def::scope;
 use def::scope::module_scopes;
 use def::scope::FunctionDef;
 use def::scope::ParameterDef;
+use def::Expr;
 use def::Function;
 use def::LoadItemId;
 use def::Stmt;
@@ -24,6 +25,7 @@ use starpls_syntax::ast::AstPtr;
 use starpls_syntax::ast::SyntaxNodePtr;
 use starpls_syntax::TextSize;
 use starpls_syntax::T;
+use typeck::assign_tys;
 use typeck::builtins::BuiltinFunction;
 use typeck::intrinsics::IntrinsicFunction;
 use typeck::resolve_type_ref;
@@ -81,6 +83,14 @@ pub struct Jar(
     def::scope::ModuleScopes,
     def::scope::module_scopes,
     def::scope::module_scopes_query,
+    def::recursion::FunctionCalls,
+    def::recursion::function_calls,
+    def::recursion::RecursionDiagnostics,
+    def::recursion::recursion_diagnostics,
+    def::targets::DeclaredTargets,
+    def::targets::declared_targets,
+    def::visibility::LoadVisibility,
+    def::visibility::load_visibility,
     typeck::builtins::BuiltinDefs,
     typeck::builtins::BuiltinFunction,
     typeck::builtins::BuiltinGlobals,
@@ -124,6 +134,13 @@ pub fn diagnostics_for_file(db: &dyn Db, file: File) -> impl Iterator<Item = Dia
     module_scopes::accumulated::<Diagnostics>(db, file).into_iter()
 }
 
+/// Returns the diagnostics for recursive calls made by the functions in the given file.
+pub fn recursion_diagnostics(db: &dyn Db, file: File) -> Vec<Diagnostic> {
+    def::recursion::recursion_diagnostics(db, file)
+        .diagnostics(db)
+        .clone()
+}
+
 pub struct Semantics<'a> {
     pub db: &'a dyn Db,
 }
@@ -198,7 +215,14 @@ impl<'a> Semantics<'a> {
     pub fn resolve_call_expr(&self, file: File, expr: &ast::CallExpr) -> Option<Callable> {
         let ty = self.type_of_expr(file, &expr.callee()?)?;
         Some(match ty.ty.kind() {
-            TyKind::Function(def) => def.clone().into(),
+            TyKind::Function(def) => {
+                let subst = source_map(self.db, file)
+                    .expr_map
+                    .get(&AstPtr::new(&ast::Expression::Call(expr.clone())))
+                    .and_then(|call| with_tcx(self.db, |tcx| tcx.call_substitution(file, *call)));
+                Callable(CallableInner::HirDef(def.clone(), subst))
+            }
+            TyKind::Lambda(def, _, _) => def.clone().into(),
             TyKind::IntrinsicFunction(func, subst) => {
                 Callable(CallableInner::IntrinsicFunction(*func, Some(subst.clone())))
             }
@@ -222,10 +246,13 @@ impl<'a> Semantics<'a> {
         let Stmt::Def { func, .. } = module[*stmt] else {
             return None;
         };
-        Some(Callable(CallableInner::HirDef(FunctionDef::Def {
-            func,
-            stmt: InFile { file, value: *stmt },
-        })))
+        Some(Callable(CallableInner::HirDef(
+            FunctionDef::Def {
+                func,
+                stmt: InFile { file, value: *stmt },
+            },
+            None,
+        )))
     }
 
     pub fn type_of_expr(&self, file: File, expr: &ast::Expression) -> Option<Type> {
@@ -245,6 +272,15 @@ impl<'a> Semantics<'a> {
             .and_then(|(stmt, index)| match module[*stmt] {
                 Stmt::Def { func, .. } => Some((func, index)),
                 _ => None,
+            })
+            .or_else(|| {
+                module
+                    .param_to_lambda_expr
+                    .get(param)
+                    .and_then(|(expr, index)| match module[*expr] {
+                        Expr::Lambda { func, .. } => Some((func, index)),
+                        _ => None,
+                    })
             })?;
         Some((
             Param(ParamInner::Param {
@@ -306,6 +342,29 @@ impl<'a> Semantics<'a> {
         })
     }
 
+    /// Returns the type of the parameter that the given argument expression is passed to.
+    pub fn expected_type_of_argument(&self, file: File, expr: &ast::Expression) -> Option<Type> {
+        let call_expr = expr
+            .syntax()
+            .parent()
+            .and_then(ast::Argument::cast)?
+            .syntax()
+            .parent()
+            .and_then(ast::Arguments::cast)?
+            .syntax()
+            .parent()
+            .and_then(ast::CallExpr::cast)?;
+        let source_map = source_map(self.db, file);
+        let call_expr = source_map
+            .expr_map
+            .get(&AstPtr::new(&ast::Expression::Call(call_expr)))?;
+        let arg_expr = source_map.expr_map.get(&AstPtr::new(expr))?;
+        with_tcx(self.db, |tcx| {
+            tcx.expected_type_of_argument(file, *call_expr, *arg_expr)
+        })
+        .map(Into::into)
+    }
+
     pub fn def_for_load_item(&self, load_item: &LoadItem) -> Option<ScopeDef> {
         let load_stmt = load_item.load_stmt(self.db)?;
         let loaded_file = self.resolve_load_stmt(load_item.id.file, &load_stmt)?;
@@ -359,7 +418,10 @@ impl Type {
     pub fn is_function(&self) -> bool {
         matches!(
             self.ty.kind(),
-            TyKind::Function(_) | TyKind::BuiltinFunction(_) | TyKind::IntrinsicFunction(_, _)
+            TyKind::Function(_)
+                | TyKind::Lambda(_, _, _)
+                | TyKind::BuiltinFunction(_)
+                | TyKind::IntrinsicFunction(_, _)
         )
     }
 
@@ -379,8 +441,50 @@ impl Type {
         self.ty.kind() == &TyKind::Unknown
     }
 
+    pub fn is_string(&self) -> bool {
+        matches!(self.ty.kind(), TyKind::String(_))
+    }
+
+    pub fn is_int(&self) -> bool {
+        matches!(self.ty.kind(), TyKind::Int(_))
+    }
+
+    /// Returns whether this is a number or a boolean, i.e. a value that can be converted to a
+    /// string with `str()` without changing its meaning.
+    pub fn is_scalar(&self) -> bool {
+        matches!(
+            self.ty.kind(),
+            TyKind::Int(_) | TyKind::Float | TyKind::Bool(_)
+        )
+    }
+
+    /// Returns whether this is a union that includes `None`.
+    pub fn is_optional(&self) -> bool {
+        match self.ty.kind() {
+            TyKind::Union(tys) => tys.iter().any(|ty| ty.kind() == &TyKind::None),
+            _ => false,
+        }
+    }
+
+    pub fn is_assignable_to(&self, db: &dyn Db, target: &Type) -> bool {
+        assign_tys(db, &self.ty, &target.ty)
+    }
+
+    /// Returns whether a string can be assigned to this type.
+    pub fn accepts_string(&self, db: &dyn Db) -> bool {
+        assign_tys(db, &TyKind::String(None).intern(), &self.ty)
+    }
+
+    /// Returns whether an integer can be assigned to this type.
+    pub fn accepts_int(&self, db: &dyn Db) -> bool {
+        assign_tys(db, &TyKind::Int(None).intern(), &self.ty)
+    }
+
     pub fn is_user_defined_function(&self) -> bool {
-        matches!(self.ty.kind(), TyKind::Function(_))
+        matches!(
+            self.ty.kind(),
+            TyKind::Function(_) | TyKind::Lambda(_, _, _)
+        )
     }
 
     pub fn params(&self, db: &dyn Db) -> Vec<(Param, Type)> {
@@ -394,7 +498,9 @@ impl Type {
         match self.ty.kind() {
             TyKind::BuiltinFunction(func) => Some(func.doc(db).clone()),
             TyKind::BuiltinType(ty, _) => Some(ty.doc(db).clone()),
-            TyKind::Function(def) => def.func().doc(db).map(|doc| doc.to_string()),
+            TyKind::Function(def) | TyKind::Lambda(def, _, _) => {
+                def.func().doc(db).map(|doc| doc.to_string())
+            }
             TyKind::IntrinsicFunction(func, _) => Some(func.doc(db).clone()),
             TyKind::Rule(rule) => rule.doc.as_ref().map(Box::to_string),
             TyKind::Provider(provider) | TyKind::ProviderInstance(provider) => provider.doc(db),
@@ -478,6 +584,13 @@ impl Type {
         }
     }
 
+    pub fn list_element_ty(&self) -> Option<Type> {
+        match self.ty.kind() {
+            TyKind::List(ty) => Some(ty.clone().into()),
+            _ => None,
+        }
+    }
+
     pub fn variable_tuple_element_ty(&self) -> Option<Type> {
         match self.ty.kind() {
             TyKind::Tuple(Tuple::Variable(ty)) => Some(ty.clone().into()),
@@ -550,7 +663,7 @@ pub struct Callable(CallableInner);
 impl Callable {
     pub fn name(&self, db: &dyn Db) -> Name {
         match self.0 {
-            CallableInner::HirDef(ref def) => def.func().name(db),
+            CallableInner::HirDef(ref def, _) => def.func().name(db),
             CallableInner::IntrinsicFunction(func, _) => func.name(db),
             CallableInner::BuiltinFunction(func) => func.name(db),
             CallableInner::Rule(_) => Name::new_inline("rule"),
@@ -570,7 +683,10 @@ impl Callable {
 
     pub fn ty(&self, db: &dyn Db) -> Type {
         match self.0 {
-            CallableInner::HirDef(ref def) => TyKind::Function(def.clone()).intern(),
+            CallableInner::HirDef(FunctionDef::Lambda { ref expr, .. }, _) => {
+                with_tcx(db, |tcx| tcx.infer_expr(expr.file, expr.value))
+            }
+            CallableInner::HirDef(ref def, _) => TyKind::Function(def.clone()).intern(),
             CallableInner::IntrinsicFunction(func, ref subst) => TyKind::IntrinsicFunction(
                 func,
                 subst
@@ -591,6 +707,9 @@ impl Callable {
     }
 
     pub fn ret_ty(&self, db: &dyn Db) -> Type {
+        if let CallableInner::HirDef(ref def @ FunctionDef::Def { .. }, Some(ref subst)) = self.0 {
+            return with_tcx(db, |tcx| tcx.infer_function_ret_ty(def, &subst.args)).into();
+        }
         self.ty(db)
             .ty
             .ret_ty(db)
@@ -600,7 +719,7 @@ impl Callable {
 
     pub fn doc(&self, db: &dyn Db) -> Option<String> {
         match self.0 {
-            CallableInner::HirDef(ref def) => def.func().doc(db).map(|doc| doc.to_string()),
+            CallableInner::HirDef(ref def, _) => def.func().doc(db).map(|doc| doc.to_string()),
             CallableInner::BuiltinFunction(func) => Some(func.doc(db).clone()),
             CallableInner::IntrinsicFunction(func, _) => Some(func.doc(db).clone()),
             CallableInner::Rule(ref rule) => rule.doc.as_ref().map(Box::to_string),
@@ -620,13 +739,13 @@ impl Callable {
 
     pub fn file(&self) -> Option<File> {
         match self.0 {
-            CallableInner::HirDef(ref def) => def.stmt().map(|stmt| stmt.file),
+            CallableInner::HirDef(ref def, _) => def.stmt().map(|stmt| stmt.file),
             _ => None,
         }
     }
 
     pub fn is_user_defined(&self) -> bool {
-        matches!(self.0, CallableInner::HirDef(_))
+        matches!(self.0, CallableInner::HirDef(..))
     }
 
     pub fn is_rule(&self) -> bool {
@@ -662,7 +781,7 @@ impl Callable {
 
 impl From<FunctionDef> for Callable {
     fn from(def: FunctionDef) -> Self {
-        Self(CallableInner::HirDef(def))
+        Self(CallableInner::HirDef(def, None))
     }
 }
 
@@ -675,8 +794,9 @@ impl From<BuiltinFunction> for Callable {
 /// Reperesents different types of callables.
 #[derive(Clone, Debug, PartialEq, Eq)]
 enum CallableInner {
-    /// A user-defined function.
-    HirDef(FunctionDef),
+    /// A user-defined function, along with the types bound to its type variables at a call site,
+    /// if known.
+    HirDef(FunctionDef, Option<Substitution>),
 
     // An intrinsic function, i.e. a function defined by the Starlark spec.
     IntrinsicFunction(IntrinsicFunction, Option<Substitution>),
@@ -782,7 +902,7 @@ pub enum ScopeDef {
 impl ScopeDef {
     pub fn syntax_node_ptr(&self, db: &dyn Db) -> Option<InFile<SyntaxNodePtr>> {
         match self {
-            ScopeDef::Callable(Callable(CallableInner::HirDef(def))) => {
+            ScopeDef::Callable(Callable(CallableInner::HirDef(def, _))) => {
                 Some(def.func().syntax_node_ptr(db))
             }
             ScopeDef::Variable(Variable { expr: Some(expr) }) => source_map(db, expr.file)
@@ -10,7 +10,9 @@ use starpls_bazel::Builtins;
 use starpls_common::File;
 use starpls_common::FileId;
 use starpls_common::FileInfo;
+use starpls_common::LabelResolution;
 use starpls_common::LoadItemCandidate;
+use starpls_common::PackageResolution;
 use starpls_common::ResolvedPath;
 use starpls_syntax::TextRange;
 use starpls_syntax::TextSize;
@@ -92,9 +94,17 @@ impl starpls_common::Db for TestDatabase {
         Ok(None)
     }
 
+    fn resolve_label(&self, _label: &str, _from: FileId) -> LabelResolution {
+        LabelResolution::new(self, None)
+    }
+
     fn resolve_build_file(&self, _file_id: FileId) -> Option<String> {
         None
     }
+
+    fn resolve_package(&self, _file_id: FileId) -> PackageResolution {
+        PackageResolution::new(self, None)
+    }
 }
 
 impl crate::Db for TestDatabase {
@@ -202,7 +212,11 @@ impl Fixture {
         // TODO(withered-magic): Make this a little bit nicer.
         let functions = vec!["provider", "rule", "struct"];
         let globals = vec![("attr", "attr")];
-        let types = vec![FixtureType::new("attr", vec![], vec!["int", "string"])];
+        let types = vec![FixtureType::new(
+            "attr",
+            vec![],
+            vec!["bool", "int", "label", "label_list", "string"],
+        )];
         db.set_builtin_defs(
             Dialect::Bazel,
             make_test_builtins(functions, globals, types),
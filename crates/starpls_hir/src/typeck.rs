@@ -1,14 +1,19 @@
 use std::fmt::Write;
+use std::hash::Hash;
+use std::hash::Hasher;
 use std::iter;
 use std::panic::UnwindSafe;
 use std::panic::{self};
 use std::sync::Arc;
+use std::thread;
 
 use crossbeam::atomic::AtomicCell;
 use either::Either;
 use parking_lot::Mutex;
+use parking_lot::MutexGuard;
 use rustc_hash::FxHashMap;
 use rustc_hash::FxHashSet;
+use rustc_hash::FxHasher;
 use smallvec::smallvec;
 use smallvec::SmallVec;
 use starpls_common::parse;
@@ -49,8 +54,10 @@ use crate::Name;
 use crate::Param;
 use crate::ParamInner;
 
+mod assign;
 mod call;
 mod infer;
+mod narrow;
 
 #[cfg(test)]
 mod tests;
@@ -163,6 +170,7 @@ pub struct InferenceOptions {
     pub infer_ctx_attributes: bool,
     pub use_code_flow_analysis: bool,
     pub allow_unused_definitions: bool,
+    pub strict_type_checking: bool,
 }
 
 #[derive(Default)]
@@ -200,6 +208,62 @@ impl TypeRef {
     pub(crate) fn is_unknown(&self) -> bool {
         self == &Self::Unknown
     }
+
+    /// Collects the type variables referenced by this type, e.g. `T` in `list[T]`, in the order
+    /// in which they first appear. By convention, type variables are named with a single
+    /// uppercase letter, optionally followed by digits, e.g. `T`, `K` or `T1`.
+    pub(crate) fn collect_type_vars(&self, type_vars: &mut Vec<Name>) {
+        match self {
+            TypeRef::Path(segments, None) if segments.len() == 1 => {
+                let name = &segments[0];
+                if is_type_var_name(name) && !type_vars.contains(name) {
+                    type_vars.push(name.clone());
+                }
+            }
+            TypeRef::Name(_, Some(args)) | TypeRef::Path(_, Some(args)) => {
+                args.iter()
+                    .for_each(|type_ref| type_ref.collect_type_vars(type_vars));
+            }
+            TypeRef::Union(type_refs) => type_refs
+                .iter()
+                .for_each(|type_ref| type_ref.collect_type_vars(type_vars)),
+            _ => {}
+        }
+    }
+
+    /// Collects the names that the type reference refers to, e.g. `foo` and `Bar` for
+    /// `foo.Provider | list[Bar]`.
+    pub(crate) fn collect_names(&self, names: &mut Vec<Name>) {
+        match self {
+            TypeRef::Name(name, args) => {
+                names.push(name.clone());
+                args.iter()
+                    .flat_map(|args| args.iter())
+                    .for_each(|type_ref| type_ref.collect_names(names));
+            }
+            TypeRef::Path(segments, args) => {
+                names.extend(segments.first().cloned());
+                args.iter()
+                    .flat_map(|args| args.iter())
+                    .for_each(|type_ref| type_ref.collect_names(names));
+            }
+            TypeRef::Union(type_refs) => type_refs
+                .iter()
+                .for_each(|type_ref| type_ref.collect_names(names)),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn has_type_vars(&self) -> bool {
+        let mut type_vars = Vec::new();
+        self.collect_type_vars(&mut type_vars);
+        !type_vars.is_empty()
+    }
+}
+
+fn is_type_var_name(name: &Name) -> bool {
+    let mut chars = name.as_str().chars();
+    chars.next().is_some_and(|c| c.is_ascii_uppercase()) && chars.all(|c| c.is_ascii_digit())
 }
 
 impl std::fmt::Display for TypeRef {
@@ -436,17 +500,28 @@ impl Ty {
         db: &'a dyn Db,
     ) -> Option<impl Iterator<Item = (Param, Ty)> + 'a> {
         Some(match self.kind() {
-            TyKind::Function(def) => Params::Simple(def.func().params(db).iter().enumerate().map(
-                |(index, param)| {
-                    let file = def.func().file(db);
-                    let ty = with_tcx(db, |tcx| tcx.infer_param(file, *param));
-                    let param = Param(ParamInner::Param {
-                        func: def.func(),
-                        index,
-                    });
-                    (param, ty)
-                },
-            )),
+            TyKind::Function(def) | TyKind::Lambda(def, _, _) => {
+                let lambda_param_tys = match self.kind() {
+                    TyKind::Lambda(_, param_tys, _) => Some(param_tys),
+                    _ => None,
+                };
+                Params::Simple(def.func().params(db).iter().enumerate().map(
+                    move |(index, param)| {
+                        let ty = match lambda_param_tys {
+                            Some(param_tys) => param_tys[index].clone(),
+                            None => {
+                                let file = def.func().file(db);
+                                with_tcx(db, |tcx| tcx.infer_param(file, *param))
+                            }
+                        };
+                        let param = Param(ParamInner::Param {
+                            func: def.func(),
+                            index,
+                        });
+                        (param, ty)
+                    },
+                ))
+            }
             TyKind::IntrinsicFunction(func, subst) => {
                 Params::Intrinsic(func.params(db).iter().enumerate().map(|(index, param)| {
                     let ty = param
@@ -607,7 +682,13 @@ impl Ty {
 
     pub(crate) fn ret_ty(&self, db: &dyn Db) -> Option<Ty> {
         Some(match self.kind() {
-            TyKind::Function(def) => resolve_builtin_type_ref_opt(db, def.func().ret_type_ref(db)),
+            TyKind::Lambda(_, _, ret_ty) => ret_ty.clone(),
+            TyKind::Function(def) => {
+                let num_vars = def.func().type_vars(db).len();
+                with_tcx(db, |tcx| {
+                    tcx.infer_function_ret_ty(def, &vec![Ty::unknown(); num_vars])
+                })
+            }
             TyKind::IntrinsicFunction(func, subst) => func.ret_ty(db).substitute(&subst.args),
             TyKind::BuiltinFunction(func) => resolve_builtin_type_ref(db, func.ret_type_ref(db)).0,
             TyKind::Provider(provider) | TyKind::ProviderRawConstructor(_, provider) => {
@@ -696,8 +777,7 @@ impl Ty {
         self.kind() == &TyKind::Any
     }
 
-    #[allow(unused)]
-    fn is_unknown(&self) -> bool {
+    pub(crate) fn is_unknown(&self) -> bool {
         self.kind() == &TyKind::Unknown || self.kind() == &TyKind::Unbound
     }
 
@@ -731,11 +811,48 @@ impl Ty {
             TyKind::IntrinsicFunction(data, subst) => {
                 TyKind::IntrinsicFunction(*data, subst.substitute(args)).intern()
             }
+            TyKind::Protocol(Protocol::Iterable(ty)) => {
+                TyKind::Protocol(Protocol::Iterable(ty.substitute(args))).intern()
+            }
+            TyKind::Protocol(Protocol::Sequence(ty)) => {
+                TyKind::Protocol(Protocol::Sequence(ty.substitute(args))).intern()
+            }
+            TyKind::Protocol(Protocol::Callable(param_tys, ret_ty)) => {
+                TyKind::Protocol(Protocol::Callable(
+                    param_tys.iter().map(|ty| ty.substitute(args)).collect(),
+                    ret_ty.substitute(args),
+                ))
+                .intern()
+            }
+            TyKind::Union(tys) if tys.iter().any(Ty::contains_bound_vars) => {
+                Ty::union(tys.iter().map(|ty| ty.substitute(args)))
+            }
             TyKind::BoundVar(index) => args[*index].clone(),
             _ => self.clone(),
         }
     }
 
+    fn contains_bound_vars(&self) -> bool {
+        match self.kind() {
+            TyKind::BoundVar(_) => true,
+            TyKind::List(ty)
+            | TyKind::Tuple(Tuple::Variable(ty))
+            | TyKind::Protocol(Protocol::Iterable(ty) | Protocol::Sequence(ty)) => {
+                ty.contains_bound_vars()
+            }
+            TyKind::Tuple(Tuple::Simple(tys)) | TyKind::Union(tys) => {
+                tys.iter().any(Ty::contains_bound_vars)
+            }
+            TyKind::Dict(key_ty, value_ty, _) => {
+                key_ty.contains_bound_vars() || value_ty.contains_bound_vars()
+            }
+            TyKind::Protocol(Protocol::Callable(param_tys, ret_ty)) => {
+                param_tys.iter().any(Ty::contains_bound_vars) || ret_ty.contains_bound_vars()
+            }
+            _ => false,
+        }
+    }
+
     pub(crate) fn known_keys(&self) -> Option<&[(InternedString, Ty)]> {
         match self.kind() {
             TyKind::Dict(_, _, known_keys) => known_keys.as_ref().map(|lit| &*lit.known_keys),
@@ -899,7 +1016,6 @@ impl Param {
 
     pub fn is_args_list(&self, db: &dyn Db) -> bool {
         match self.0 {
-            // TODO(withered-magic): Handle lambda parameters.
             ParamInner::Param { func, index } => {
                 let module = module(db, func.file(db));
                 matches!(module[func.params(db)[index]], HirDefParam::ArgsList { .. })
@@ -918,7 +1034,6 @@ impl Param {
 
     pub fn is_kwargs_dict(&self, db: &dyn Db) -> bool {
         match self.0 {
-            // TODO(withered-magic): Handle lambda parameters.
             ParamInner::Param { func, index } => {
                 let module = module(db, func.file(db));
                 matches!(
@@ -1281,6 +1396,9 @@ pub(crate) enum TyKind {
     /// A user-defined function.
     Function(FunctionDef),
 
+    /// A lambda, along with the types inferred for its parameters and body.
+    Lambda(FunctionDef, Box<[Ty]>, Ty),
+
     /// A function predefined by the Starlark specification.
     IntrinsicFunction(IntrinsicFunction, Substitution),
 
@@ -1367,12 +1485,43 @@ pub enum AttributeKind {
     StringListDict,
 }
 
+impl AttributeKind {
+    /// Returns where label strings appear in values of this kind of attribute, if anywhere.
+    pub(crate) fn label_position(&self) -> Option<LabelPosition> {
+        Some(match self {
+            AttributeKind::Label => LabelPosition::Value,
+            AttributeKind::LabelList => LabelPosition::ListElement,
+            AttributeKind::LabelKeyedStringDict => LabelPosition::DictKey,
+            AttributeKind::StringKeyedLabelDict => LabelPosition::DictValue,
+            _ => return None,
+        })
+    }
+}
+
+/// Where label strings appear in the value of an attribute or parameter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum LabelPosition {
+    /// The value itself is a label, e.g. `attr.label()`.
+    Value,
+    /// The value is a list of labels, e.g. `attr.label_list()`.
+    ListElement,
+    /// The value is a dict keyed by labels, e.g. `attr.label_keyed_string_dict()`.
+    DictKey,
+    /// The value is a dict with label values, e.g. `attr.string_keyed_label_dict()`.
+    DictValue,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Attribute {
     pub kind: AttributeKind,
     pub doc: Option<InternedString>,
     pub mandatory: bool,
     pub default_value: Option<Either<InFile<SyntaxNodePtr>, InternedString>>,
+    /// Whether the attribute's value can be set with `select()`.
+    pub configurable: bool,
+    /// The values allowed for this attribute, as specified by `values` in the attribute's
+    /// definition. Each value is a literal type, e.g. `String(Some(..))`.
+    pub values: Option<Box<[Ty]>>,
 }
 
 impl Attribute {
@@ -1381,12 +1530,16 @@ impl Attribute {
         doc: Option<InternedString>,
         mandatory: bool,
         default_value: Option<Either<InFile<SyntaxNodePtr>, InternedString>>,
+        configurable: bool,
+        values: Option<Box<[Ty]>>,
     ) -> Self {
         Self {
             kind,
             doc,
             mandatory,
             default_value,
+            configurable,
+            values,
         }
     }
 
@@ -1669,6 +1822,94 @@ impl Substitution {
     }
 }
 
+/// Binds the type variables in `target` by matching it against `source`, the type of the value
+/// being assigned to it. For example, matching `list['0]` against `list[string]` binds `'0` to
+/// `string`. A variable that gets bound more than once is bound to the union of its types.
+pub(crate) fn bind_type_vars(db: &dyn Db, target: &Ty, source: &Ty, bindings: &mut [Option<Ty>]) {
+    use Protocol::*;
+
+    match (target.kind(), source.kind()) {
+        (_, TyKind::Any | TyKind::Unknown | TyKind::Never) => {}
+        (TyKind::BoundVar(index), _) => {
+            let ty = Ty::union(
+                bindings[*index]
+                    .take()
+                    .into_iter()
+                    .chain(iter::once(source.clone())),
+            );
+            bindings[*index] = Some(ty);
+        }
+        (TyKind::List(target), TyKind::List(source) | TyKind::Protocol(Sequence(source)))
+        | (
+            TyKind::Protocol(Iterable(target) | Sequence(target))
+            | TyKind::Tuple(Tuple::Variable(target)),
+            TyKind::List(source)
+            | TyKind::Protocol(Iterable(source) | Sequence(source))
+            | TyKind::Tuple(Tuple::Variable(source)),
+        ) => bind_type_vars(db, target, source, bindings),
+        (
+            TyKind::Protocol(Iterable(target) | Sequence(target))
+            | TyKind::Tuple(Tuple::Variable(target)),
+            TyKind::Tuple(Tuple::Simple(sources)),
+        ) => sources
+            .iter()
+            .for_each(|source| bind_type_vars(db, target, source, bindings)),
+        (TyKind::Tuple(Tuple::Simple(targets)), TyKind::Tuple(Tuple::Simple(sources)))
+            if targets.len() == sources.len() =>
+        {
+            targets
+                .iter()
+                .zip(sources.iter())
+                .for_each(|(target, source)| bind_type_vars(db, target, source, bindings))
+        }
+        (TyKind::Dict(key_target, value_target, _), TyKind::Dict(key_source, value_source, _)) => {
+            bind_type_vars(db, key_target, key_source, bindings);
+            bind_type_vars(db, value_target, value_source, bindings);
+        }
+        (TyKind::Protocol(Iterable(target)), TyKind::Dict(source, _, _)) => {
+            bind_type_vars(db, target, source, bindings)
+        }
+        (TyKind::Protocol(Iterable(target)), TyKind::Range | TyKind::BytesElems) => {
+            bind_type_vars(db, target, &Ty::int(), bindings)
+        }
+        (TyKind::Protocol(Iterable(target)), TyKind::StringElems) => {
+            bind_type_vars(db, target, &Ty::string(), bindings)
+        }
+        (TyKind::Union(targets), _) => {
+            // Bind only the parts of `source` that aren't covered by the other members of the
+            // union, e.g. matching `'0 | None` against `string | None` binds `'0` to `string`.
+            // If several members have type variables, the first one that binds anything is used,
+            // e.g. matching `Iterable['0] | '0` against `list[int]` binds `'0` to `int`.
+            let (generic_targets, targets): (Vec<_>, Vec<_>) =
+                targets.iter().partition(|ty| ty.contains_bound_vars());
+            let sources = match source.kind() {
+                TyKind::Union(sources) => sources.iter().collect(),
+                _ => vec![source],
+            };
+            for source in sources {
+                if targets.iter().any(|target| assign_tys(db, source, target)) {
+                    continue;
+                }
+                for generic_target in generic_targets.iter() {
+                    let mut new_bindings = vec![None; bindings.len()];
+                    bind_type_vars(db, generic_target, source, &mut new_bindings);
+                    if new_bindings.iter().any(Option::is_some) {
+                        for (binding, new_binding) in bindings.iter_mut().zip(new_bindings) {
+                            if let Some(new_binding) = new_binding {
+                                *binding = Some(Ty::union(
+                                    binding.take().into_iter().chain(iter::once(new_binding)),
+                                ));
+                            }
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
 /// A marker type indicating that a value fulfills some behavior.
 /// For example, `list[int]` fulfills `Sequence[int]`. These types
 /// are used mostly by builtins that might return values that fulfill
@@ -1677,25 +1918,50 @@ impl Substitution {
 pub enum Protocol {
     Iterable(Ty),
     Sequence(Ty),
+    /// A value that can be called with arguments of the given types, e.g. the `key` function
+    /// accepted by `sorted()`.
+    Callable(Box<[Ty]>, Ty),
 }
 
-#[derive(Default)]
+/// Holds the state used for type inference. Inference state is kept in a pool of independent
+/// `InferenceContext`s, so that up to as many threads as there are contexts can run type inference
+/// concurrently. Each context maintains its own caches.
 pub struct GlobalContext {
     shared_state: Arc<SharedState>,
-    cx: Arc<Mutex<InferenceContext>>,
+    contexts: Arc<[Mutex<InferenceContext>]>,
+}
+
+impl Default for GlobalContext {
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
 }
 
 impl GlobalContext {
     pub fn new(options: InferenceOptions) -> Self {
+        Self::with_num_contexts(options, 1)
+    }
+
+    pub fn with_num_contexts(options: InferenceOptions, num_contexts: usize) -> Self {
         Self {
             shared_state: Arc::new(SharedState {
                 options,
                 ..Default::default()
             }),
-            ..Default::default()
+            contexts: (0..num_contexts.max(1))
+                .map(|_| Mutex::new(InferenceContext::default()))
+                .collect(),
         }
     }
 
+    pub fn options(&self) -> &InferenceOptions {
+        &self.shared_state.options
+    }
+
+    pub fn num_contexts(&self) -> usize {
+        self.contexts.len()
+    }
+
     pub fn cancel(&self) -> CancelGuard {
         CancelGuard::new(self)
     }
@@ -1704,7 +1970,7 @@ impl GlobalContext {
     where
         F: FnMut(&mut TyContext) -> T + std::panic::UnwindSafe,
     {
-        let mut cx = self.cx.lock();
+        let mut cx = self.lock_context();
         let mut tcx = TyContext {
             db,
             cx: &mut cx,
@@ -1713,6 +1979,24 @@ impl GlobalContext {
         };
         f(&mut tcx)
     }
+
+    /// Locks an inference context, preferring one that isn't currently in use. Each thread starts
+    /// its search at a fixed position so that it tends to reuse the same context and its caches.
+    fn lock_context(&self) -> MutexGuard<'_, InferenceContext> {
+        let num_contexts = self.contexts.len();
+        let preferred = if num_contexts == 1 {
+            0
+        } else {
+            let mut hasher = FxHasher::default();
+            thread::current().id().hash(&mut hasher);
+            hasher.finish() as usize % num_contexts
+        };
+
+        (0..num_contexts)
+            .map(|offset| (preferred + offset) % num_contexts)
+            .find_map(|index| self.contexts[index].try_lock())
+            .unwrap_or_else(|| self.contexts[preferred].lock())
+    }
 }
 
 pub(crate) fn with_tcx<F, T>(db: &dyn Db, f: F) -> T
@@ -1739,28 +2023,124 @@ pub(crate) struct InferenceContext {
     pub(crate) type_of_expr: FxHashMap<FileExprId, Ty>,
     pub(crate) type_of_load_item: FxHashMap<FileLoadItemId, Ty>,
     pub(crate) type_of_param: FxHashMap<FileParamId, Ty>,
+    pub(crate) expected_type_of_arg: FxHashMap<FileExprId, Ty>,
+    /// The types bound to the type variables of user-defined functions at each call expression.
+    pub(crate) call_substitutions: FxHashMap<FileExprId, Substitution>,
     pub(crate) source_assign_done: FxHashSet<FileExprId>,
     pub(crate) flow_node_type_cache: FxHashMap<CodeFlowCacheKey, Option<Ty>>,
     pub(crate) definition_is_used: FxHashMap<InFile<Either<ExprId, StmtId>>, bool>,
+    /// The current approximations for the types of names at loops that are still being evaluated.
+    pub(crate) loop_approximations: FxHashMap<CodeFlowCacheKey, Ty>,
+    /// Cache entries recorded while a loop is being evaluated, which are discarded if they were
+    /// computed from an approximation that turns out to be wrong.
+    pub(crate) speculative_entries: Option<Vec<CacheEntry>>,
+    /// Lambdas whose return types are currently being inferred.
+    pub(crate) lambda_ret_stack: Vec<FileExprId>,
+}
+
+impl InferenceContext {
+    pub(crate) fn record_cache_entry(&mut self, entry: CacheEntry) {
+        if let Some(entries) = &mut self.speculative_entries {
+            entries.push(entry);
+        }
+    }
+
+    /// Starts recording cache entries, so that everything inferred after this point can be
+    /// discarded with `rollback_to()`.
+    pub(crate) fn checkpoint(&mut self) -> InferenceCheckpoint {
+        let outermost = self.speculative_entries.is_none();
+        InferenceCheckpoint {
+            entries: self.speculative_entries.get_or_insert_with(Vec::new).len(),
+            diagnostics: self.diagnostics.len(),
+            outermost,
+        }
+    }
+
+    pub(crate) fn rollback_to(&mut self, checkpoint: InferenceCheckpoint) {
+        if let Some(entries) = &mut self.speculative_entries {
+            for entry in entries.drain(checkpoint.entries..) {
+                match entry {
+                    CacheEntry::Expr(id) => {
+                        self.type_of_expr.remove(&id);
+                    }
+                    CacheEntry::ExpectedArg(id) => {
+                        self.expected_type_of_arg.remove(&id);
+                    }
+                    CacheEntry::CallSubstitution(id) => {
+                        self.call_substitutions.remove(&id);
+                    }
+                    CacheEntry::SourceAssign(id) => {
+                        self.source_assign_done.remove(&id);
+                    }
+                    CacheEntry::FlowNode(key) => {
+                        self.flow_node_type_cache.remove(&key);
+                    }
+                    CacheEntry::Param(id) => {
+                        self.type_of_param.remove(&id);
+                    }
+                    CacheEntry::LoadItem(id) => {
+                        self.type_of_load_item.remove(&id);
+                    }
+                    CacheEntry::LoadStmt(id) => {
+                        self.resolved_load_stmts.remove(&id);
+                    }
+                }
+            }
+        }
+        self.diagnostics.truncate(checkpoint.diagnostics);
+    }
+
+    /// Keeps everything inferred since the checkpoint. Recording stops once the outermost
+    /// checkpoint is committed.
+    pub(crate) fn commit(&mut self, checkpoint: InferenceCheckpoint) {
+        if checkpoint.outermost {
+            self.speculative_entries = None;
+        }
+    }
+}
+
+pub(crate) enum CacheEntry {
+    Expr(FileExprId),
+    ExpectedArg(FileExprId),
+    CallSubstitution(FileExprId),
+    SourceAssign(FileExprId),
+    FlowNode(CodeFlowCacheKey),
+    Param(FileParamId),
+    LoadItem(FileLoadItemId),
+    LoadStmt(FileLoadStmt),
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct InferenceCheckpoint {
+    entries: usize,
+    diagnostics: usize,
+    outermost: bool,
 }
 
 pub struct CancelGuard<'a> {
     gcx: &'a GlobalContext,
-    cx: &'a Mutex<InferenceContext>,
 }
 
 impl<'a> CancelGuard<'a> {
     fn new(gcx: &'a GlobalContext) -> Self {
         gcx.shared_state.cancelled.store(true);
-        Self { gcx, cx: &gcx.cx }
+        Self { gcx }
     }
 }
 
 impl Drop for CancelGuard<'_> {
     fn drop(&mut self) {
-        let mut cx = self.cx.lock();
+        // Wait for all in-progress inference to unwind before resetting the contexts.
+        let mut contexts = self
+            .gcx
+            .contexts
+            .iter()
+            .map(|cx| cx.lock())
+            .collect::<Vec<_>>();
         self.gcx.shared_state.cancelled.store(false);
-        *cx = Default::default();
+        for cx in contexts.iter_mut() {
+            **cx = Default::default();
+        }
     }
 }
 
@@ -1774,6 +2154,8 @@ pub struct TyContext<'a> {
 struct TypeRefResolver<'a, 'b> {
     db: &'a dyn Db,
     context: Option<(&'a mut TyContext<'b>, InFile<StmtId>)>,
+    /// The type variables in scope, which are resolved to the corresponding bound variables.
+    type_vars: &'a [Name],
     errors: Vec<String>,
 }
 
@@ -1861,6 +2243,12 @@ impl<'a, 'b> TypeRefResolver<'a, 'b> {
             }
         }
 
+        if args.is_none() {
+            if let Some(index) = self.type_vars.iter().position(|type_var| type_var == name) {
+                return TyKind::BoundVar(index).intern();
+            }
+        }
+
         match name.as_str() {
             "Any" => types.any.clone(),
             "Unknown" | "unknown" => types.unknown.clone(),
@@ -1964,10 +2352,22 @@ pub(crate) fn resolve_type_ref(
     tcx: &mut TyContext,
     type_ref: &TypeRef,
     usage: Option<InFile<StmtId>>,
+) -> (Ty, Vec<String>) {
+    resolve_type_ref_with_type_vars(tcx, type_ref, usage, &[])
+}
+
+/// Like `resolve_type_ref()`, but resolves the given type variables to bound variables, e.g.
+/// `T` in `list[T]` is resolved to `'0` if `T` is the first of `type_vars`.
+pub(crate) fn resolve_type_ref_with_type_vars(
+    tcx: &mut TyContext,
+    type_ref: &TypeRef,
+    usage: Option<InFile<StmtId>>,
+    type_vars: &[Name],
 ) -> (Ty, Vec<String>) {
     TypeRefResolver {
         db: tcx.db,
         context: usage.map(|usage| (tcx, usage)),
+        type_vars,
         errors: vec![],
     }
     .resolve_type_ref(type_ref)
@@ -1987,6 +2387,7 @@ pub(crate) fn resolve_builtin_type_ref(db: &dyn Db, type_ref: &TypeRef) -> (Ty,
     TypeRefResolver {
         db,
         context: None,
+        type_vars: &[],
         errors: vec![],
     }
     .resolve_type_ref(type_ref)
@@ -1998,7 +2399,9 @@ pub(crate) fn resolve_builtin_type_ref_opt(db: &dyn Db, type_ref: Option<TypeRef
         .unwrap_or_else(Ty::unknown)
 }
 
-// TODO(withered-magic): This function currently assumes that all types are covariant in their arguments.
+/// Returns whether `source` is assignable to `target` under the lenient rules, which treat all
+/// type constructors as covariant in their arguments. See `TyContext::is_assignable()` for the
+/// stricter rules.
 pub(crate) fn assign_tys(db: &dyn Db, source: &Ty, target: &Ty) -> bool {
     use Protocol::*;
 
@@ -2028,12 +2431,36 @@ pub(crate) fn assign_tys(db: &dyn Db, source: &Ty, target: &Ty) -> bool {
                     .zip(targets.iter())
                     .all(|(source, target)| assign_tys(db, source, target))
         }
+        // Dicts are iterated over by key.
+        (TyKind::Dict(source, _, _), TyKind::Protocol(Iterable(target))) => {
+            assign_tys(db, source, target)
+        }
+        (TyKind::Range | TyKind::BytesElems, TyKind::Protocol(Iterable(target))) => {
+            assign_tys(db, &Ty::int(), target)
+        }
+        (TyKind::StringElems, TyKind::Protocol(Iterable(target))) => {
+            assign_tys(db, &Ty::string(), target)
+        }
         (TyKind::Protocol(source), TyKind::Protocol(target)) => match &(source, target) {
             (Iterable(source), Iterable(target))
             | (Sequence(source), Sequence(target))
             | (Sequence(source), Iterable(target)) => assign_tys(db, source, target),
+            // Callable protocols are compared by `TyContext::is_assignable()`.
+            (Callable(_, _), Callable(_, _)) => true,
             _ => false,
         },
+        // The signatures of user-defined functions are checked against callable protocols by
+        // `TyContext::is_assignable()`. The parameters of builtin callables aren't checked yet.
+        (
+            TyKind::Function(_)
+            | TyKind::Lambda(_, _, _)
+            | TyKind::IntrinsicFunction(_, _)
+            | TyKind::BuiltinFunction(_)
+            | TyKind::Rule(_)
+            | TyKind::Provider(_)
+            | TyKind::ProviderRawConstructor(_, _),
+            TyKind::Protocol(Callable(_, _)),
+        ) => true,
         (TyKind::Dict(key_source, value_source, _), TyKind::Dict(key_target, value_target, _)) => {
             assign_tys(db, key_source, key_target) && assign_tys(db, value_source, value_target)
         }
@@ -2050,8 +2477,8 @@ pub(crate) fn assign_tys(db: &dyn Db, source: &Ty, target: &Ty) -> bool {
                     .any(|target_ty| assign_tys(db, source_ty, target_ty))
             })
         }
-        // TODO(withered-magic): The logic below also temporarily allows assignments like `int | None` to `int`. Fix
-        // this once we support type guards.
+        // The logic below also allows assignments like `int | None` to `int`, which are only
+        // rejected with strict type checking.
         (_, TyKind::Union(tys)) => tys.iter().any(|target| assign_tys(db, source, target)),
         (TyKind::Union(tys), _) => tys.iter().any(|source| assign_tys(db, source, target)),
         (TyKind::BuiltinType(source, _), TyKind::BuiltinType(target, _)) => source == target,
@@ -0,0 +1,331 @@
+use starpls_common::File;
+
+use crate::def::scope::FunctionDef;
+use crate::def::Expr;
+use crate::def::ExprId;
+use crate::def::Param;
+use crate::module;
+use crate::typeck::assign_tys;
+use crate::typeck::Protocol;
+use crate::typeck::Tuple;
+use crate::typeck::Ty;
+use crate::typeck::TyContext;
+use crate::typeck::TyKind;
+use crate::Name;
+
+impl TyContext<'_> {
+    /// Returns whether a value of type `source` can be used where a value of type `target` is
+    /// expected. Unless strict type checking is enabled, this uses the lenient rules implemented
+    /// by `assign_tys()`.
+    ///
+    /// With strict type checking, `list` and `dict` are invariant in their type arguments, since
+    /// they can be mutated through the target. Tuples and read-only protocols like `Sequence`
+    /// stay covariant, function parameters are checked contravariantly, and a union is only
+    /// assignable if all of its members are.
+    pub(crate) fn is_assignable(&mut self, source: &Ty, target: &Ty) -> bool {
+        use Protocol::*;
+
+        // `assign_tys()` can't look up the signatures of functions, so they're checked against
+        // callable protocols here regardless of strictness.
+        match (source.kind(), target.kind()) {
+            (
+                TyKind::Function(def) | TyKind::Lambda(def, _, _),
+                TyKind::Protocol(Callable(param_tys, ret_ty)),
+            ) => {
+                let source = self.function_signature(def);
+                let target = callable_signature(param_tys, ret_ty);
+                return self.is_assignable_signature(&source, &target);
+            }
+            (
+                TyKind::Protocol(Callable(source_param_tys, source_ret_ty)),
+                TyKind::Protocol(Callable(target_param_tys, target_ret_ty)),
+            ) => {
+                let source = callable_signature(source_param_tys, source_ret_ty);
+                let target = callable_signature(target_param_tys, target_ret_ty);
+                return self.is_assignable_signature(&source, &target);
+            }
+            (TyKind::Function(_) | TyKind::Lambda(_, _, _), TyKind::Union(targets))
+                if !self.shared_state.options.strict_type_checking =>
+            {
+                return targets
+                    .iter()
+                    .any(|target| self.is_assignable(source, target));
+            }
+            _ => {}
+        }
+
+        if !self.shared_state.options.strict_type_checking {
+            return assign_tys(self.db, source, target);
+        }
+
+        match (source.kind(), target.kind()) {
+            (TyKind::Any | TyKind::Unknown | TyKind::Never, _)
+            | (_, TyKind::Any | TyKind::Unknown) => true,
+            (TyKind::Union(sources), _) => sources
+                .iter()
+                .all(|source| self.is_assignable(source, target)),
+            (_, TyKind::Union(targets)) => targets
+                .iter()
+                .any(|target| self.is_assignable(source, target)),
+            (TyKind::List(source), TyKind::List(target)) => self.is_equivalent(source, target),
+            // A `Sequence` might be a tuple or a list of a narrower type.
+            (TyKind::Protocol(_), TyKind::List(_)) => false,
+            (
+                TyKind::Dict(key_source, value_source, _),
+                TyKind::Dict(key_target, value_target, _),
+            ) => {
+                self.is_equivalent(key_source, key_target)
+                    && self.is_equivalent(value_source, value_target)
+            }
+            (
+                TyKind::List(source) | TyKind::Protocol(Sequence(source)),
+                TyKind::Protocol(Iterable(target) | Sequence(target)),
+            )
+            | (TyKind::Protocol(Iterable(source)), TyKind::Protocol(Iterable(target))) => {
+                self.is_assignable(source, target)
+            }
+            (
+                TyKind::Tuple(tuple),
+                TyKind::Protocol(Iterable(target) | Sequence(target))
+                | TyKind::Tuple(Tuple::Variable(target)),
+            ) => match tuple {
+                Tuple::Simple(sources) => sources
+                    .iter()
+                    .all(|source| self.is_assignable(source, target)),
+                Tuple::Variable(source) => self.is_assignable(source, target),
+            },
+            (TyKind::Tuple(Tuple::Simple(sources)), TyKind::Tuple(Tuple::Simple(targets))) => {
+                sources.len() == targets.len()
+                    && sources
+                        .iter()
+                        .zip(targets.iter())
+                        .all(|(source, target)| self.is_assignable(source, target))
+            }
+            (
+                TyKind::Function(source) | TyKind::Lambda(source, _, _),
+                TyKind::Function(target) | TyKind::Lambda(target, _, _),
+            ) => source == target || self.is_assignable_function(source, target),
+            _ => assign_tys(self.db, source, target),
+        }
+    }
+
+    /// Like `is_assignable()`, but for the value of the given expression, whose type is `source`.
+    /// List and dict literals can't be mutated through another reference, so their elements are
+    /// checked against the target's type arguments individually, e.g. `["a"]` is assignable to
+    /// `list[string | None]`.
+    pub(crate) fn is_expr_assignable(
+        &mut self,
+        file: File,
+        expr: ExprId,
+        source: &Ty,
+        target: &Ty,
+    ) -> bool {
+        use Protocol::*;
+
+        if !self.shared_state.options.strict_type_checking {
+            return self.is_assignable(source, target);
+        }
+
+        let module = module(self.db, file);
+        match (&module[expr], target.kind()) {
+            (Expr::Paren { expr }, _) => {
+                let source = self.infer_expr(file, *expr);
+                self.is_expr_assignable(file, *expr, &source, target)
+            }
+            (Expr::List { .. } | Expr::Dict { .. }, TyKind::Union(targets)) => targets
+                .iter()
+                .any(|target| self.is_expr_assignable(file, expr, source, target)),
+            (
+                Expr::List { exprs },
+                TyKind::List(target) | TyKind::Protocol(Iterable(target) | Sequence(target)),
+            ) => exprs.iter().all(|expr| {
+                let source = self.infer_expr(file, *expr);
+                self.is_expr_assignable(file, *expr, &source, target)
+            }),
+            (Expr::Dict { entries }, TyKind::Dict(key_target, value_target, _)) => {
+                entries.iter().all(|entry| {
+                    let key_source = self.infer_expr(file, entry.key);
+                    let value_source = self.infer_expr(file, entry.value);
+                    self.is_expr_assignable(file, entry.key, &key_source, key_target)
+                        && self.is_expr_assignable(file, entry.value, &value_source, value_target)
+                })
+            }
+            _ => self.is_assignable(source, target),
+        }
+    }
+
+    fn is_equivalent(&mut self, ty1: &Ty, ty2: &Ty) -> bool {
+        self.is_assignable(ty1, ty2) && self.is_assignable(ty2, ty1)
+    }
+
+    /// A function can be used in place of another function if it accepts every call that the
+    /// other function accepts, and if its return type is assignable to the other function's.
+    fn is_assignable_function(&mut self, source: &FunctionDef, target: &FunctionDef) -> bool {
+        let source = self.function_signature(source);
+        let target = self.function_signature(target);
+        self.is_assignable_signature(&source, &target)
+    }
+
+    fn function_signature(&mut self, def: &FunctionDef) -> Signature {
+        let db = self.db;
+        let func = def.func();
+        let file = func.file(db);
+        let module = module(db, file);
+        let mut signature = Signature {
+            positional: Vec::new(),
+            args: None,
+            keyword_only: Vec::new(),
+            kwargs: None,
+            ret: self.infer_function_ret_ty(def, &[]),
+        };
+
+        let mut saw_args = false;
+        for param in func.params(db).iter() {
+            let ty = self.infer_param(file, *param);
+            match &module[*param] {
+                Param::Simple { name, default, .. } => {
+                    let param = SignatureParam {
+                        name: name.clone(),
+                        ty,
+                        is_optional: default.is_some(),
+                    };
+                    if saw_args {
+                        signature.keyword_only.push(param);
+                    } else {
+                        signature.positional.push(param);
+                    }
+                }
+                Param::ArgsList { name, .. } => {
+                    // A bare `*` only marks the start of the keyword-only parameters.
+                    saw_args = true;
+                    if !name.is_missing() {
+                        signature.args = Some(match ty.kind() {
+                            TyKind::Tuple(Tuple::Variable(ty)) => ty.clone(),
+                            _ => self.unknown_ty(),
+                        });
+                    }
+                }
+                Param::KwargsDict { .. } => {
+                    signature.kwargs = Some(match ty.kind() {
+                        TyKind::Dict(_, ty, _) => ty.clone(),
+                        _ => self.unknown_ty(),
+                    });
+                }
+            }
+        }
+        signature
+    }
+
+    /// Checks that every call accepted by `target` is also accepted by `source`, with parameter
+    /// types checked contravariantly. The names of positional parameters aren't compared, since
+    /// callable types like `Callable[[int], Any]` don't have them.
+    fn is_assignable_signature(&mut self, source: &Signature, target: &Signature) -> bool {
+        for (index, target_param) in target.positional.iter().enumerate() {
+            let source_ty = match source.positional.get(index) {
+                // Callers of the target can omit optional parameters, so the source's
+                // parameter must be optional too.
+                Some(source_param) if target_param.is_optional && !source_param.is_optional => {
+                    return false;
+                }
+                Some(source_param) => &source_param.ty,
+                None => match &source.args {
+                    Some(ty) => ty,
+                    None => return false,
+                },
+            };
+            if !self.is_assignable(&target_param.ty, source_ty) {
+                return false;
+            }
+        }
+
+        if let Some(target_args) = &target.args {
+            match &source.args {
+                Some(source_args) if self.is_assignable(target_args, source_args) => {}
+                _ => return false,
+            }
+        }
+
+        // Callers of the target can only pass the source's remaining positional parameters by
+        // keyword, so they're matched up with the target's keyword-only parameters.
+        let source_keywords = source
+            .positional
+            .iter()
+            .skip(target.positional.len())
+            .chain(&source.keyword_only)
+            .collect::<Vec<_>>();
+        for target_param in &target.keyword_only {
+            let source_ty = match source_keywords
+                .iter()
+                .find(|source_param| source_param.name == target_param.name)
+            {
+                Some(source_param) if target_param.is_optional && !source_param.is_optional => {
+                    return false;
+                }
+                Some(source_param) => &source_param.ty,
+                None => match &source.kwargs {
+                    Some(ty) => ty,
+                    None => return false,
+                },
+            };
+            if !self.is_assignable(&target_param.ty, source_ty) {
+                return false;
+            }
+        }
+        let is_always_passed = |source_param: &&SignatureParam| {
+            source_param.is_optional
+                || target.keyword_only.iter().any(|target_param| {
+                    target_param.name == source_param.name && !target_param.is_optional
+                })
+        };
+        if !source_keywords.iter().all(is_always_passed) {
+            return false;
+        }
+
+        if let Some(target_kwargs) = &target.kwargs {
+            match &source.kwargs {
+                Some(source_kwargs) if self.is_assignable(target_kwargs, source_kwargs) => {}
+                _ => return false,
+            }
+        }
+
+        self.is_assignable(&source.ret, &target.ret)
+    }
+}
+
+/// Returns the signature of a callable protocol like `Callable[[int], string]`, whose parameters
+/// are all positional and required.
+fn callable_signature(param_tys: &[Ty], ret_ty: &Ty) -> Signature {
+    Signature {
+        positional: param_tys
+            .iter()
+            .map(|ty| SignatureParam {
+                name: Name::missing(),
+                ty: ty.clone(),
+                is_optional: false,
+            })
+            .collect(),
+        args: None,
+        keyword_only: Vec::new(),
+        kwargs: None,
+        ret: ret_ty.clone(),
+    }
+}
+
+/// The parameters and return type of a function, with parameters grouped by how arguments are
+/// passed to them.
+struct Signature {
+    /// Parameters that can be passed either by position or by keyword.
+    positional: Vec<SignatureParam>,
+    /// The type of the elements of `*args`, if the function accepts extra positional arguments.
+    args: Option<Ty>,
+    keyword_only: Vec<SignatureParam>,
+    /// The type of the values of `**kwargs`, if the function accepts extra keyword arguments.
+    kwargs: Option<Ty>,
+    ret: Ty,
+}
+
+struct SignatureParam {
+    name: Name,
+    ty: Ty,
+    is_optional: bool,
+}
@@ -25,7 +25,9 @@ use starpls_syntax::ast::{self};
 use crate::def::resolver::Export;
 use crate::def::resolver::Resolver;
 use crate::def::Argument;
+use crate::def::Expr;
 use crate::def::InternedString;
+use crate::module;
 use crate::source_map;
 use crate::typeck::Attribute;
 use crate::typeck::AttributeData;
@@ -364,6 +366,8 @@ impl BuiltinFunction {
                 let mut doc: Option<InternedString> = None;
                 let mut mandatory = false;
                 let mut default_ptr = None;
+                let mut configurable = true;
+                let mut values = None;
                 for (arg, ty) in args {
                     if let Argument::Keyword { name, expr } = arg {
                         match name.as_str() {
@@ -382,6 +386,28 @@ impl BuiltinFunction {
                                     default_ptr = Some(ptr.syntax_node_ptr());
                                 }
                             }
+                            "configurable" => {
+                                if let TyKind::Bool(Some(b)) = ty.kind() {
+                                    configurable = *b;
+                                }
+                            }
+                            "values" => {
+                                // Only literal values can be checked at call sites.
+                                if let Expr::List { exprs } = &module(db, file)[*expr] {
+                                    values = Some(
+                                        exprs
+                                            .iter()
+                                            .map(|expr| tcx.infer_expr(file, *expr))
+                                            .filter(|ty| {
+                                                matches!(
+                                                    ty.kind(),
+                                                    TyKind::String(Some(_)) | TyKind::Int(Some(_))
+                                                )
+                                            })
+                                            .collect(),
+                                    );
+                                }
+                            }
                             _ => {}
                         }
                     }
@@ -412,6 +438,8 @@ impl BuiltinFunction {
                             value: text_range,
                         })
                     }),
+                    configurable,
+                    values,
                 )))
             }
 
@@ -553,6 +581,7 @@ pub(crate) enum BuiltinFunctionParam {
         default_value: Option<String>,
         positional: bool,
         is_mandatory: bool,
+        is_configurable: bool,
     },
     ArgsList {
         name: Name,
@@ -582,6 +611,15 @@ impl BuiltinFunctionParam {
         }
     }
 
+    pub(crate) fn is_configurable(&self) -> bool {
+        match self {
+            BuiltinFunctionParam::Simple {
+                is_configurable, ..
+            } => *is_configurable,
+            _ => true,
+        }
+    }
+
     pub(crate) fn name(&self) -> Name {
         match self {
             BuiltinFunctionParam::Simple { name, .. }
@@ -899,6 +937,7 @@ fn builtin_param(param: &Param) -> BuiltinFunctionParam {
             },
             positional: true,
             is_mandatory: param.is_mandatory,
+            is_configurable: !param.is_nonconfigurable,
         }
     }
 }
@@ -917,6 +956,7 @@ fn builtin_provider(db: &dyn Db, ty: &Type, callable: Option<&Callable>) -> Buil
                 default_value: None,
                 positional: false,
                 is_mandatory: false,
+                is_configurable: true,
             })
             .collect(),
     };
@@ -998,6 +1038,8 @@ pub(crate) fn common_attributes_query(db: &dyn Db) -> CommonAttributes {
                             db,
                             attr.default_value.into_boxed_str(),
                         ))),
+                        configurable: !attr.is_nonconfigurable,
+                        values: None,
                     },
                 )
             })
@@ -145,7 +145,11 @@ impl Slots {
                         }
                     }
 
-                    if !self.disable_errors {
+                    // Repeated keyword arguments are reported while lowering.
+                    let is_repeated = args[..arg_index].iter().any(
+                        |arg| matches!(arg, Argument::Keyword { name, .. } if name == arg_name),
+                    );
+                    if !self.disable_errors && !is_repeated {
                         errors.push(ArgError {
                             expr: *expr,
                             message: format!(
@@ -308,6 +312,18 @@ pub(crate) enum Slot {
     },
 }
 
+impl Slot {
+    /// Returns the values assigned to this slot.
+    pub(crate) fn providers(&self) -> &[SlotProvider] {
+        match self {
+            Slot::Positional { provider } | Slot::Keyword { provider, .. } => {
+                std::slice::from_ref(provider)
+            }
+            Slot::ArgsList { providers, .. } | Slot::KwargsDict { providers } => &providers[..],
+        }
+    }
+}
+
 /// Describes a value assigned to a slot. This type enumerates
 /// all the ways in which arguments can be passed to a function.
 #[derive(Clone, Debug, PartialEq, Eq)]
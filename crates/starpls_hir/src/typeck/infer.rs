@@ -1,14 +1,22 @@
 use std::sync::Arc;
 
 use either::Either;
+use rustc_hash::FxHashMap;
+use rustc_hash::FxHashSet;
+use starpls_bazel::label::Label;
+use starpls_bazel::APIContext;
 use starpls_common::line_index;
 use starpls_common::parse;
 use starpls_common::Diagnostic;
+use starpls_common::DiagnosticCode;
 use starpls_common::DiagnosticTag;
+use starpls_common::Dialect;
 use starpls_common::File;
 use starpls_common::FileRange;
 use starpls_common::InFile;
+use starpls_common::ResolvedPath;
 use starpls_common::Severity;
+use starpls_common::UnfetchedRepositoryError;
 use starpls_syntax::ast::ArithOp;
 use starpls_syntax::ast::AstNode;
 use starpls_syntax::ast::AstPtr;
@@ -33,7 +41,10 @@ use crate::def::scope::ParameterDef;
 use crate::def::scope::ScopeDef;
 use crate::def::scope::ScopeHirId;
 use crate::def::scope::VariableDef;
+use crate::def::targets::declared_targets;
+use crate::def::visibility::load_visibility;
 use crate::def::Argument;
+use crate::def::DictEntry;
 use crate::def::Expr;
 use crate::def::ExprId;
 use crate::def::InternedString;
@@ -49,21 +60,29 @@ use crate::display::DisplayWithDb;
 use crate::module;
 use crate::source_map;
 use crate::typeck::assign_tys;
+use crate::typeck::bind_type_vars;
 use crate::typeck::builtins::builtin_types;
+use crate::typeck::builtins::BuiltinFunctionParam;
 use crate::typeck::call::Slot;
 use crate::typeck::call::SlotProvider;
 use crate::typeck::call::Slots;
+use crate::typeck::intrinsics::IntrinsicFunction;
 use crate::typeck::intrinsics::IntrinsicFunctionParam;
 use crate::typeck::intrinsics::IntrinsicTypes;
+use crate::typeck::narrow::narrow_none;
 use crate::typeck::resolve_builtin_type_ref;
 use crate::typeck::resolve_type_ref;
 use crate::typeck::resolve_type_ref_opt;
+use crate::typeck::resolve_type_ref_with_type_vars;
+use crate::typeck::Attribute;
+use crate::typeck::CacheEntry;
 use crate::typeck::CodeFlowCacheKey;
 use crate::typeck::DictLiteral;
 use crate::typeck::FileExprId;
 use crate::typeck::FileLoadItemId;
 use crate::typeck::FileLoadStmt;
 use crate::typeck::FileParamId;
+use crate::typeck::LabelPosition;
 use crate::typeck::Protocol;
 use crate::typeck::Provider;
 use crate::typeck::RuleKind;
@@ -76,8 +95,25 @@ use crate::typeck::TyData;
 use crate::typeck::TyKind;
 use crate::typeck::TypeRef;
 use crate::typeck::TypecheckCancelled;
+use crate::Db;
 use crate::Name;
 
+/// The maximum number of times that a loop is evaluated before the type of a name at the loop
+/// is widened.
+const MAX_LOOP_ITERATIONS: usize = 5;
+
+/// Methods of `list` and `dict` that mutate their receiver.
+const MUTATING_METHODS: &[&str] = &[
+    "append",
+    "clear",
+    "extend",
+    "insert",
+    "pop",
+    "remove",
+    "setdefault",
+    "update",
+];
+
 impl TyContext<'_> {
     fn infer_all_exprs(&mut self, file: File) {
         for (expr, _) in module(self.db, file).exprs.iter() {
@@ -96,13 +132,7 @@ impl TyContext<'_> {
             return;
         }
 
-        if !self.shared_state.options.use_code_flow_analysis {
-            for stmt in stmts.iter() {
-                self.walk_stmt(file, *stmt);
-            }
-            return;
-        }
-
+        // Reachability is checked even if code flow analysis is disabled for inference.
         let cfg = code_flow_graph(self.db, file).cfg(self.db);
         let mut prev_flow_node_or_unreachable = {
             self.walk_stmt(file, stmts[0]);
@@ -150,8 +180,9 @@ impl TyContext<'_> {
             {
                 self.add_diagnostic_for_range(
                     file,
-                    Severity::Warning,
+                    Severity::Hint,
                     TextRange::new(start, end),
+                    DiagnosticCode::UnreachableCode,
                     Some(vec![DiagnosticTag::Unnecessary]),
                     "Code is unreachable".to_string(),
                 );
@@ -169,14 +200,18 @@ impl TyContext<'_> {
                     self.infer_load_item(file, load_item);
                 }
             }
-            Stmt::Assign { lhs, rhs, .. } => match &module[*lhs] {
-                Expr::Index { .. } => {
+            Stmt::Assign { lhs, rhs, op, .. } => match &module[*lhs] {
+                Expr::Index {
+                    lhs: collection, ..
+                } => {
+                    self.check_frozen_mutation(file, *collection, *lhs);
                     let lhs_ty = self.infer_expr(file, *lhs);
                     let rhs_ty = self.infer_expr(file, *rhs);
-                    if !assign_tys(self.db, &rhs_ty, &lhs_ty) {
+                    if !self.is_expr_assignable(file, *rhs, &rhs_ty, &lhs_ty) {
                         self.add_expr_diagnostic_error(
                             file,
                             *lhs,
+                            DiagnosticCode::TypeMismatch,
                             format!(
                                 "Cannot use value of type \"{}\" as type \"{}\" in assignment",
                                 rhs_ty.display(self.db).alt(),
@@ -194,6 +229,7 @@ impl TyContext<'_> {
                         self.add_expr_diagnostic_error(
                             file,
                             *lhs,
+                            DiagnosticCode::InvalidAssignment,
                             format!(
                                 "Cannot assign to field \"{}\" for immutable type \"{}\"",
                                 field.as_str(),
@@ -207,6 +243,7 @@ impl TyContext<'_> {
                             if matches!(
                                 ty.kind(),
                                 TyKind::Function(_)
+                                    | TyKind::Lambda(_, _, _)
                                     | TyKind::BuiltinFunction(_)
                                     | TyKind::IntrinsicFunction(_, _)
                             ) {
@@ -219,6 +256,7 @@ impl TyContext<'_> {
                         self.add_expr_diagnostic_error(
                             file,
                             *lhs,
+                            DiagnosticCode::InvalidAssignment,
                             format!(
                                 "Cannot reassign to method \"{}\" of type \"{}\"",
                                 name,
@@ -227,6 +265,9 @@ impl TyContext<'_> {
                         );
                     }
                 }
+                Expr::Name { name } if op.is_some() => {
+                    self.check_frozen_augmented_assignment(file, name, *lhs)
+                }
                 _ => {}
             },
             Stmt::Def { stmts, .. } | Stmt::For { stmts, .. } => {
@@ -248,6 +289,188 @@ impl TyContext<'_> {
         }
     }
 
+    /// Reports keys in a dict literal that are equal to an earlier key, i.e. two identical string
+    /// or int literals. Keys are compared syntactically, since the types of the keys of large dicts
+    /// aren't inferred.
+    fn check_duplicate_dict_keys(&mut self, file: File, entries: &[DictEntry]) {
+        let db = self.db;
+        let module = module(db, file);
+        let mut keys = FxHashSet::default();
+        for entry in entries.iter() {
+            let key = match &module[entry.key] {
+                Expr::Literal {
+                    literal: Literal::String(s),
+                } => format!("\"{}\"", s.value(db)),
+                Expr::Literal {
+                    literal: Literal::Int(x),
+                } => x.to_string(),
+                _ => continue,
+            };
+            if !keys.insert(key.clone()) {
+                self.add_expr_diagnostic_error(
+                    file,
+                    entry.key,
+                    DiagnosticCode::DuplicateKey,
+                    format!("Duplicate key {} in dict literal", key),
+                );
+            }
+        }
+    }
+
+    /// Reports a mutation of the given collection from inside a function if the collection is a
+    /// module-level global or a loaded value. Both are frozen by the time any function runs.
+    fn check_frozen_mutation(&mut self, file: File, collection: ExprId, mutation: ExprId) {
+        let db = self.db;
+        let Expr::Name { name } = &module(db, file)[collection] else {
+            return;
+        };
+        let resolver = Resolver::new_for_expr(db, file, collection);
+        if !resolver.is_in_function() {
+            return;
+        }
+        let Some(def) = resolver
+            .resolve_name(name)
+            .and_then(|(execution_scope, mut defs)| {
+                (execution_scope == ExecutionScopeId::Module)
+                    .then(|| defs.next().map(|def| def.def.clone()))
+                    .flatten()
+            })
+        else {
+            return;
+        };
+        let ty = self.infer_expr(file, collection);
+        self.report_frozen_mutation(file, name, &def, &ty, mutation);
+    }
+
+    /// Reports an augmented assignment like `X += [1]` inside a function, where `X` refers to a
+    /// frozen global. Since the assignment makes `X` local to the function, this only applies if
+    /// the function doesn't assign to `X` anywhere else.
+    fn check_frozen_augmented_assignment(&mut self, file: File, name: &Name, target: ExprId) {
+        let db = self.db;
+        let resolver = Resolver::new_for_expr(db, file, target);
+        if !resolver.is_in_function()
+            || resolver.resolve_name(name).is_some_and(|(_, mut defs)| {
+                defs.any(|def| match def.def {
+                    ScopeDef::Variable(VariableDef { expr, .. }) => *expr != target,
+                    _ => true,
+                })
+            })
+        {
+            return;
+        }
+        let Some(def) = resolver.resolve_module_level_name(name) else {
+            return;
+        };
+        let ty = match &def {
+            ScopeDef::Variable(VariableDef {
+                file,
+                source: Some(source),
+                ..
+            }) => self.infer_expr(*file, *source),
+            ScopeDef::LoadItem(LoadItemDef { file, load_item }) => {
+                self.infer_load_item(*file, *load_item)
+            }
+            _ => return,
+        };
+        self.report_frozen_mutation(file, name, &def, &ty, target);
+    }
+
+    fn report_frozen_mutation(
+        &mut self,
+        file: File,
+        name: &Name,
+        def: &ScopeDef,
+        ty: &Ty,
+        mutation: ExprId,
+    ) {
+        if !matches!(ty.kind(), TyKind::List(_) | TyKind::Dict(_, _, _)) {
+            return;
+        }
+        let (severity, message) = match def {
+            ScopeDef::Variable(_) => {
+                // Helpers called while the module is loading may still mutate its globals, e.g. to
+                // populate a registry. Functions can also be called indirectly, so this is only a
+                // warning.
+                let in_load_time_function = Resolver::new_for_expr(self.db, file, mutation)
+                    .enclosing_def_stmt()
+                    .is_some_and(|stmt| self.load_time_functions(file).contains(&stmt));
+                if in_load_time_function {
+                    return;
+                }
+                (
+                    Severity::Warning,
+                    format!(
+                        "Cannot mutate global \"{}\" from a function, as globals are frozen once the module is loaded",
+                        name.as_str()
+                    ),
+                )
+            }
+            ScopeDef::LoadItem(_) => (
+                Severity::Error,
+                format!(
+                    "Cannot mutate \"{}\", as values loaded from other modules are frozen",
+                    name.as_str()
+                ),
+            ),
+            _ => return,
+        };
+        self.add_expr_diagnostic_with_severity(
+            file,
+            mutation,
+            severity,
+            DiagnosticCode::FrozenMutation,
+            None,
+            message,
+        );
+    }
+
+    /// Returns the functions in the given file that are called while the module is loading, either
+    /// directly from module-level code or through other such functions.
+    fn load_time_functions(&self, file: File) -> FxHashSet<StmtId> {
+        let db = self.db;
+        let module = module(db, file);
+        let mut calls: FxHashMap<Option<StmtId>, Vec<StmtId>> = FxHashMap::default();
+        for expr in module.exprs.iter().map(|(_, expr)| expr) {
+            let Expr::Call { callee, .. } = expr else {
+                continue;
+            };
+            let Expr::Name { name } = &module[*callee] else {
+                continue;
+            };
+            let resolver = Resolver::new_for_expr(db, file, *callee);
+            let Some(stmt) =
+                resolver
+                    .resolve_name(name)
+                    .and_then(|(_, defs)| match defs.last()?.def {
+                        ScopeDef::Function(FunctionDef::Def { stmt, .. }) if stmt.file == file => {
+                            Some(stmt.value)
+                        }
+                        _ => None,
+                    })
+            else {
+                continue;
+            };
+            let caller = if resolver.is_in_function() {
+                match resolver.enclosing_def_stmt() {
+                    Some(caller) => Some(caller),
+                    None => continue,
+                }
+            } else {
+                None
+            };
+            calls.entry(caller).or_default().push(stmt);
+        }
+
+        let mut functions = FxHashSet::default();
+        let mut stack = calls.get(&None).cloned().unwrap_or_default();
+        while let Some(stmt) = stack.pop() {
+            if functions.insert(stmt) {
+                stack.extend(calls.get(&Some(stmt)).into_iter().flatten().copied());
+            }
+        }
+        functions
+    }
+
     fn report_unused_definitions(&mut self, file: File) {
         for (expr, name) in module(self.db, file).exprs.iter().filter_map(|(id, expr)| {
             if let Expr::Name { name } = expr {
@@ -268,6 +491,7 @@ impl TyContext<'_> {
                 self.add_expr_diagnostic_warning(
                     file,
                     expr,
+                    DiagnosticCode::UnusedDefinition,
                     Some(vec![DiagnosticTag::Unnecessary]),
                     format!("\"{}\" is not accessed", name.as_str()),
                 );
@@ -319,6 +543,7 @@ impl TyContext<'_> {
                         file,
                         Severity::Warning,
                         name_node.syntax().text_range(),
+                        DiagnosticCode::UnusedDefinition,
                         Some(vec![DiagnosticTag::Unnecessary]),
                         format!("\"{}\" is not accessed", name.as_str()),
                     );
@@ -328,6 +553,79 @@ impl TyContext<'_> {
                 maybe_add_diagnostic(self, file, stmt, &name);
             }
         }
+
+        // Symbols bound by `load` statements are local to the loading module, so any that aren't
+        // referenced within the module are unused.
+        let used_load_items = self.used_load_items(file);
+        for (load_item, name) in module(self.db, file)
+            .load_items
+            .iter()
+            .map(|(id, load_item)| match load_item {
+                LoadItem::Direct { name, .. } => (id, name.to_string()),
+                LoadItem::Aliased { alias, .. } => (id, alias.as_str().to_string()),
+            })
+        {
+            if name.is_empty() || used_load_items.contains(&load_item) {
+                continue;
+            }
+
+            if let Some(ptr) = source_map(self.db, file).load_item_map_back.get(&load_item) {
+                self.add_diagnostic_for_range(
+                    file,
+                    Severity::Warning,
+                    ptr.syntax_node_ptr().text_range(),
+                    DiagnosticCode::UnusedLoad,
+                    Some(vec![DiagnosticTag::Unnecessary]),
+                    format!("\"{}\" is not accessed", name),
+                );
+            }
+        }
+    }
+
+    /// Collects the `load`ed symbols that are referenced anywhere in the module. This doesn't rely
+    /// on inference, so uses in type comments, annotations and unreachable code are all counted.
+    fn used_load_items(&self, file: File) -> FxHashSet<LoadItemId> {
+        let module = module(self.db, file);
+        let mut used_load_items = FxHashSet::default();
+        for (expr, name) in module.exprs.iter().filter_map(|(expr, data)| match data {
+            Expr::Name { name } => Some((expr, name)),
+            _ => None,
+        }) {
+            let resolver = Resolver::new_for_expr(self.db, file, expr);
+            if let Some((_, defs)) = resolver.resolve_name(name) {
+                used_load_items.extend(defs.filter_map(|def| match def.def {
+                    ScopeDef::LoadItem(LoadItemDef { load_item, .. }) => Some(*load_item),
+                    _ => None,
+                }));
+            }
+        }
+
+        // `load` statements may only appear at the top level of a module, so any type reference
+        // to a loaded name is counted as a use, even where the name is shadowed.
+        let mut names = Vec::new();
+        for type_ref in module
+            .params
+            .iter()
+            .filter_map(|(_, param)| param.type_ref())
+            .chain(module.stmts.iter().filter_map(|(_, stmt)| match stmt {
+                Stmt::Def { func, .. } => func.ret_type_ref(self.db),
+                Stmt::Assign {
+                    type_ref: Some((type_ref, _)),
+                    ..
+                } => Some(type_ref.clone()),
+                _ => None,
+            }))
+        {
+            type_ref.collect_names(&mut names);
+        }
+        used_load_items.extend(module.load_items.iter().filter_map(|(id, load_item)| {
+            let name = match load_item {
+                LoadItem::Direct { name, .. } => &**name,
+                LoadItem::Aliased { alias, .. } => alias.as_str(),
+            };
+            names.iter().any(|used| used.as_str() == name).then_some(id)
+        }));
+        used_load_items
     }
 
     pub fn diagnostics_for_file(&mut self, file: File) -> Vec<Diagnostic> {
@@ -384,17 +682,26 @@ impl TyContext<'_> {
                     .infer_name(file, name, expr)
                     .unwrap_or_else(|| self.unbound_ty());
 
-                // Report unbound and possibly unbound variables.
+                // Report unbound and possibly unbound variables. Globals that are referenced
+                // before they're assigned have already been reported while computing scopes.
                 if ty.is_unbound() {
-                    self.add_expr_diagnostic_error(
-                        file,
-                        expr,
-                        format!("\"{}\" is not defined", name.as_str()),
-                    );
+                    if !module_scopes(db, file)
+                        .scopes(db)
+                        .used_before_assignment
+                        .contains(&expr)
+                    {
+                        self.add_expr_diagnostic_error(
+                            file,
+                            expr,
+                            DiagnosticCode::UndefinedName,
+                            format!("\"{}\" is not defined", name.as_str()),
+                        );
+                    }
                 } else if ty.is_possibly_unbound() {
                     self.add_expr_diagnostic_error(
                         file,
                         expr,
+                        DiagnosticCode::PossiblyUnbound,
                         format!("\"{}\" is possibly unbound", name.as_str()),
                     )
                 }
@@ -408,6 +715,7 @@ impl TyContext<'_> {
             }
             Expr::ListComp { expr, .. } => TyKind::List(self.infer_expr(file, *expr)).intern(),
             Expr::Dict { entries } => {
+                self.check_duplicate_dict_keys(file, entries);
                 let key_ty = match entries.len() {
                     0 => Ty::unknown(),
                     len if len > 32 => {
@@ -476,6 +784,9 @@ impl TyContext<'_> {
             } => {
                 let receiver_ty = self.infer_expr(file, *dot_expr);
                 match receiver_ty.kind() {
+                    // Narrowing can rule out every type of the receiver, in which case the
+                    // expression is never evaluated and there's nothing to report.
+                    TyKind::Never => receiver_ty,
                     TyKind::Unknown
                     | TyKind::Unbound
                     | TyKind::Any
@@ -527,6 +838,7 @@ impl TyContext<'_> {
                                 self.add_expr_diagnostic_warning_ty(
                                     file,
                                     expr,
+                                    DiagnosticCode::UnknownField,
                                     format!(
                                         "Cannot access field \"{}\" for type \"{}\"",
                                         field.as_str(),
@@ -554,6 +866,7 @@ impl TyContext<'_> {
                                 None => self.add_expr_diagnostic_error_ty(
                                     file,
                                     expr,
+                                    DiagnosticCode::InvalidIndex,
                                     format!(
                                         "Index {} is out of range for type {}",
                                         x,
@@ -565,6 +878,7 @@ impl TyContext<'_> {
                             _ => self.add_expr_diagnostic_error_ty(
                                 file,
                                 expr,
+                                DiagnosticCode::InvalidIndex,
                                 format!(
                                     "Cannot index tuple with type \"{}\"",
                                     index_ty.display(db).alt()
@@ -586,6 +900,7 @@ impl TyContext<'_> {
                                 TyKind::Provider(provider),
                             ) => Some(TyKind::ProviderInstance(provider.clone()).intern()),
                             (TyKind::Any | TyKind::Unknown, _) => Some(Ty::unknown()),
+                            (TyKind::Never, _) => Some(Ty::never()),
                             (TyKind::BuiltinType(ty, _), _) => match ty.indexable_by(db) {
                                 Some((expected_index_ty, return_ty)) => {
                                     let expected_index_ty =
@@ -605,6 +920,7 @@ impl TyContext<'_> {
                             self.add_expr_diagnostic_warning_ty(
                                 file,
                                 expr,
+                                DiagnosticCode::InvalidIndex,
                                 format!("Type \"{}\" is not indexable", lhs_ty.display(db).alt()),
                             )
                         });
@@ -619,6 +935,7 @@ impl TyContext<'_> {
                     self.add_expr_diagnostic_warning_ty(
                         file,
                         *lhs,
+                        DiagnosticCode::InvalidIndex,
                         format!(
                             "Cannot index {} with type \"{}\"",
                             name,
@@ -631,6 +948,15 @@ impl TyContext<'_> {
                 let mut saw_keyword = false;
                 let mut saw_unpacked_dict = false;
                 let callee_ty = self.infer_expr(file, *callee);
+                if let Expr::Dot {
+                    expr: receiver,
+                    field,
+                } = &curr_module[*callee]
+                {
+                    if MUTATING_METHODS.contains(&field.as_str()) {
+                        self.check_frozen_mutation(file, *receiver, expr);
+                    }
+                }
                 let arg_tys: Vec<_> = args
                     .iter()
                     .map(|arg| match arg {
@@ -639,6 +965,7 @@ impl TyContext<'_> {
                                 self.add_expr_diagnostic_error(
                                     file,
                                     *expr,
+                                    DiagnosticCode::InvalidArgument,
                                     String::from(
                                         "Positional argument cannot follow keyword arguments",
                                     ),
@@ -648,6 +975,7 @@ impl TyContext<'_> {
                                 self.add_expr_diagnostic_error(
                                     file,
                                     *expr,
+                                    DiagnosticCode::InvalidArgument,
                                     String::from(
                                         "Positional argument cannot follow keyword argument unpacking",
                                     ),
@@ -664,6 +992,7 @@ impl TyContext<'_> {
                                 self.add_expr_diagnostic_error(
                                     file,
                                     *expr,
+                                    DiagnosticCode::InvalidArgument,
                                     String::from(
                                         "Unpacked iterable argument cannot follow keyword arguments",
                                     ),
@@ -673,6 +1002,7 @@ impl TyContext<'_> {
                                 self.add_expr_diagnostic_error(
                                     file,
                                     *expr,
+                                    DiagnosticCode::InvalidArgument,
                                     String::from(
                                         "Unpacked iterable argument cannot follow keyword argument unpacking",
                                     ),
@@ -690,7 +1020,7 @@ impl TyContext<'_> {
 
                 // TODO(withered-magic): This is hilariously non-DRY, should probably clean this up at some point.
                 match callee_ty.kind() {
-                    TyKind::Function(def) => {
+                    TyKind::Function(def) | TyKind::Lambda(def, _, _) => {
                         let module = module(db, def.func().file(db));
                         let params = def.func().params(db).iter().copied();
                         let mut slots: Slots = params
@@ -701,16 +1031,69 @@ impl TyContext<'_> {
                         let errors = slots.assign_args(args, None).0;
 
                         for error in errors {
-                            self.add_expr_diagnostic_error(file, error.expr, error.message);
+                            self.add_expr_diagnostic_error(
+                                file,
+                                error.expr,
+                                DiagnosticCode::InvalidArgument,
+                                error.message,
+                            );
                         }
 
                         let mut missing_params = Vec::new();
 
+                        // Bind the function's type variables, if it has any, to the types of
+                        // the arguments passed for them. Each type variable is bound by the first
+                        // argument that mentions it, so that later arguments with conflicting
+                        // types are reported as mismatches instead of widening the binding.
+                        let type_vars = def.func().type_vars(db);
+                        let param_tys = params
+                            .clone()
+                            .map(|param| {
+                                self.resolve_signature_type_ref(
+                                    def,
+                                    module[param].type_ref(),
+                                    &type_vars,
+                                )
+                            })
+                            .collect::<Vec<_>>();
+                        let mut bindings = vec![None; type_vars.len()];
+                        for (param_ty, slot) in param_tys.iter().zip(slots.slots.iter()) {
+                            for provider in slot.providers() {
+                                if let SlotProvider::Single(_, index) = provider {
+                                    let mut arg_bindings = vec![None; type_vars.len()];
+                                    bind_type_vars(
+                                        db,
+                                        param_ty,
+                                        &arg_tys[*index],
+                                        &mut arg_bindings,
+                                    );
+                                    for (binding, arg_binding) in
+                                        bindings.iter_mut().zip(arg_bindings)
+                                    {
+                                        if binding.is_none() {
+                                            *binding = arg_binding;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        let subst = bindings
+                            .into_iter()
+                            .map(|ty| ty.unwrap_or_else(|| self.unknown_ty()))
+                            .collect::<Vec<_>>();
+                        let id = FileExprId::new(file, expr);
+                        self.cx.call_substitutions.insert(
+                            id,
+                            Substitution {
+                                args: subst.iter().cloned().collect(),
+                            },
+                        );
+                        self.cx.record_cache_entry(CacheEntry::CallSubstitution(id));
+
                         // Validate argument types.
-                        for (param, slot) in params.zip(slots.slots) {
+                        for ((param, slot), param_ty) in params.zip(slots.slots).zip(param_tys) {
                             let hir_param = &module[param];
-                            let param_ty =
-                                resolve_type_ref_opt(self, hir_param.type_ref(), def.stmt());
+                            let param_ty = param_ty.substitute(&subst);
 
                             // TODO(withered-magic): Deduplicate the following logic for
                             // validating providers, as it's currently shared between
@@ -727,9 +1110,7 @@ impl TyContext<'_> {
                                 }
                                 SlotProvider::Single(expr, index) => {
                                     let ty = &arg_tys[index];
-                                    if !assign_tys(db, ty, &param_ty) {
-                                        self.add_expr_diagnostic_error(file, expr, format!("Argument of type \"{}\" cannot be assigned to parameter of type \"{}\"", ty.display(self.db).alt(), param_ty.display(self.db).alt()));
-                                    }
+                                    self.check_argument_ty(file, expr, ty, &param_ty);
                                 }
                                 _ => {}
                             };
@@ -757,13 +1138,15 @@ impl TyContext<'_> {
                                 message.push('"');
                             }
 
-                            self.add_expr_diagnostic_error(file, expr, message);
+                            self.add_expr_diagnostic_error(
+                                file,
+                                expr,
+                                DiagnosticCode::MissingArgument,
+                                message,
+                            );
                         }
 
-                        def.func()
-                            .ret_type_ref(db)
-                            .map(|type_ref| resolve_type_ref(self, &type_ref, def.stmt()).0)
-                            .unwrap_or_else(|| self.unknown_ty())
+                        self.infer_function_ret_ty(def, &subst)
                     }
                     TyKind::IntrinsicFunction(func, subst) => {
                         let params = func.params(db);
@@ -771,25 +1154,30 @@ impl TyContext<'_> {
                         let errors = slots.assign_args(args, None).0;
 
                         for error in errors {
-                            self.add_expr_diagnostic_error(file, error.expr, error.message);
+                            self.add_expr_diagnostic_error(
+                                file,
+                                error.expr,
+                                DiagnosticCode::InvalidArgument,
+                                error.message,
+                            );
                         }
 
-                        // Validate argument types.
-                        for (param, slot) in params.iter().zip(slots.slots) {
-                            let param_ty = match param {
-                                IntrinsicFunctionParam::Positional { ty, .. }
-                                | IntrinsicFunctionParam::Keyword { ty, .. }
-                                | IntrinsicFunctionParam::ArgsList { ty } => ty.clone(),
-                                IntrinsicFunctionParam::KwargsDict => self.any_ty(),
-                            }
-                            .substitute(&subst.args);
+                        let (param_tys, bindings) =
+                            self.bind_intrinsic_type_vars(*func, subst, &slots, |_, _, index| {
+                                Some(arg_tys[index].clone())
+                            });
 
+                        // Validate argument types.
+                        for ((param, slot), param_ty) in
+                            params.iter().zip(slots.slots).zip(param_tys)
+                        {
                             let mut validate_provider = |provider| match provider {
                                 SlotProvider::Missing => {
                                     if !param.is_optional() {
                                         self.add_expr_diagnostic_error(
                                             file,
                                             expr,
+                                            DiagnosticCode::MissingArgument,
                                             format!(
                                                 "Missing expected argument of type \"{}\"",
                                                 param_ty.display(db).alt()
@@ -799,9 +1187,7 @@ impl TyContext<'_> {
                                 }
                                 SlotProvider::Single(expr, index) => {
                                     let ty = &arg_tys[index];
-                                    if !assign_tys(db, ty, &param_ty) {
-                                        self.add_expr_diagnostic_error(file, expr, format!("Argument of type \"{}\" cannot be assigned to parameter of type \"{}\"", ty.display(self.db).alt(), param_ty.display(self.db).alt()));
-                                    }
+                                    self.check_argument_ty(file, expr, ty, &param_ty);
                                     if let IntrinsicFunctionParam::Keyword {
                                         name,
                                         deprecated,
@@ -826,6 +1212,7 @@ impl TyContext<'_> {
                                                     file,
                                                     Severity::Info,
                                                     arg_name_node.syntax().text_range(),
+                                                    DiagnosticCode::DeprecatedArgument,
                                                     Some(vec![DiagnosticTag::Deprecated]),
                                                     format!(
                                                         "Argument \"{}\" is deprecated",
@@ -851,7 +1238,11 @@ impl TyContext<'_> {
                         }
 
                         func.maybe_unique_ret_type(db, args_with_ty)
-                            .unwrap_or_else(|| func.ret_ty(db).substitute(&subst.args))
+                            .unwrap_or_else(|| {
+                                func.ret_ty(db)
+                                    .substitute(&subst.args)
+                                    .substitute(&bindings)
+                            })
                     }
                     TyKind::BuiltinFunction(func) => {
                         let params = func.params(db);
@@ -859,7 +1250,12 @@ impl TyContext<'_> {
                         let errors = slots.assign_args(args, None).0;
 
                         for error in errors {
-                            self.add_expr_diagnostic_error(file, error.expr, error.message);
+                            self.add_expr_diagnostic_error(
+                                file,
+                                error.expr,
+                                DiagnosticCode::InvalidArgument,
+                                error.message,
+                            );
                         }
 
                         let mut missing_params = Vec::new();
@@ -867,20 +1263,24 @@ impl TyContext<'_> {
                         // Validate argument types.
                         for (param, slot) in params.iter().zip(slots.slots) {
                             let param_ty = resolve_type_ref_opt(self, param.type_ref(), None);
+                            let name = param.name();
+                            let constraints =
+                                AttributeConstraints::from_builtin_param(&name, param);
                             let mut validate_provider = |provider| match provider {
                                 SlotProvider::Missing => {
-                                    if param.is_mandatory() {
-                                        let name = param.name();
-                                        if !name.is_missing() {
-                                            missing_params.push(name.clone());
-                                        }
+                                    if param.is_mandatory() && !name.is_missing() {
+                                        missing_params.push(name.clone());
                                     }
                                 }
                                 SlotProvider::Single(expr, index) => {
                                     let ty = &arg_tys[index];
-                                    if !assign_tys(db, ty, &param_ty) {
-                                        self.add_expr_diagnostic_error(file, expr, format!("Argument of type \"{}\" cannot be assigned to parameter of type \"{}\"", ty.display(self.db).alt(), param_ty.display(self.db).alt()));
-                                    }
+                                    self.check_attribute_value(
+                                        file,
+                                        expr,
+                                        ty,
+                                        &param_ty,
+                                        &constraints,
+                                    );
                                 }
                                 _ => {}
                             };
@@ -908,7 +1308,12 @@ impl TyContext<'_> {
                                 message.push('"');
                             }
 
-                            self.add_expr_diagnostic_error(file, expr, message);
+                            self.add_expr_diagnostic_error(
+                                file,
+                                expr,
+                                DiagnosticCode::MissingArgument,
+                                message,
+                            );
                         }
 
                         func.maybe_unique_ret_type(self, file, expr, args_with_ty)
@@ -928,9 +1333,13 @@ impl TyContext<'_> {
                                 match provider {
                                     SlotProvider::Single(expr, index) => {
                                         let ty = &arg_tys[index];
-                                        if !assign_tys(db, ty, &expected_ty) {
-                                            self.add_expr_diagnostic_error(file, expr, format!("Argument of type \"{}\" cannot be assigned to parameter of type \"{}\"", ty.display(self.db).alt(), expected_ty.display(self.db).alt()));
-                                        }
+                                        self.check_attribute_value(
+                                            file,
+                                            expr,
+                                            ty,
+                                            &expected_ty,
+                                            &AttributeConstraints::from_attribute(name, attr),
+                                        );
                                     }
                                     SlotProvider::Missing => {
                                         if attr.mandatory {
@@ -954,7 +1363,12 @@ impl TyContext<'_> {
                                 message.push('"');
                             }
 
-                            self.add_expr_diagnostic_error(file, expr, message);
+                            self.add_expr_diagnostic_error(
+                                file,
+                                expr,
+                                DiagnosticCode::MissingArgument,
+                                message,
+                            );
                         }
 
                         self.none_ty()
@@ -981,9 +1395,7 @@ impl TyContext<'_> {
                                 match provider {
                                     SlotProvider::Single(expr, index) => {
                                         let ty = &arg_tys[index];
-                                        if !assign_tys(db, ty, &expected_ty) {
-                                            self.add_expr_diagnostic_error(file, expr, format!("Argument of type \"{}\" cannot be assigned to parameter of type \"{}\"", ty.display(self.db).alt(), expected_ty.display(self.db).alt()));
-                                        }
+                                        self.check_argument_ty(file, expr, ty, &expected_ty);
                                     }
                                     SlotProvider::Missing => {
                                         if data.attr.mandatory {
@@ -1007,7 +1419,12 @@ impl TyContext<'_> {
                                 message.push('"');
                             }
 
-                            self.add_expr_diagnostic_error(file, expr, message);
+                            self.add_expr_diagnostic_error(
+                                file,
+                                expr,
+                                DiagnosticCode::MissingArgument,
+                                message,
+                            );
                         }
 
                         self.none_ty()
@@ -1027,6 +1444,7 @@ impl TyContext<'_> {
                                     self.add_expr_diagnostic_error(
                                         file,
                                         *expr,
+                                        DiagnosticCode::InvalidArgument,
                                         format!("Cannot set attribute \"{}\"", name.as_str()),
                                     );
                                 }
@@ -1041,9 +1459,13 @@ impl TyContext<'_> {
                                 match provider {
                                     SlotProvider::Single(expr, index) => {
                                         let ty = &arg_tys[index];
-                                        if !assign_tys(db, ty, &expected_ty) {
-                                            self.add_expr_diagnostic_error(file, expr, format!("Argument of type \"{}\" cannot be assigned to parameter of type \"{}\"", ty.display(self.db).alt(), expected_ty.display(self.db).alt()));
-                                        }
+                                        self.check_attribute_value(
+                                            file,
+                                            expr,
+                                            ty,
+                                            &expected_ty,
+                                            &AttributeConstraints::from_attribute(name, attr),
+                                        );
                                     }
                                     SlotProvider::Missing => {
                                         if attr.mandatory {
@@ -1067,15 +1489,26 @@ impl TyContext<'_> {
                                 message.push('"');
                             }
 
-                            self.add_expr_diagnostic_error(file, expr, message);
+                            self.add_expr_diagnostic_error(
+                                file,
+                                expr,
+                                DiagnosticCode::MissingArgument,
+                                message,
+                            );
                         }
 
                         self.none_ty()
                     }
-                    TyKind::Unknown | TyKind::Any | TyKind::Unbound => self.unknown_ty(),
+                    // A `Never` callee can't actually be called, e.g. because narrowing ruled out
+                    // all of its types. The result is unknown rather than `Never`, since the
+                    // latter would mark the code after the call as unreachable.
+                    TyKind::Unknown | TyKind::Any | TyKind::Unbound | TyKind::Never => {
+                        self.unknown_ty()
+                    }
                     _ => self.add_expr_diagnostic_warning_ty(
                         file,
                         expr,
+                        DiagnosticCode::UnsupportedOperation,
                         format!("Type \"{}\" is not callable", callee_ty.display(db).alt()),
                     ),
                 }
@@ -1114,6 +1547,7 @@ impl TyContext<'_> {
                         self.add_expr_diagnostic_error(
                             file,
                             expr,
+                            DiagnosticCode::TypeMismatch,
                             "`start`, `stop`, and `step` operands must be integers or `None`",
                         )
                     }
@@ -1137,6 +1571,7 @@ impl TyContext<'_> {
                     _ => self.add_expr_diagnostic_warning_ty(
                         file,
                         expr,
+                        DiagnosticCode::InvalidIndex,
                         format!(
                             "Cannot slice expression of type \"{}\"",
                             lhs_ty.display(db).alt()
@@ -1146,7 +1581,19 @@ impl TyContext<'_> {
             }
             Expr::Paren { expr } => self.infer_expr(file, *expr),
             Expr::Lambda { func, .. } => {
-                TyKind::Function(FunctionDef::Lambda { func: *func }).intern()
+                // Lambdas have no type annotations, so their parameter and return types are
+                // inferred here and stored on the type itself.
+                let def = FunctionDef::Lambda {
+                    func: *func,
+                    expr: InFile { file, value: expr },
+                };
+                let param_tys = func
+                    .params(db)
+                    .iter()
+                    .map(|param| self.infer_param(file, *param))
+                    .collect();
+                let ret_ty = self.infer_function_ret_ty(&def, &[]);
+                TyKind::Lambda(def, param_tys, ret_ty).intern()
             }
             _ => self.unknown_ty(),
         };
@@ -1160,6 +1607,7 @@ impl TyContext<'_> {
             Err(()) => self.add_expr_diagnostic_error_ty(
                 file,
                 parent,
+                DiagnosticCode::UnsupportedOperation,
                 format!(
                     "Operator \"{}\" is not supported for type \"{}\"",
                     op,
@@ -1175,6 +1623,7 @@ impl TyContext<'_> {
             (UnaryOp::Arith(_), TyKind::Float) => self.float_ty(),
             (UnaryOp::Not, _) => self.bool_ty(),
             (_, TyKind::Unknown | TyKind::Any) => self.unknown_ty(),
+            (_, TyKind::Never) => ty.clone(),
             (op, TyKind::Union(tys)) => {
                 let mut mapped_tys = Vec::with_capacity(tys.len());
                 for ty in tys.iter() {
@@ -1203,6 +1652,7 @@ impl TyContext<'_> {
             self.add_expr_diagnostic_warning_ty(
                 file,
                 parent,
+                DiagnosticCode::UnsupportedOperation,
                 format!(
                     "Operator \"{}\" not supported for types \"{}\" and \"{}\"",
                     op,
@@ -1216,6 +1666,10 @@ impl TyContext<'_> {
             (TyKind::Any | TyKind::Unknown, _) | (_, TyKind::Any | TyKind::Unknown) => {
                 return self.unknown_ty()
             }
+            // Operations on `Never` values are never evaluated. The right-hand side of a logical
+            // operator is the exception, since its type is just dropped from the result.
+            (TyKind::Never, _) => return Ty::never(),
+            (_, TyKind::Never) if !matches!(op, BinaryOp::Logic(_)) => return Ty::never(),
             _ => {}
         }
 
@@ -1292,6 +1746,7 @@ impl TyContext<'_> {
                     self.add_expr_diagnostic_warning(
                         file,
                         parent,
+                        DiagnosticCode::UnsupportedOperation,
                         None,
                         format!(
                             "Operator \"{}\" not supported for types \"{}\" and \"{}\"",
@@ -1304,13 +1759,13 @@ impl TyContext<'_> {
                 self.bool_ty()
             }
             BinaryOp::Logic(LogicOp::Or) => match (lhs_kind, rhs_kind) {
-                // TODO(withered-magic): Strip None from optional types once we implement narrowing.
                 (TyKind::Bool(Some(lhs)), TyKind::Bool(Some(rhs))) => {
                     TyKind::Bool(Some(*lhs || *rhs)).intern()
                 }
                 (TyKind::Bool(Some(false)) | TyKind::Int(Some(0)) | TyKind::None, _) => rhs,
                 (TyKind::Tuple(Tuple::Simple(tys)), _) if tys.is_empty() => rhs,
-                _ => Ty::union([lhs, rhs].into_iter()),
+                // The left-hand side is only the result if it's truthy, so it can't be `None`.
+                _ => Ty::union([narrow_none(&lhs, false), rhs].into_iter()),
             },
             BinaryOp::Logic(LogicOp::And) => match (lhs_kind, rhs_kind) {
                 (TyKind::Bool(Some(lhs)), TyKind::Bool(Some(rhs))) => {
@@ -1363,6 +1818,7 @@ impl TyContext<'_> {
         }
         self.infer_source_expr_assign_inner(file, source, expected_ty, execution_scope);
         self.cx.source_assign_done.insert(key);
+        self.cx.record_cache_entry(CacheEntry::SourceAssign(key));
     }
 
     fn infer_source_expr_assign_inner(
@@ -1410,6 +1866,7 @@ impl TyContext<'_> {
                                     file,
                                     Severity::Error,
                                     type_ref.1,
+                                    DiagnosticCode::InvalidType,
                                     None,
                                     error,
                                 );
@@ -1453,21 +1910,13 @@ impl TyContext<'_> {
             None => return,
         };
 
-        let sub_ty = match source_ty.kind() {
-            TyKind::List(ty)
-            | TyKind::Tuple(Tuple::Variable(ty))
-            | TyKind::Protocol(Protocol::Iterable(ty) | Protocol::Sequence(ty)) => ty.clone(),
-            TyKind::Tuple(Tuple::Simple(tys)) => Ty::union(tys.iter().cloned()),
-            TyKind::Dict(key_ty, _, _) => key_ty.clone(),
-            TyKind::Any => self.any_ty(),
-            TyKind::Range => self.int_ty(),
-            TyKind::StringElems => self.string_ty(),
-            TyKind::BytesElems => self.int_ty(),
-            TyKind::Unknown => self.unknown_ty(),
-            _ => {
+        let sub_ty = match self.iterable_element_ty(&source_ty) {
+            Some(ty) => ty,
+            None => {
                 self.add_expr_diagnostic_warning(
                     file,
                     source,
+                    DiagnosticCode::UnsupportedOperation,
                     None,
                     format!("Type \"{}\" is not iterable", source_ty.display(db).alt()),
                 );
@@ -1484,6 +1933,24 @@ impl TyContext<'_> {
         }
     }
 
+    /// Returns the type of the elements produced by iterating over a value of type `ty`, or
+    /// `None` if `ty` isn't iterable.
+    fn iterable_element_ty(&self, ty: &Ty) -> Option<Ty> {
+        Some(match ty.kind() {
+            TyKind::List(ty)
+            | TyKind::Tuple(Tuple::Variable(ty))
+            | TyKind::Protocol(Protocol::Iterable(ty) | Protocol::Sequence(ty)) => ty.clone(),
+            TyKind::Tuple(Tuple::Simple(tys)) => Ty::union(tys.iter().cloned()),
+            TyKind::Dict(key_ty, _, _) => key_ty.clone(),
+            TyKind::Any => self.any_ty(),
+            TyKind::Range => self.int_ty(),
+            TyKind::StringElems => self.string_ty(),
+            TyKind::BytesElems => self.int_ty(),
+            TyKind::Unknown => self.unknown_ty(),
+            _ => return None,
+        })
+    }
+
     pub(crate) fn infer_name(
         &mut self,
         file: File,
@@ -1565,8 +2032,22 @@ impl TyContext<'_> {
                     self.infer_assign(*file, expr, source, known_ty.clone(), def_execution_scope)
                 }));
 
-                if known_ty.is_some() {
-                    return known_ty;
+                if let Some(known_ty) = known_ty {
+                    // Known types can still be narrowed by conditions, e.g. `if x != None:`.
+                    if !self.shared_state.options.use_code_flow_analysis {
+                        return Some(known_ty);
+                    }
+                    return Some(
+                        self.infer_name_from_code_flow(
+                            file,
+                            name,
+                            hir_id,
+                            curr_execution_scope,
+                            &known_ty,
+                            Some(&known_ty),
+                        )
+                        .unwrap_or(known_ty),
+                    );
                 }
 
                 (def_execution_scope, effective_ty)
@@ -1574,9 +2055,11 @@ impl TyContext<'_> {
 
             None => {
                 return Some(match resolver.resolve_name_in_prelude_or_builtins(name)? {
-                    ScopeDef::IntrinsicFunction(func) => {
-                        TyKind::IntrinsicFunction(func, Substitution::new_identity(0)).intern()
-                    }
+                    ScopeDef::IntrinsicFunction(func) => TyKind::IntrinsicFunction(
+                        func,
+                        Substitution::new_identity(func.num_vars(self.db)),
+                    )
+                    .intern(),
                     ScopeDef::BuiltinFunction(func) => TyKind::BuiltinFunction(func).intern(),
                     ScopeDef::BuiltinVariable(type_ref) => {
                         resolve_builtin_type_ref(self.db, &type_ref).0
@@ -1590,7 +2073,6 @@ impl TyContext<'_> {
             }
         };
 
-        // TODO(withered-magic): We should eventually apply narrowing to the effective type here.
         let start_ty = if def_execution_scope != curr_execution_scope {
             effective_ty.clone()
         } else {
@@ -1600,8 +2082,15 @@ impl TyContext<'_> {
         // See if we can narrow the effective type further through code-flow analysis. If not, then
         // fall back to the effective type.
         Some(
-            self.infer_name_from_code_flow(file, name, hir_id, curr_execution_scope, &start_ty)
-                .unwrap_or(effective_ty),
+            self.infer_name_from_code_flow(
+                file,
+                name,
+                hir_id,
+                curr_execution_scope,
+                &start_ty,
+                None,
+            )
+            .unwrap_or(effective_ty),
         )
     }
 
@@ -1612,19 +2101,29 @@ impl TyContext<'_> {
         usage: impl Into<ScopeHirId>,
         execution_scope: ExecutionScopeId,
         start_ty: &Ty,
+        known_ty: Option<&Ty>,
     ) -> Option<Ty> {
         // If an expression is missing its corresponding node in the code flow graph, that
         // means the expression is unreachable. We use the `Never` type to represent this case.
         let cfg = code_flow_graph(self.db, file).cfg(self.db);
         let start_node = match cfg.hir_to_flow_node.get(&usage.into()) {
             Some(start_node) => start_node,
-            None => return Some(TyKind::Never.intern()),
+            None => return Some(known_ty.cloned().unwrap_or_else(|| TyKind::Never.intern())),
         };
-        self.infer_ref_from_flow_node(cfg, file, execution_scope, name, start_ty, *start_node)
+        self.infer_ref_from_flow_node(
+            cfg,
+            file,
+            execution_scope,
+            name,
+            start_ty,
+            known_ty,
+            *start_node,
+        )
     }
 
     /// Returning `None` here means that code-flow analysis failed and that a fallback type should
-    /// be returned instead.
+    /// be returned instead. If the name has a known type, e.g. because it's a parameter, then
+    /// assignments to it are assumed to have that type and only narrowing is applied.
     fn infer_ref_from_flow_node(
         &mut self,
         cfg: &CodeFlowGraph,
@@ -1632,6 +2131,7 @@ impl TyContext<'_> {
         execution_scope: ExecutionScopeId,
         name: &Name,
         start_ty: &Ty,
+        known_ty: Option<&Ty>,
         start_node: FlowNodeId,
     ) -> Option<Ty> {
         if let Some(res) =
@@ -1658,6 +2158,9 @@ impl TyContext<'_> {
                         curr_node_id = *antecedent;
                         continue;
                     }
+                    if let Some(known_ty) = known_ty {
+                        break 'outer Some(known_ty.clone());
+                    }
 
                     self.infer_source_expr_assign(file, *source, None, execution_scope);
                     self.cx
@@ -1675,6 +2178,7 @@ impl TyContext<'_> {
                             execution_scope,
                             name,
                             start_ty,
+                            known_ty,
                             *antecedent,
                         ) {
                             Some(antecedent_ty) => {
@@ -1685,7 +2189,18 @@ impl TyContext<'_> {
                     }
                     Ty::union(antecedent_tys.into_iter())
                 }
-                FlowNode::Loop { .. } => break 'outer None, // TODO(withered-magic): Correctly handle loops.
+                FlowNode::Loop { .. } => match self.infer_ref_at_loop(
+                    cfg,
+                    file,
+                    execution_scope,
+                    name,
+                    start_ty,
+                    known_ty,
+                    curr_node_id,
+                ) {
+                    Some(ty) => ty,
+                    None => break 'outer None,
+                },
                 FlowNode::Call { expr, antecedent } => {
                     let ty = self.infer_expr(file, *expr);
                     if matches!(ty.kind(), TyKind::Never) {
@@ -1695,6 +2210,29 @@ impl TyContext<'_> {
                         continue;
                     }
                 }
+                FlowNode::TrueCondition { expr, antecedent }
+                | FlowNode::FalseCondition { expr, antecedent } => {
+                    if !self.condition_narrows_name(file, *expr, name) {
+                        curr_node_id = *antecedent;
+                        continue;
+                    }
+
+                    let assume_true = matches!(curr_node, FlowNode::TrueCondition { .. });
+                    match self.infer_ref_from_flow_node(
+                        cfg,
+                        file,
+                        execution_scope,
+                        name,
+                        start_ty,
+                        known_ty,
+                        *antecedent,
+                    ) {
+                        Some(ty) => {
+                            self.narrow_ty_for_condition(file, name, &ty, *expr, assume_true)
+                        }
+                        None => break 'outer None,
+                    }
+                }
                 FlowNode::Unreachable { .. } => Ty::never(),
             };
 
@@ -1704,6 +2242,99 @@ impl TyContext<'_> {
         self.cache_ref_type_at_flow_node(file, execution_scope, name, start_node, res)
     }
 
+    /// Infers the type of a name at the start of a loop. The loop's antecedents include the back
+    /// edges from its body, so we start with the type at the loop's entry and then re-evaluate
+    /// the antecedents until the type stops changing. Anything inferred from an approximation
+    /// that turns out to be wrong is discarded.
+    fn infer_ref_at_loop(
+        &mut self,
+        cfg: &CodeFlowGraph,
+        file: File,
+        execution_scope: ExecutionScopeId,
+        name: &Name,
+        start_ty: &Ty,
+        known_ty: Option<&Ty>,
+        loop_node: FlowNodeId,
+    ) -> Option<Ty> {
+        let FlowNode::Loop { antecedents } = &cfg.flow_nodes[loop_node] else {
+            return None;
+        };
+        let key = CodeFlowCacheKey {
+            file,
+            execution_scope,
+            name: name.clone(),
+            flow_node: loop_node,
+        };
+
+        // If this loop is already being evaluated, then we've reached it again through one of
+        // its back edges.
+        if let Some(ty) = self.cx.loop_approximations.get(&key) {
+            return Some(ty.clone());
+        }
+        if let Some(res) =
+            self.read_cached_ref_type_at_flow_node(file, execution_scope, name, loop_node)
+        {
+            return res;
+        }
+
+        let checkpoint = self.cx.checkpoint();
+        let mut approximation = Ty::never();
+        let mut iterations = 0;
+        let mut widened = false;
+        let res = loop {
+            self.cx
+                .loop_approximations
+                .insert(key.clone(), approximation.clone());
+
+            // The first antecedent is the loop's entry. Only it is evaluated at first, since
+            // starting the back edges from `Never` would make e.g. `x = x + 1` look like an
+            // unsupported operation.
+            let evaluated = if iterations == 0 {
+                &antecedents[..antecedents.len().min(1)]
+            } else {
+                &antecedents[..]
+            };
+            let Some(antecedent_tys) = evaluated
+                .iter()
+                .map(|antecedent| {
+                    self.infer_ref_from_flow_node(
+                        cfg,
+                        file,
+                        execution_scope,
+                        name,
+                        start_ty,
+                        known_ty,
+                        *antecedent,
+                    )
+                })
+                .collect::<Option<Vec<_>>>()
+            else {
+                break None;
+            };
+
+            let ty = Ty::union(antecedent_tys.into_iter());
+            if widened || (iterations > 0 && Ty::eq(&ty, &approximation)) {
+                break Some(approximation);
+            }
+
+            // The approximation was wrong, so everything inferred from it has to go.
+            self.cx.rollback_to(checkpoint);
+            iterations += 1;
+            if iterations < MAX_LOOP_ITERATIONS {
+                approximation = ty;
+            } else {
+                // Types that keep growing, e.g. `x = [x]`, never converge. In that case, we
+                // widen the approximation one last time and use it as the result.
+                approximation = Ty::union([approximation, ty].into_iter());
+                widened = true;
+            }
+        };
+
+        self.cx.loop_approximations.remove(&key);
+        self.cx.commit(checkpoint);
+        self.cache_ref_type_at_flow_node(file, execution_scope, name, loop_node, res)
+    }
+
     fn exists_flow_path(
         &mut self,
         cfg: &CodeFlowGraph,
@@ -1715,7 +2346,9 @@ impl TyContext<'_> {
             true
         } else {
             match &cfg.flow_nodes[from_node] {
-                FlowNode::Assign { antecedent, .. } => {
+                FlowNode::Assign { antecedent, .. }
+                | FlowNode::TrueCondition { antecedent, .. }
+                | FlowNode::FalseCondition { antecedent, .. } => {
                     self.exists_flow_path(cfg, file, *antecedent, to_node)
                 }
                 FlowNode::Branch { antecedents } => antecedents
@@ -1760,15 +2393,16 @@ impl TyContext<'_> {
         flow_node: FlowNodeId,
         res: Option<Ty>,
     ) -> Option<Ty> {
-        self.cx.flow_node_type_cache.insert(
-            CodeFlowCacheKey {
-                file,
-                execution_scope,
-                name: name.clone(),
-                flow_node,
-            },
-            res.clone(),
-        );
+        let key = CodeFlowCacheKey {
+            file,
+            execution_scope,
+            name: name.clone(),
+            flow_node,
+        };
+        self.cx
+            .flow_node_type_cache
+            .insert(key.clone(), res.clone());
+        self.cx.record_cache_entry(CacheEntry::FlowNode(key));
         res
     }
 
@@ -1795,10 +2429,11 @@ impl TyContext<'_> {
                 // If we have an expected type from a type comment, use that.
                 // We also emit any error if the source and expected types aren't compatible.
                 if let Some(expected_ty) = expected_ty {
-                    if !assign_tys(self.db, &source_ty, &expected_ty) {
+                    if !self.is_expr_assignable(file, root, &source_ty, &expected_ty) {
                         self.add_expr_diagnostic_error(
                             file,
                             root,
+                            DiagnosticCode::TypeMismatch,
                             format!(
                                 "Expression of type \"{}\" cannot be assigned to variable of type \"{}\"",
                                 source_ty.display(self.db).alt(),
@@ -1849,6 +2484,7 @@ impl TyContext<'_> {
                     self.add_expr_diagnostic_error(
                         file,
                         root,
+                        DiagnosticCode::InvalidAssignment,
                         format!(
                             "Tuple size mismatch, {} on left-hand side and {} on right-hand side",
                             exprs.len(),
@@ -1873,6 +2509,7 @@ impl TyContext<'_> {
                 self.add_expr_diagnostic_warning(
                     file,
                     root,
+                    DiagnosticCode::UnsupportedOperation,
                     None,
                     format!(
                         "Type \"{}\" is not iterable",
@@ -1916,9 +2553,9 @@ impl TyContext<'_> {
     }
 
     fn set_expr_type(&mut self, file: File, expr: ExprId, ty: Ty) -> Ty {
-        self.cx
-            .type_of_expr
-            .insert(FileExprId::new(file, expr), ty.clone());
+        let id = FileExprId::new(file, expr);
+        self.cx.type_of_expr.insert(id, ty.clone());
+        self.cx.record_cache_entry(CacheEntry::Expr(id));
         ty
     }
 
@@ -1945,14 +2582,291 @@ impl TyContext<'_> {
         &mut self,
         file: File,
         expr: ExprId,
+        code: DiagnosticCode,
         tags: Option<Vec<DiagnosticTag>>,
         message: T,
     ) {
-        self.add_expr_diagnostic_with_severity(file, expr, Severity::Warning, tags, message)
+        self.add_expr_diagnostic_with_severity(file, expr, Severity::Warning, code, tags, message)
+    }
+
+    /// Reports an error if an argument's type isn't assignable to the type of the parameter that
+    /// it's passed to. The parameter's type is recorded either way, for use by quick fixes.
+    fn check_argument_ty(&mut self, file: File, expr: ExprId, ty: &Ty, param_ty: &Ty) {
+        let id = FileExprId::new(file, expr);
+        self.cx.expected_type_of_arg.insert(id, param_ty.clone());
+        self.cx.record_cache_entry(CacheEntry::ExpectedArg(id));
+        if !self.is_expr_assignable(file, expr, ty, param_ty) {
+            self.add_expr_diagnostic_error(
+                file,
+                expr,
+                DiagnosticCode::TypeMismatch,
+                format!(
+                    "Argument of type \"{}\" cannot be assigned to parameter of type \"{}\"",
+                    ty.display(self.db).alt(),
+                    param_ty.display(self.db).alt()
+                ),
+            );
+        }
+    }
+
+    /// Checks the value passed for a rule attribute. In addition to the usual type check, this
+    /// only allows `select()` for configurable attributes, validates label strings, and checks
+    /// literal values against the attribute's allowlist.
+    fn check_attribute_value(
+        &mut self,
+        file: File,
+        expr: ExprId,
+        ty: &Ty,
+        expected_ty: &Ty,
+        attr: &AttributeConstraints,
+    ) {
+        let mut operands = Vec::new();
+        self.collect_concatenated_operands(file, expr, &mut operands);
+        let operands = operands
+            .into_iter()
+            .map(|operand| (operand, self.select_branches(file, operand)))
+            .collect::<Vec<_>>();
+
+        if operands.iter().all(|(_, branches)| branches.is_none()) {
+            self.check_attribute_value_ty(file, expr, ty, expected_ty);
+            self.check_attribute_literals(file, expr, attr);
+            return;
+        }
+
+        // The value contains `select()` calls, e.g. `["a"] + select({...})`, so check each of the
+        // other operands and each of the select branches individually.
+        for (operand, branches) in operands {
+            match branches {
+                Some(_) if !attr.configurable => {
+                    self.add_expr_diagnostic_error(
+                        file,
+                        operand,
+                        DiagnosticCode::InvalidAttributeValue,
+                        format!(
+                            "Attribute \"{}\" is not configurable and cannot be set with select()",
+                            attr.name.as_str()
+                        ),
+                    );
+                }
+                Some(branches) => {
+                    for branch in branches {
+                        let ty = self.infer_expr(file, branch);
+                        self.check_attribute_value(file, branch, &ty, expected_ty, attr);
+                    }
+                }
+                None => {
+                    let ty = self.infer_expr(file, operand);
+                    self.check_attribute_value_ty(file, operand, &ty, expected_ty);
+                    self.check_attribute_literals(file, operand, attr);
+                }
+            }
+        }
     }
 
-    fn add_expr_diagnostic_error<T: Into<String>>(&mut self, file: File, expr: ExprId, message: T) {
-        self.add_expr_diagnostic_with_severity(file, expr, Severity::Error, None, message)
+    /// Checks the type of a value passed for a rule attribute. Like Bazel, this also accepts the
+    /// integers 0 and 1 for boolean attributes.
+    fn check_attribute_value_ty(&mut self, file: File, expr: ExprId, ty: &Ty, expected_ty: &Ty) {
+        if matches!(expected_ty.kind(), TyKind::Bool(_))
+            && matches!(ty.kind(), TyKind::Int(Some(0 | 1)))
+        {
+            return;
+        }
+        self.check_argument_ty(file, expr, ty, expected_ty);
+    }
+
+    /// Collects the operands of a chain of `+` operations, e.g. `a + (b + c)`.
+    fn collect_concatenated_operands(&self, file: File, expr: ExprId, operands: &mut Vec<ExprId>) {
+        match &module(self.db, file)[expr] {
+            Expr::Binary {
+                lhs,
+                rhs,
+                op: Some(BinaryOp::Arith(ArithOp::Add)),
+            } => {
+                self.collect_concatenated_operands(file, *lhs, operands);
+                self.collect_concatenated_operands(file, *rhs, operands);
+            }
+            Expr::Paren { expr } => self.collect_concatenated_operands(file, *expr, operands),
+            _ => operands.push(expr),
+        }
+    }
+
+    /// If the given expression is a call to the builtin `select()`, returns the values of the
+    /// dict literal passed to it.
+    fn select_branches(&mut self, file: File, expr: ExprId) -> Option<Vec<ExprId>> {
+        let module = module(self.db, file);
+        let Expr::Call { callee, args } = &module[expr] else {
+            return None;
+        };
+        match &module[*callee] {
+            Expr::Name { name } if name.as_str() == "select" => {}
+            _ => return None,
+        }
+
+        // Skip user-defined functions that shadow the builtin.
+        if let TyKind::Function(_) | TyKind::Lambda(_, _, _) = self.infer_expr(file, *callee).kind()
+        {
+            return None;
+        }
+
+        Some(match args.first() {
+            Some(Argument::Simple { expr }) => match &module[*expr] {
+                Expr::Dict { entries } => entries.iter().map(|entry| entry.value).collect(),
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        })
+    }
+
+    /// Checks literal label strings and literal values in the value of a rule attribute.
+    fn check_attribute_literals(&mut self, file: File, expr: ExprId, attr: &AttributeConstraints) {
+        let module = module(self.db, file);
+        if let Some(values) = attr.values {
+            let elements = match &module[expr] {
+                Expr::List { exprs } => exprs.to_vec(),
+                _ => vec![expr],
+            };
+            for element in elements {
+                let ty = self.infer_expr(file, element);
+                let Some(value) = literal_value_text(self.db, &ty) else {
+                    continue;
+                };
+                if !values.contains(&ty) {
+                    let allowed = values
+                        .iter()
+                        .filter_map(|ty| literal_value_text(self.db, ty))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    self.add_expr_diagnostic_error(
+                        file,
+                        element,
+                        DiagnosticCode::InvalidAttributeValue,
+                        format!(
+                            "Value {} is not allowed for attribute \"{}\", expected one of {}",
+                            value,
+                            attr.name.as_str(),
+                            allowed
+                        ),
+                    );
+                }
+            }
+        }
+
+        let labels = match (attr.label_position, &module[expr]) {
+            (Some(LabelPosition::Value), _) => vec![expr],
+            (Some(LabelPosition::ListElement), Expr::List { exprs }) => exprs.to_vec(),
+            (Some(LabelPosition::DictKey), Expr::Dict { entries }) => {
+                entries.iter().map(|entry| entry.key).collect()
+            }
+            (Some(LabelPosition::DictValue), Expr::Dict { entries }) => {
+                entries.iter().map(|entry| entry.value).collect()
+            }
+            _ => Vec::new(),
+        };
+        for label in labels {
+            if let TyKind::String(Some(s)) = self.infer_expr(file, label).kind() {
+                let text = s.value(self.db);
+                match Label::parse(text) {
+                    Ok(parsed) => self.check_label_resolves(file, label, text, &parsed),
+                    Err(err) => self.add_expr_diagnostic_error(
+                        file,
+                        label,
+                        DiagnosticCode::InvalidAttributeValue,
+                        format!("Invalid label \"{}\": {}", text, err),
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Reports labels that don't refer to an existing package, source file or target.
+    fn check_label_resolves(&mut self, file: File, expr: ExprId, text: &str, label: &Label) {
+        let db = self.db;
+
+        // Relative labels passed to rules in macros are resolved against the package of the
+        // BUILD file that calls the macro, which isn't known here.
+        if label.is_relative() && file.api_context(db) != Some(APIContext::Build) {
+            return;
+        }
+
+        // Skip pseudo-targets used in `visibility`.
+        if (label.repo().is_empty() && label.package() == "visibility")
+            || matches!(label.target(), "__pkg__" | "__subpackages__")
+        {
+            return;
+        }
+
+        let resolved_path = match db.resolve_label(text, file.id(db)).resolved_path(db) {
+            Some(resolved_path) => resolved_path.clone(),
+            None => return,
+        };
+        let (severity, reason) = match resolved_path {
+            ResolvedPath::Source { .. } => return,
+            ResolvedPath::BuildTarget {
+                build_file, target, ..
+            } => {
+                let Some(names) = db
+                    .get_file(build_file)
+                    .and_then(|build_file| declared_targets(db, build_file).names(db).as_ref())
+                else {
+                    return;
+                };
+                // Targets like `:node_modules/foo` can refer to files in an output directory.
+                if names.contains(&target)
+                    || target
+                        .split_once('/')
+                        .is_some_and(|(dir, _)| names.contains(dir))
+                {
+                    return;
+                }
+
+                if target.contains('.') {
+                    // Files that aren't in the package might still be implicit outputs of a
+                    // declared rule, e.g. `libfoo.so` for `foo`.
+                    if names.iter().any(|name| target.contains(name.as_str())) {
+                        return;
+                    }
+                    (
+                        Severity::Warning,
+                        format!("source file \"{}\" does not exist", target),
+                    )
+                } else {
+                    (
+                        Severity::Warning,
+                        format!(
+                            "target \"{}\" is not declared in the package's BUILD file",
+                            target
+                        ),
+                    )
+                }
+            }
+            ResolvedPath::MissingPackage => {
+                (Severity::Warning, "package does not exist".to_string())
+            }
+            // The repository might be fetched later, so this isn't necessarily a mistake.
+            ResolvedPath::UnfetchedRepository { repo } => (
+                Severity::Info,
+                UnfetchedRepositoryError { repo }.to_string(),
+            ),
+        };
+
+        self.add_expr_diagnostic_with_severity(
+            file,
+            expr,
+            severity,
+            DiagnosticCode::UnresolvedLabel,
+            None,
+            format!("Could not resolve label \"{}\": {}", text, reason),
+        );
+    }
+
+    fn add_expr_diagnostic_error<T: Into<String>>(
+        &mut self,
+        file: File,
+        expr: ExprId,
+        code: DiagnosticCode,
+        message: T,
+    ) {
+        self.add_expr_diagnostic_with_severity(file, expr, Severity::Error, code, None, message)
     }
 
     fn add_expr_diagnostic_with_severity<T: Into<String>>(
@@ -1960,6 +2874,7 @@ impl TyContext<'_> {
         file: File,
         expr: ExprId,
         severity: Severity,
+        code: DiagnosticCode,
         tags: Option<Vec<DiagnosticTag>>,
         message: T,
     ) {
@@ -1967,16 +2882,17 @@ impl TyContext<'_> {
             Some(ptr) => ptr.syntax_node_ptr().text_range(),
             None => return,
         };
-        self.add_diagnostic_for_range(file, severity, range, tags, message);
+        self.add_diagnostic_for_range(file, severity, range, code, tags, message);
     }
 
     fn add_expr_diagnostic_error_ty<T: Into<String>>(
         &mut self,
         file: File,
         expr: ExprId,
+        code: DiagnosticCode,
         message: T,
     ) -> Ty {
-        self.add_expr_diagnostic_error(file, expr, message);
+        self.add_expr_diagnostic_error(file, expr, code, message);
         self.unknown_ty()
     }
 
@@ -1984,9 +2900,10 @@ impl TyContext<'_> {
         &mut self,
         file: File,
         expr: ExprId,
+        code: DiagnosticCode,
         message: T,
     ) -> Ty {
-        self.add_expr_diagnostic_warning(file, expr, None, message);
+        self.add_expr_diagnostic_warning(file, expr, code, None, message);
         self.unknown_ty()
     }
 
@@ -1995,6 +2912,7 @@ impl TyContext<'_> {
         file: File,
         severity: Severity,
         range: TextRange,
+        code: DiagnosticCode,
         tags: Option<Vec<DiagnosticTag>>,
         message: T,
     ) {
@@ -2006,6 +2924,8 @@ impl TyContext<'_> {
                 range,
             },
             tags,
+            code,
+            related_information: None,
         });
     }
 
@@ -2020,6 +2940,7 @@ impl TyContext<'_> {
             .infer_ctx_attributes
             .then(|| self.infer_param_from_rule_usage(file, param))
             .and_then(|ty| ty)
+            .or_else(|| self.infer_lambda_param_from_call(file, param))
             .unwrap_or_else(|| {
                 let module = module(self.db, file);
                 let usage = module.param_to_def_stmt.get(&param).map(|(stmt, _)| *stmt);
@@ -2047,12 +2968,146 @@ impl TyContext<'_> {
                 }
             });
 
-        self.cx
-            .type_of_param
-            .insert(FileParamId::new(file, param), ty.clone());
+        let id = FileParamId::new(file, param);
+        self.cx.type_of_param.insert(id, ty.clone());
+        self.cx.record_cache_entry(CacheEntry::Param(id));
         ty
     }
 
+    /// Infers the type of a lambda's parameter from the call that the lambda is passed to, using
+    /// the declared type of the parameter that the lambda is assigned to. For example, `x` in
+    /// `sorted(names, key = lambda x: x.lower())` has the element type of `names`, since the `key`
+    /// parameter of `sorted()` is declared as a function that accepts the elements of the iterable.
+    fn infer_lambda_param_from_call(&mut self, file: File, param: ParamId) -> Option<Ty> {
+        let db = self.db;
+        let module = module(db, file);
+        let (lambda, index) = *module.param_to_lambda_expr.get(&param)?;
+        let (call, arg_index) = *module.lambda_expr_to_call_arg.get(&lambda)?;
+        let Expr::Call { callee, args } = &module[call] else {
+            return None;
+        };
+
+        // Only intrinsic functions declare callable parameter types for now.
+        let TyKind::IntrinsicFunction(func, subst) = self.infer_expr(file, *callee).kind().clone()
+        else {
+            return None;
+        };
+        let mut slots: Slots = func.params(db)[..].into();
+        slots.assign_args(args, None);
+
+        // The lambda itself is skipped when binding type variables, since its type depends on
+        // the types of its parameters.
+        let (param_tys, _) = self.bind_intrinsic_type_vars(func, &subst, &slots, |tcx, expr, i| {
+            (i != arg_index).then(|| tcx.infer_expr(file, expr))
+        });
+        let param_ty = slots
+            .slots
+            .iter()
+            .zip(param_tys)
+            .find_map(|(slot, param_ty)| {
+                let is_lambda_slot = slot.providers().iter().any(
+                    |provider| matches!(provider, SlotProvider::Single(_, i) if *i == arg_index),
+                );
+                is_lambda_slot.then_some(param_ty)
+            })?;
+        let callable_param_tys = match param_ty.kind() {
+            TyKind::Protocol(Protocol::Callable(param_tys, _)) => param_tys,
+            TyKind::Union(tys) => tys.iter().find_map(|ty| match ty.kind() {
+                TyKind::Protocol(Protocol::Callable(param_tys, _)) => Some(param_tys),
+                _ => None,
+            })?,
+            _ => return None,
+        };
+        callable_param_tys.get(index).cloned()
+    }
+
+    /// Returns the types of an intrinsic function's parameters, with the function's type
+    /// variables bound to the types of the arguments assigned to them, along with the bindings
+    /// themselves. `arg_ty` returns the type of the argument with the given expression and index,
+    /// or `None` if the argument shouldn't be used for binding.
+    fn bind_intrinsic_type_vars(
+        &mut self,
+        func: IntrinsicFunction,
+        subst: &Substitution,
+        slots: &Slots,
+        mut arg_ty: impl FnMut(&mut Self, ExprId, usize) -> Option<Ty>,
+    ) -> (Vec<Ty>, Vec<Ty>) {
+        let db = self.db;
+        let param_tys = func
+            .params(db)
+            .iter()
+            .map(|param| {
+                param
+                    .ty()
+                    .unwrap_or_else(|| self.any_ty())
+                    .substitute(&subst.args)
+            })
+            .collect::<Vec<_>>();
+        let mut bindings = vec![None; subst.args.len()];
+        for (param_ty, slot) in param_tys.iter().zip(slots.slots.iter()) {
+            for provider in slot.providers() {
+                if let SlotProvider::Single(expr, index) = provider {
+                    if let Some(ty) = arg_ty(self, *expr, *index) {
+                        bind_type_vars(db, param_ty, &ty, &mut bindings);
+                    }
+                }
+            }
+        }
+        let bindings = bindings
+            .into_iter()
+            .map(|ty| ty.unwrap_or_else(|| self.unknown_ty()))
+            .collect::<Vec<_>>();
+        let param_tys = param_tys
+            .into_iter()
+            .map(|ty| ty.substitute(&bindings))
+            .collect();
+        (param_tys, bindings)
+    }
+
+    /// Resolves a type from the signature of `def`, with the given type variables resolved to
+    /// bound variables.
+    fn resolve_signature_type_ref(
+        &mut self,
+        def: &FunctionDef,
+        type_ref: Option<TypeRef>,
+        type_vars: &[Name],
+    ) -> Ty {
+        type_ref
+            .map(|type_ref| {
+                resolve_type_ref_with_type_vars(self, &type_ref, def.stmt(), type_vars).0
+            })
+            .unwrap_or_else(|| self.unknown_ty())
+    }
+
+    /// Returns the return type of a function. This is the type given by the function's type
+    /// comment for `def` statements, with the function's type variables substituted by `args`,
+    /// and the type of the body for lambdas.
+    pub(crate) fn infer_function_ret_ty(&mut self, def: &FunctionDef, args: &[Ty]) -> Ty {
+        let db = self.db;
+        match def {
+            FunctionDef::Def { func, .. } => {
+                let type_vars = func.type_vars(db);
+                self.resolve_signature_type_ref(def, func.ret_type_ref(db), &type_vars)
+                    .substitute(args)
+            }
+            FunctionDef::Lambda { expr, .. } => {
+                let Expr::Lambda { body, .. } = module(db, expr.file)[expr.value] else {
+                    return self.unknown_ty();
+                };
+
+                // Guard against lambdas that call themselves, e.g. `f = lambda: f()`.
+                let id = FileExprId::new(expr.file, expr.value);
+                if self.cx.lambda_ret_stack.contains(&id) {
+                    return self.unknown_ty();
+                }
+                self.cx.lambda_ret_stack.push(id);
+                let ty = self.infer_expr(expr.file, body);
+                self.cx.lambda_ret_stack.pop();
+                ty
+            }
+        }
+    }
+
     fn infer_param_from_rule_usage(&mut self, file: File, param: ParamId) -> Option<Ty> {
         let module = module(self.db, file);
         let name = match module[module.param_to_def_stmt.get(&param)?.0] {
@@ -2092,10 +3147,26 @@ impl TyContext<'_> {
         type_ref: &TypeRef,
         usage: Option<StmtId>,
     ) -> Ty {
-        let (ty, errors) = usage.map_or_else(
-            || resolve_builtin_type_ref(self.db, type_ref),
-            |usage| resolve_type_ref(self, type_ref, Some(InFile { file, value: usage })),
-        );
+        let (ty, errors) = match usage {
+            Some(usage) => {
+                // Type variables aren't bound to anything within the function's body.
+                let type_vars = match module(self.db, file)[usage] {
+                    Stmt::Def { func, .. } => func.type_vars(self.db),
+                    _ => Vec::new(),
+                };
+                let (ty, errors) = resolve_type_ref_with_type_vars(
+                    self,
+                    type_ref,
+                    Some(InFile { file, value: usage }),
+                    &type_vars,
+                );
+                (
+                    ty.substitute(&vec![self.unknown_ty(); type_vars.len()]),
+                    errors,
+                )
+            }
+            None => resolve_builtin_type_ref(self.db, type_ref),
+        };
 
         // TODO(withered-magic): This will eventually need to handle diagnostics
         // for other places that type comments can appear.
@@ -2105,6 +3176,7 @@ impl TyContext<'_> {
                     file,
                     Severity::Warning,
                     ptr.syntax_node_ptr().text_range(),
+                    DiagnosticCode::InvalidType,
                     None,
                     error,
                 );
@@ -2133,6 +3205,19 @@ impl TyContext<'_> {
         };
 
         let ty = match &module(db, file).load_items[load_item] {
+            LoadItem::Direct { name, .. } | LoadItem::Aliased { name, .. }
+                if name.starts_with('_') =>
+            {
+                self.add_diagnostic_for_range(
+                    file,
+                    Severity::Error,
+                    range(),
+                    DiagnosticCode::PrivateSymbol,
+                    None,
+                    format!("Symbol \"{}\" is private and cannot be loaded", name),
+                );
+                self.unknown_ty()
+            }
             LoadItem::Direct { name, load_stmt }
             | LoadItem::Aliased {
                 name, load_stmt, ..
@@ -2145,6 +3230,7 @@ impl TyContext<'_> {
                                 file,
                                 Severity::Warning,
                                 range(),
+                                DiagnosticCode::CircularLoad,
                                 None,
                                 "Cannot load the current file",
                             );
@@ -2174,6 +3260,7 @@ impl TyContext<'_> {
                                     file,
                                     Severity::Warning,
                                     load_stmt.ptr(db).text_range(),
+                                    DiagnosticCode::CircularLoad,
                                     None,
                                     message.clone(),
                                 )
@@ -2184,6 +3271,7 @@ impl TyContext<'_> {
                                 file,
                                 Severity::Warning,
                                 load_stmt.ptr(db).text_range(),
+                                DiagnosticCode::CircularLoad,
                                 None,
                                 message,
                             );
@@ -2210,6 +3298,7 @@ impl TyContext<'_> {
                                         file,
                                         Severity::Warning,
                                         range(),
+                                        DiagnosticCode::UnresolvedSymbol,
                                         None,
                                         format!(
                                             "Could not resolve symbol \"{}\" in module \"{}\"",
@@ -2226,9 +3315,9 @@ impl TyContext<'_> {
             }
         };
 
-        self.cx
-            .type_of_load_item
-            .insert(FileLoadItemId::new(file, load_item), ty.clone());
+        let id = FileLoadItemId::new(file, load_item);
+        self.cx.type_of_load_item.insert(id, ty.clone());
+        self.cx.record_cache_entry(CacheEntry::LoadItem(id));
         ty
     }
 
@@ -2244,13 +3333,24 @@ impl TyContext<'_> {
             .db
             .load_file(module, file.dialect(self.db), file.id(self.db))
         {
-            Ok(Some(loaded_file)) => Some(loaded_file),
+            Ok(Some(loaded_file)) => {
+                self.check_load_visibility(file, loaded_file, load_stmt);
+                Some(loaded_file)
+            }
             Ok(None) => return None,
             Err(err) => {
+                // Modules in external repositories that haven't been fetched yet might resolve
+                // once the repository is fetched.
+                let severity = if err.is::<UnfetchedRepositoryError>() {
+                    Severity::Info
+                } else {
+                    Severity::Warning
+                };
                 self.add_diagnostic_for_range(
                     file,
-                    Severity::Warning,
+                    severity,
                     load_stmt.ptr(self.db).text_range(),
+                    DiagnosticCode::UnresolvedModule,
                     None,
                     format!(
                         "Could not resolve module \"{}\": {}",
@@ -2263,9 +3363,61 @@ impl TyContext<'_> {
         };
 
         self.cx.resolved_load_stmts.insert(id, res);
+        self.cx.record_cache_entry(CacheEntry::LoadStmt(id));
         res
     }
 
+    fn check_load_visibility(&mut self, file: File, loaded_file: File, load_stmt: LoadStmt) {
+        let db = self.db;
+        if loaded_file.dialect(db) != Dialect::Bazel {
+            return;
+        }
+        let Some(package) = db.resolve_package(file.id(db)).package(db).clone() else {
+            return;
+        };
+        let Some(loaded_package) = db.resolve_package(loaded_file.id(db)).package(db).clone()
+        else {
+            // Bazel only loads `.bzl` files that belong to a package.
+            self.add_diagnostic_for_range(
+                file,
+                Severity::Error,
+                load_stmt.ptr(db).text_range(),
+                DiagnosticCode::UnresolvedModule,
+                None,
+                format!(
+                    "Module \"{}\" is not in a package, since there's no BUILD file in its directory or any of its parents",
+                    load_stmt.module(db)
+                ),
+            );
+            return;
+        };
+
+        // The package specs passed to `visibility()` only refer to packages in the loaded file's
+        // repository.
+        if package.repo != loaded_package.repo
+            || load_visibility(db, loaded_file).is_visible_to(
+                db,
+                &loaded_package.package,
+                &package.package,
+            )
+        {
+            return;
+        }
+
+        self.add_diagnostic_for_range(
+            file,
+            Severity::Error,
+            load_stmt.ptr(db).text_range(),
+            DiagnosticCode::LoadVisibility,
+            None,
+            format!(
+                "Module \"{}\" is not visible to package \"//{}\"",
+                load_stmt.module(db),
+                package.package
+            ),
+        );
+    }
+
     fn push_load_resolution<F, T>(&mut self, file: File, load_stmt: LoadStmt, f: F) -> T
     where
         F: FnOnce(&mut Self) -> T,
@@ -2276,6 +3428,35 @@ impl TyContext<'_> {
         res
     }
 
+    /// Returns the types that the type variables of a user-defined function are bound to at the
+    /// given call expression.
+    pub(crate) fn call_substitution(
+        &mut self,
+        file: File,
+        call_expr: ExprId,
+    ) -> Option<Substitution> {
+        self.infer_expr(file, call_expr);
+        self.cx
+            .call_substitutions
+            .get(&FileExprId::new(file, call_expr))
+            .cloned()
+    }
+
+    /// Returns the type of the parameter that an argument of the given call expression is
+    /// passed to.
+    pub(crate) fn expected_type_of_argument(
+        &mut self,
+        file: File,
+        call_expr: ExprId,
+        arg_expr: ExprId,
+    ) -> Option<Ty> {
+        self.infer_expr(file, call_expr);
+        self.cx
+            .expected_type_of_arg
+            .get(&FileExprId::new(file, arg_expr))
+            .cloned()
+    }
+
     pub(crate) fn resolve_call_expr_active_param(
         &mut self,
         file: File,
@@ -2306,7 +3487,7 @@ impl TyContext<'_> {
 
                 let callee_ty = self.infer_expr(file, *callee);
                 let mut slots: Slots = match callee_ty.kind() {
-                    TyKind::Function(def) => {
+                    TyKind::Function(def) | TyKind::Lambda(def, _, _) => {
                         let module = module(db, def.func().file(db));
                         let params = def.func().params(db).iter().copied();
                         params
@@ -2372,3 +3553,56 @@ impl TyContext<'_> {
         self.types().bytes.clone()
     }
 }
+
+/// Constraints on the value of a rule attribute, checked at rule call sites.
+struct AttributeConstraints<'a> {
+    name: &'a Name,
+    configurable: bool,
+    label_position: Option<LabelPosition>,
+    values: Option<&'a [Ty]>,
+}
+
+impl<'a> AttributeConstraints<'a> {
+    fn from_attribute(name: &'a Name, attr: &'a Attribute) -> Self {
+        Self {
+            name,
+            configurable: attr.configurable,
+            label_position: attr.kind.label_position(),
+            values: attr.values.as_deref(),
+        }
+    }
+
+    /// Derives the constraints for a parameter of a native rule, whose attributes are only
+    /// described by their type.
+    fn from_builtin_param(name: &'a Name, param: &BuiltinFunctionParam) -> Self {
+        let label_position = match param.type_ref() {
+            Some(TypeRef::Name(ty_name, None)) if ty_name.as_str() == "Label" => {
+                Some(LabelPosition::Value)
+            }
+            Some(TypeRef::Name(ty_name, Some(args))) if ty_name.as_str() == "list" => {
+                match &args[..] {
+                    [TypeRef::Name(element, None)] if element.as_str() == "Label" => {
+                        Some(LabelPosition::ListElement)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        Self {
+            name,
+            configurable: param.is_configurable(),
+            label_position,
+            values: None,
+        }
+    }
+}
+
+/// Returns the source text of a literal string or int type, e.g. `"a"` or `1`.
+fn literal_value_text(db: &dyn Db, ty: &Ty) -> Option<String> {
+    match ty.kind() {
+        TyKind::String(Some(s)) => Some(format!("{:?}", s.value(db))),
+        TyKind::Int(Some(i)) => Some(i.to_string()),
+        _ => None,
+    }
+}
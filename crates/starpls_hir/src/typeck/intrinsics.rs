@@ -505,58 +505,6 @@ With no argument, `list()` returns a new empty list."#,
         vec![positional_opt(Any)],
         List(Any.intern()),
     );
-    add_function(
-        "max",
-        r#"`max(x)` returns the greatest element in the iterable sequence x.
-
-It is an error if any element does not support ordered comparison,
-or if the sequence is empty.
-        
-The optional named parameter `key` specifies a function to be applied
-to each element prior to comparison.
-        
-```python
-max([3, 1, 4, 1, 5, 9])                         # 9
-max("two", "three", "four")                     # "two", the lexicographically greatest
-max("two", "three", "four", key=len)            # "three", the longest
-```
-"#,
-        vec![
-            ArgsList { ty: Any.intern() },
-            Keyword {
-                name: Name::new_inline("key"),
-                ty: Any.intern(),
-                deprecated: false,
-            },
-        ],
-        Any,
-    );
-    add_function(
-        "min",
-        r#"`min(x)` returns the least element in the iterable sequence x.
-
-It is an error if any element does not support ordered comparison,
-or if the sequence is empty.
-        
-The optional named parameter `key` specifies a function to be applied
-to each element prior to comparison.
-        
-```python
-min([3, 1, 4, 1, 5, 9])                         # 1
-min("two", "three", "four")                     # "four", the lexicographically least
-min("two", "three", "four", key=len)            # "two", the shortest
-```
-"#,
-        vec![
-            ArgsList { ty: Any.intern() },
-            Keyword {
-                name: Name::new_inline("key"),
-                ty: Any.intern(),
-                deprecated: false,
-            },
-        ],
-        Any,
-    );
     add_function(
         "print",
         r#"`print(*args, sep=" ")` prints its arguments, followed by a newline.
@@ -677,44 +625,6 @@ reversed({"one": 1, "two": 2}.keys())           # ["two", "one"]
         vec![positional(Any)],
         List(Any.intern()),
     );
-    // TODO(withered-magic): Iterable[T] -> List[T]
-    add_function(
-        "sorted",
-        r#"`sorted(x)` returns a new list containing the elements of the iterable sequence x,
-in sorted order.  The sort algorithm is stable.
-
-The optional named boolean parameter `reverse`, if true, causes `sorted` to
-return results in reverse sorted order.
-
-The optional named parameter `key` specifies a function of one
-argument to apply to obtain the value's sort key.
-The default behavior is the identity function.
-The `key` function is called exactly once per element of the sequence, in order,
-even for a single-element list.
-
-```python
-sorted([3, 1, 4, 1, 5, 9])                                 # [1, 1, 3, 4, 5, 9]
-sorted([3, 1, 4, 1, 5, 9], reverse=True)                   # [9, 5, 4, 3, 1, 1]
-
-sorted(["two", "three", "four"], key=len)                  # ["two", "four", "three"], shortest to longest
-sorted(["two", "three", "four"], key=len, reverse=True)    # ["three", "four", "two"], longest to shortest
-```
-"#,
-        vec![
-            positional(Any),
-            Keyword {
-                name: Name::new_inline("reverse"),
-                ty: non_literal_bool().intern(),
-                deprecated: false,
-            },
-            Keyword {
-                name: Name::new_inline("key"),
-                ty: Any.intern(),
-                deprecated: false,
-            },
-        ],
-        List(Any.intern()),
-    );
     add_function(
         "str",
         r#"`str(x)` formats its argument as a string.
@@ -778,6 +688,111 @@ zip(range(10), ["a", "b", "c"])         # [(0, "a"), (1, "b"), (2, "c")]
         List(Any.intern()),
     );
 
+    // The `key` functions accepted by these functions are called with the elements being
+    // compared, so the type of those elements is bound to a type variable.
+    let mut add_generic_function = |name, doc, params, num_vars, ret_ty| {
+        functions.insert(
+            Name::new_inline(name),
+            function(db, name, doc, params, num_vars, ret_ty),
+        );
+    };
+    add_generic_function(
+        "max",
+        r#"`max(x)` returns the greatest element in the iterable sequence x.
+
+It is an error if any element does not support ordered comparison,
+or if the sequence is empty.
+        
+The optional named parameter `key` specifies a function to be applied
+to each element prior to comparison.
+        
+```python
+max([3, 1, 4, 1, 5, 9])                         # 9
+max("two", "three", "four")                     # "two", the lexicographically greatest
+max("two", "three", "four", key=len)            # "three", the longest
+```
+"#,
+        vec![
+            ArgsList {
+                ty: iterable_or_element_ty(),
+            },
+            Keyword {
+                name: Name::new_inline("key"),
+                ty: key_function_ty(),
+                deprecated: false,
+            },
+        ],
+        1,
+        Any,
+    );
+    add_generic_function(
+        "min",
+        r#"`min(x)` returns the least element in the iterable sequence x.
+
+It is an error if any element does not support ordered comparison,
+or if the sequence is empty.
+        
+The optional named parameter `key` specifies a function to be applied
+to each element prior to comparison.
+        
+```python
+min([3, 1, 4, 1, 5, 9])                         # 1
+min("two", "three", "four")                     # "four", the lexicographically least
+min("two", "three", "four", key=len)            # "two", the shortest
+```
+"#,
+        vec![
+            ArgsList {
+                ty: iterable_or_element_ty(),
+            },
+            Keyword {
+                name: Name::new_inline("key"),
+                ty: key_function_ty(),
+                deprecated: false,
+            },
+        ],
+        1,
+        Any,
+    );
+    add_generic_function(
+        "sorted",
+        r#"`sorted(x)` returns a new list containing the elements of the iterable sequence x,
+in sorted order.  The sort algorithm is stable.
+
+The optional named boolean parameter `reverse`, if true, causes `sorted` to
+return results in reverse sorted order.
+
+The optional named parameter `key` specifies a function of one
+argument to apply to obtain the value's sort key.
+The default behavior is the identity function.
+The `key` function is called exactly once per element of the sequence, in order,
+even for a single-element list.
+
+```python
+sorted([3, 1, 4, 1, 5, 9])                                 # [1, 1, 3, 4, 5, 9]
+sorted([3, 1, 4, 1, 5, 9], reverse=True)                   # [9, 5, 4, 3, 1, 1]
+
+sorted(["two", "three", "four"], key=len)                  # ["two", "four", "three"], shortest to longest
+sorted(["two", "three", "four"], key=len, reverse=True)    # ["three", "four", "two"], longest to shortest
+```
+"#,
+        vec![
+            positional(Protocol(typeck::Protocol::Iterable(BoundVar(0).intern()))),
+            Keyword {
+                name: Name::new_inline("reverse"),
+                ty: non_literal_bool().intern(),
+                deprecated: false,
+            },
+            Keyword {
+                name: Name::new_inline("key"),
+                ty: key_function_ty(),
+                deprecated: false,
+            },
+        ],
+        1,
+        List(BoundVar(0).intern()),
+    );
+
     IntrinsicFunctions::new(db, functions)
 }
 
@@ -1900,6 +1915,30 @@ fn positional_opt(kind: TyKind) -> IntrinsicFunctionParam {
     }
 }
 
+/// The type of the values compared by `min()` and `max()`, which are passed either as a single
+/// iterable or as separate arguments.
+fn iterable_or_element_ty() -> Ty {
+    TyKind::Union(smallvec![
+        TyKind::Protocol(typeck::Protocol::Iterable(TyKind::BoundVar(0).intern())).intern(),
+        TyKind::BoundVar(0).intern(),
+    ])
+    .intern()
+}
+
+/// The type of the optional `key` function accepted by `min()`, `max()` and `sorted()`, which is
+/// called with each of the values being compared.
+fn key_function_ty() -> Ty {
+    TyKind::Union(smallvec![
+        TyKind::Protocol(typeck::Protocol::Callable(
+            Box::new([TyKind::BoundVar(0).intern()]),
+            TyKind::Any.intern(),
+        ))
+        .intern(),
+        TyKind::None.intern(),
+    ])
+    .intern()
+}
+
 fn non_literal_string() -> TyKind {
     TyKind::String(None)
 }
@@ -0,0 +1,245 @@
+use starpls_common::File;
+use starpls_syntax::ast::BinaryOp;
+use starpls_syntax::ast::CmpOp;
+use starpls_syntax::ast::LogicOp;
+use starpls_syntax::ast::UnaryOp;
+
+use crate::def::codeflow::NarrowingComparison;
+use crate::def::Expr;
+use crate::def::ExprId;
+use crate::module;
+use crate::typeck::Tuple;
+use crate::typeck::Ty;
+use crate::typeck::TyContext;
+use crate::typeck::TyKind;
+use crate::Name;
+
+impl TyContext<'_> {
+    /// Returns whether the given condition can narrow the type of `name`.
+    pub(crate) fn condition_narrows_name(&self, file: File, expr: ExprId, name: &Name) -> bool {
+        let module = module(self.db, file);
+        match &module[expr] {
+            Expr::Name { name: cond_name } => cond_name == name,
+            Expr::Paren { expr }
+            | Expr::Unary {
+                op: Some(UnaryOp::Not),
+                expr,
+            } => self.condition_narrows_name(file, *expr, name),
+            Expr::Binary {
+                lhs,
+                rhs,
+                op: Some(BinaryOp::Logic(_)),
+            } => {
+                self.condition_narrows_name(file, *lhs, name)
+                    || self.condition_narrows_name(file, *rhs, name)
+            }
+            Expr::Binary {
+                lhs,
+                rhs,
+                op: Some(BinaryOp::Cmp(CmpOp::Eq | CmpOp::Ne)),
+            } => match NarrowingComparison::from_operands(module, *lhs, *rhs) {
+                Some(
+                    NarrowingComparison::None { name: cond_name }
+                    | NarrowingComparison::Type {
+                        name: cond_name, ..
+                    },
+                ) => cond_name == name,
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Narrows `ty`, the type of `name`, given that the condition `expr` evaluated to a truthy
+    /// value if `assume_true` is set, or to a falsy value otherwise.
+    pub(crate) fn narrow_ty_for_condition(
+        &self,
+        file: File,
+        name: &Name,
+        ty: &Ty,
+        expr: ExprId,
+        assume_true: bool,
+    ) -> Ty {
+        if !self.condition_narrows_name(file, expr, name) {
+            return ty.clone();
+        }
+
+        let module = module(self.db, file);
+        match &module[expr] {
+            Expr::Name { .. } => narrow_truthiness(ty, assume_true),
+            Expr::Paren { expr } => {
+                self.narrow_ty_for_condition(file, name, ty, *expr, assume_true)
+            }
+            Expr::Unary { expr, .. } => {
+                self.narrow_ty_for_condition(file, name, ty, *expr, !assume_true)
+            }
+            Expr::Binary {
+                lhs,
+                rhs,
+                op: Some(BinaryOp::Logic(op)),
+            } => {
+                // If `a and b` is truthy, then both `a` and `b` are truthy. Similarly, if `a or b`
+                // is falsy, then both `a` and `b` are falsy.
+                let is_and = matches!(op, LogicOp::And);
+                let lhs_ty = self.narrow_ty_for_condition(file, name, ty, *lhs, assume_true);
+                if is_and == assume_true {
+                    return self.narrow_ty_for_condition(file, name, &lhs_ty, *rhs, assume_true);
+                }
+
+                // Otherwise, either the left-hand side alone determined the result, or the
+                // right-hand side was evaluated after the left-hand side had the opposite value.
+                if !self.condition_narrows_name(file, *lhs, name)
+                    || !self.condition_narrows_name(file, *rhs, name)
+                {
+                    return ty.clone();
+                }
+                let rhs_ty = self.narrow_ty_for_condition(file, name, ty, *lhs, !assume_true);
+                let rhs_ty = self.narrow_ty_for_condition(file, name, &rhs_ty, *rhs, assume_true);
+                Ty::union([lhs_ty, rhs_ty].into_iter())
+            }
+            Expr::Binary {
+                lhs,
+                rhs,
+                op: Some(BinaryOp::Cmp(op)),
+            } => {
+                let holds = matches!(op, CmpOp::Eq) == assume_true;
+                match NarrowingComparison::from_operands(module, *lhs, *rhs) {
+                    Some(NarrowingComparison::None { .. }) => narrow_none(ty, holds),
+                    Some(NarrowingComparison::Type { type_name, .. }) => {
+                        narrow_type_name(ty, type_name.value(self.db), holds)
+                    }
+                    None => ty.clone(),
+                }
+            }
+            _ => ty.clone(),
+        }
+    }
+}
+
+/// Keeps the members of a (possibly union) type that satisfy `f`. Types that are eliminated
+/// entirely become `Never`, since the corresponding code can't actually be reached. Unlike
+/// `Unknown`, `Never` disappears when the narrowed type is merged with others, e.g. after an
+/// `if` statement or at the start of a loop, so it doesn't leak into the merged type.
+fn filter_ty(ty: &Ty, f: impl Fn(&Ty) -> bool) -> Ty {
+    match ty.kind() {
+        TyKind::Union(tys) => {
+            if tys.iter().all(&f) {
+                return ty.clone();
+            }
+            Ty::union(tys.iter().filter(|ty| f(*ty)).cloned())
+        }
+        _ if f(ty) => ty.clone(),
+        _ => Ty::never(),
+    }
+}
+
+fn narrow_truthiness(ty: &Ty, assume_true: bool) -> Ty {
+    filter_ty(ty, |ty| match ty.kind() {
+        TyKind::None | TyKind::Bool(Some(false)) | TyKind::Int(Some(0)) => !assume_true,
+        TyKind::Tuple(Tuple::Simple(tys)) if tys.is_empty() => !assume_true,
+        // Functions, structs and the like are always truthy.
+        TyKind::Bool(Some(true))
+        | TyKind::Int(Some(_))
+        | TyKind::Function(_)
+        | TyKind::Lambda(_, _, _)
+        | TyKind::IntrinsicFunction(_, _)
+        | TyKind::BuiltinFunction(_)
+        | TyKind::Struct(_)
+        | TyKind::Rule(_)
+        | TyKind::Provider(_)
+        | TyKind::ProviderInstance(_)
+        | TyKind::Target => assume_true,
+        _ => true,
+    })
+}
+
+fn is_unknown(ty: &Ty) -> bool {
+    matches!(ty.kind(), TyKind::Unknown | TyKind::Any)
+}
+
+/// Narrows a type given whether it is `None`.
+pub(crate) fn narrow_none(ty: &Ty, is_none: bool) -> Ty {
+    // `Unknown` might be `None`, so it's narrowed to `None` when it must be.
+    if is_none {
+        let has_unknown = match ty.kind() {
+            TyKind::Union(tys) => tys.iter().any(is_unknown),
+            _ => is_unknown(ty),
+        };
+        if has_unknown {
+            return Ty::none();
+        }
+    }
+    filter_ty(ty, |ty| match ty.kind() {
+        TyKind::None => is_none,
+        TyKind::Unknown | TyKind::Any | TyKind::Unbound => true,
+        _ => !is_none,
+    })
+}
+
+/// Narrows a type given whether `type(x) == type_name`.
+fn narrow_type_name(ty: &Ty, type_name: &str, is_type: bool) -> Ty {
+    // `Unknown` can be narrowed to the type named by the comparison.
+    if is_type {
+        if let Some(named_ty) = ty_for_type_name(type_name) {
+            let members: Vec<Ty> = match ty.kind() {
+                TyKind::Union(tys) => tys.iter().cloned().collect(),
+                _ => vec![ty.clone()],
+            };
+            if members.iter().any(is_unknown) {
+                return Ty::union(members.into_iter().filter_map(|ty| {
+                    if is_unknown(&ty) {
+                        Some(named_ty.clone())
+                    } else if type_name_of(&ty).map_or(true, |name| name == type_name) {
+                        Some(ty)
+                    } else {
+                        None
+                    }
+                }));
+            }
+        }
+    }
+
+    filter_ty(ty, |ty| match type_name_of(ty) {
+        Some(name) => (name == type_name) == is_type,
+        None => true,
+    })
+}
+
+/// Returns the value that `type()` returns for values of the given type, if known.
+fn type_name_of(ty: &Ty) -> Option<&'static str> {
+    Some(match ty.kind() {
+        TyKind::None => "NoneType",
+        TyKind::Bool(_) => "bool",
+        TyKind::Int(_) => "int",
+        TyKind::Float => "float",
+        TyKind::String(_) => "string",
+        TyKind::Bytes => "bytes",
+        TyKind::List(_) => "list",
+        TyKind::Tuple(_) => "tuple",
+        TyKind::Dict(_, _, _) => "dict",
+        TyKind::Range => "range",
+        TyKind::Function(_) | TyKind::Lambda(_, _, _) => "function",
+        TyKind::IntrinsicFunction(_, _) | TyKind::BuiltinFunction(_) => {
+            "builtin_function_or_method"
+        }
+        TyKind::Struct(_) => "struct",
+        TyKind::Target => "Target",
+        _ => return None,
+    })
+}
+
+fn ty_for_type_name(type_name: &str) -> Option<Ty> {
+    Some(match type_name {
+        "NoneType" => Ty::none(),
+        "bool" => Ty::bool(),
+        "int" => Ty::int(),
+        "float" => TyKind::Float.intern(),
+        "string" => Ty::string(),
+        "bytes" => TyKind::Bytes.intern(),
+        "list" => Ty::list(Ty::unknown()),
+        "tuple" => TyKind::Tuple(Tuple::Variable(Ty::unknown())).intern(),
+        "dict" => Ty::dict(Ty::unknown(), Ty::unknown(), None),
+        "range" => TyKind::Range.intern(),
+        _ => return None,
+    })
+}
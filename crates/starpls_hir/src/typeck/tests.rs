@@ -42,11 +42,68 @@ fn check_infer_with_code_flow_analysis(input: &str, expect: Expect) {
     )
 }
 
+fn check_infer_with_strict_type_checking(input: &str, expect: Expect) {
+    check_infer_with_options(
+        input,
+        expect,
+        InferenceOptions {
+            strict_type_checking: true,
+            allow_unused_definitions: true,
+            ..Default::default()
+        },
+    )
+}
+
+/// Checks whether the type of the first name in each pair is assignable to the type of the
+/// second with strict type checking. Each name's type is inferred at its last occurrence.
+fn check_strict_assignability(input: &str, pairs: &[(&str, &str)], expect: Expect) {
+    let mut builder = TestDatabaseBuilder::default();
+    builder.set_inference_options(InferenceOptions {
+        strict_type_checking: true,
+        allow_unused_definitions: true,
+        ..Default::default()
+    });
+    let mut db = builder.build();
+    let file = db.create_file(FileId(0), Dialect::Standard, None, input.to_string());
+    let root = parse(&db, file).syntax(&db);
+    let source_map = source_map(&db, file);
+    let expr_for_name = |name: &str| {
+        source_map
+            .expr_map
+            .iter()
+            .filter(|(ptr, _)| ptr.to_node(&root).syntax().text() == name)
+            .max_by_key(|(ptr, _)| ptr.syntax_node_ptr().text_range().start())
+            .map(|(_, expr)| *expr)
+            .expect("name not found")
+    };
+
+    let mut res = String::new();
+    for (source, target) in pairs {
+        let (source_expr, target_expr) = (expr_for_name(source), expr_for_name(target));
+        let assignable = db.gcx().with_tcx(&db, |tcx| {
+            let source_ty = tcx.infer_expr(file, source_expr);
+            let target_ty = tcx.infer_expr(file, target_expr);
+            tcx.is_assignable(&source_ty, &target_ty)
+        });
+        writeln!(res, "{} -> {}: {}", source, target, assignable).unwrap();
+    }
+    expect.assert_eq(&res);
+}
+
 fn check_infer_with_unused_definitions(input: &str, expect: Expect) {
     check_infer_with_options(input, expect, Default::default())
 }
 
 fn check_infer_with_options(input: &str, expect: Expect, options: InferenceOptions) {
+    check_infer_with_dialect(input, expect, Dialect::Bazel, options)
+}
+
+fn check_infer_with_dialect(
+    input: &str,
+    expect: Expect,
+    dialect: Dialect,
+    options: InferenceOptions,
+) {
     let mut builder = TestDatabaseBuilder::default();
     builder.add_function("provider");
     builder.add_function("rule");
@@ -117,15 +174,14 @@ fn check_infer_with_options(input: &str, expect: Expect, options: InferenceOptio
 
     let mut db = builder.build();
     let file_id = FileId(0);
-    let file = db.create_file(
-        file_id,
-        Dialect::Bazel,
-        Some(FileInfo::Bazel {
+    let info = match dialect {
+        Dialect::Standard => None,
+        Dialect::Bazel => Some(FileInfo::Bazel {
             api_context: APIContext::Bzl,
             is_external: false,
         }),
-        input.to_string(),
-    );
+    };
+    let file = db.create_file(file_id, dialect, info, input.to_string());
     let root = parse(&db, file).syntax(&db);
     let source_map = source_map(&db, file);
     let mut res = String::new();
@@ -331,6 +387,361 @@ greeting = 1 # type: string
     )
 }
 
+#[test]
+fn test_strict_type_checking() {
+    check_infer_with_strict_type_checking(
+        r#"
+def f(x, y, z):
+    # type: (int | None, list[string], tuple[string, string]) -> None
+    a = x # type: int
+    b = y # type: list[string | None]
+    c = z # type: tuple[string | None, ...]
+"#,
+        expect![[r#"
+            91..92 "a": int
+            95..96 "x": int | None
+            113..114 "b": list[string | None]
+            117..118 "y": list[string]
+            151..152 "c": tuple[string | None, ...]
+            155..156 "z": tuple[string, string]
+
+            95..96 Expression of type "int | None" cannot be assigned to variable of type "int"
+            117..118 Expression of type "list[string]" cannot be assigned to variable of type "list[string | None]"
+        "#]],
+    )
+}
+
+#[test]
+fn test_type_vars() {
+    check_infer(
+        r#"
+def first(xs):
+    # type: (list[T]) -> T
+    return xs[0]
+
+def lookup(d, key, default):
+    # type: (dict[K, V], K, V | None) -> V | None
+    pass
+
+first(["a", "b"])
+lookup({"a": 1}, "a", None)
+"#,
+        expect![[r#"
+            54..56 "xs": list[Unknown]
+            57..58 "0": Literal[0]
+            54..59 "xs[0]": Unknown
+            150..155 "first": def first(xs: list[T]) -> T
+            157..160 "\"a\"": Literal["a"]
+            162..165 "\"b\"": Literal["b"]
+            156..166 "[\"a\", \"b\"]": list[string]
+            150..167 "first([\"a\", \"b\"])": string
+            168..174 "lookup": def lookup(d: dict[K, V], key: K, default: V | None) -> V | None
+            176..179 "\"a\"": Literal["a"]
+            181..182 "1": Literal[1]
+            175..183 "{\"a\": 1}": dict[string, int]
+            185..188 "\"a\"": Literal["a"]
+            190..194 "None": None
+            168..195 "lookup({\"a\": 1}, \"a\", None)": int | None
+        "#]],
+    )
+}
+
+#[test]
+fn test_conflicting_type_var_bindings() {
+    check_infer(
+        r#"
+def lookup(d, key, default):
+    # type: (dict[K, V], K, V | None) -> V | None
+    pass
+
+lookup({"a": 1}, 1, None)
+"#,
+        expect![[r#"
+            90..96 "lookup": def lookup(d: dict[K, V], key: K, default: V | None) -> V | None
+            98..101 "\"a\"": Literal["a"]
+            103..104 "1": Literal[1]
+            97..105 "{\"a\": 1}": dict[string, int]
+            107..108 "1": Literal[1]
+            110..114 "None": None
+            90..115 "lookup({\"a\": 1}, 1, None)": int | None
+
+            107..108 Argument of type "Literal[1]" cannot be assigned to parameter of type "string"
+        "#]],
+    )
+}
+
+#[test]
+fn test_inline_type_annotations() {
+    check_infer_with_dialect(
+        r#"
+def first(parts: list[string], sep: string = ",") -> string:
+    return parts[0]
+
+first(["a", "b"])
+"#,
+        expect![[r#"
+            46..49 "\",\"": Literal[","]
+            73..78 "parts": list[string]
+            79..80 "0": Literal[0]
+            73..81 "parts[0]": string
+            83..88 "first": def first(parts: list[string], sep: string) -> string
+            90..93 "\"a\"": Literal["a"]
+            95..98 "\"b\"": Literal["b"]
+            89..99 "[\"a\", \"b\"]": list[string]
+            83..100 "first([\"a\", \"b\"])": string
+        "#]],
+        Dialect::Standard,
+        InferenceOptions {
+            allow_unused_definitions: true,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn test_strict_type_checking_dicts_and_unions() {
+    check_infer_with_strict_type_checking(
+        r#"
+def f(x, y, s):
+    # type: (dict[string, int], int | string, Sequence[string]) -> None
+    a = x # type: dict[string, int | None]
+    b = {"k": 1} # type: dict[string, int | None]
+    c = y # type: int | string | None
+    d = y # type: int
+    e = s # type: list[string]
+"#,
+        expect![[r#"
+            93..94 "a": dict[string, int | None]
+            97..98 "x": dict[string, int]
+            136..137 "b": dict[string, int | None]
+            141..144 "\"k\"": Literal["k"]
+            146..147 "1": Literal[1]
+            140..148 "{\"k\": 1}": dict[string, int]
+            186..187 "c": int | string | None
+            190..191 "y": int | string
+            224..225 "d": int
+            228..229 "y": int | string
+            246..247 "e": list[string]
+            250..251 "s": Sequence[string]
+
+            97..98 Expression of type "dict[string, int]" cannot be assigned to variable of type "dict[string, int | None]"
+            228..229 Expression of type "int | string" cannot be assigned to variable of type "int"
+            250..251 Expression of type "Sequence[string]" cannot be assigned to variable of type "list[string]"
+        "#]],
+    )
+}
+
+#[test]
+fn test_strict_type_checking_call_arguments() {
+    check_infer_with_strict_type_checking(
+        r#"
+def f(x):
+    # type: (list[string | None]) -> None
+    pass
+
+def h(d):
+    # type: (dict[string, int | None]) -> None
+    pass
+
+def g(y, z):
+    # type: (list[string], dict[string, int]) -> None
+    f(["a"])
+    f(y)
+    h({"a": 1})
+    h(z)
+"#,
+        expect![[r#"
+            201..202 "f": def f(x: list[string | None]) -> None
+            204..207 "\"a\"": Literal["a"]
+            203..208 "[\"a\"]": list[string]
+            201..209 "f([\"a\"])": None
+            214..215 "f": def f(x: list[string | None]) -> None
+            216..217 "y": list[string]
+            214..218 "f(y)": None
+            223..224 "h": def h(d: dict[string, int | None]) -> None
+            226..229 "\"a\"": Literal["a"]
+            231..232 "1": Literal[1]
+            225..233 "{\"a\": 1}": dict[string, int]
+            223..234 "h({\"a\": 1})": None
+            239..240 "h": def h(d: dict[string, int | None]) -> None
+            241..242 "z": dict[string, int]
+            239..243 "h(z)": None
+
+            216..217 Argument of type "list[string]" cannot be assigned to parameter of type "list[string | None]"
+            241..242 Argument of type "dict[string, int]" cannot be assigned to parameter of type "dict[string, int | None]"
+        "#]],
+    )
+}
+
+#[test]
+fn test_strict_type_checking_functions() {
+    check_strict_assignability(
+        r#"
+def narrow(x):
+    # type: (int) -> None
+    pass
+
+def wide(x):
+    # type: (int | None) -> None
+    pass
+
+def returns_int():
+    # type: () -> int
+    pass
+
+def returns_optional():
+    # type: () -> int | None
+    pass
+
+narrow
+wide
+returns_int
+returns_optional
+"#,
+        &[
+            ("wide", "narrow"),
+            ("narrow", "wide"),
+            ("returns_int", "returns_optional"),
+            ("returns_optional", "returns_int"),
+        ],
+        expect![[r#"
+            wide -> narrow: true
+            narrow -> wide: false
+            returns_int -> returns_optional: true
+            returns_optional -> returns_int: false
+        "#]],
+    )
+}
+
+#[test]
+fn test_strict_type_checking_function_defaults() {
+    check_strict_assignability(
+        r#"
+def one(x):
+    # type: (int) -> None
+    pass
+
+def two(x, y):
+    # type: (int, int) -> None
+    pass
+
+def with_default(x, y = 1):
+    # type: (int, int) -> None
+    pass
+
+one
+two
+with_default
+"#,
+        &[
+            ("with_default", "one"),
+            ("one", "with_default"),
+            ("two", "one"),
+            ("two", "with_default"),
+            ("with_default", "two"),
+        ],
+        expect![[r#"
+            with_default -> one: true
+            one -> with_default: false
+            two -> one: false
+            two -> with_default: false
+            with_default -> two: true
+        "#]],
+    )
+}
+
+#[test]
+fn test_strict_type_checking_function_args_and_kwargs() {
+    check_strict_assignability(
+        r#"
+def one(x):
+    pass
+
+def two(x, y):
+    pass
+
+def with_args(*args):
+    pass
+
+def with_kwargs(x, **kwargs):
+    pass
+
+def keyword(x, y = 1):
+    pass
+
+one
+two
+with_args
+with_kwargs
+keyword
+"#,
+        &[
+            ("with_args", "one"),
+            ("with_args", "two"),
+            ("one", "with_args"),
+            ("with_kwargs", "one"),
+            ("one", "with_kwargs"),
+            ("keyword", "with_kwargs"),
+            ("with_kwargs", "keyword"),
+        ],
+        expect![[r#"
+            with_args -> one: true
+            with_args -> two: true
+            one -> with_args: false
+            with_kwargs -> one: true
+            one -> with_kwargs: false
+            keyword -> with_kwargs: false
+            with_kwargs -> keyword: false
+        "#]],
+    )
+}
+
+#[test]
+fn test_strict_type_checking_keyword_only_params() {
+    check_strict_assignability(
+        r#"
+def one(x):
+    pass
+
+def required(x, *, y):
+    pass
+
+def optional(x, *, y = 1):
+    pass
+
+def positional(x, y):
+    pass
+
+def with_kwargs(x, **kwargs):
+    pass
+
+one
+required
+optional
+positional
+with_kwargs
+"#,
+        &[
+            ("required", "one"),
+            ("optional", "one"),
+            ("optional", "required"),
+            ("required", "optional"),
+            ("positional", "required"),
+            ("with_kwargs", "required"),
+            ("required", "positional"),
+        ],
+        expect![[r#"
+            required -> one: false
+            optional -> one: true
+            optional -> required: true
+            required -> optional: false
+            positional -> required: true
+            with_kwargs -> required: true
+            required -> positional: false
+        "#]],
+    )
+}
+
 #[test]
 fn test_type_ignore_comment() {
     check_infer(
@@ -1927,6 +2338,37 @@ lambda x, y, z: x + y + z
     );
 }
 
+#[test]
+fn test_lambda_key_function() {
+    check_infer(
+        r#"
+names = ["b", "a"]
+sorted(names, key = lambda name: name.upper())
+max(1, 2, key = lambda n: -n)
+"#,
+        expect![[r#"
+            1..6 "names": list[string]
+            10..13 "\"b\"": Literal["b"]
+            15..18 "\"a\"": Literal["a"]
+            9..19 "[\"b\", \"a\"]": list[string]
+            20..26 "sorted": def sorted(x0: Iterable['0], reverse: bool = None, key: Callable[['0], Any] | None = None) -> list['0]
+            27..32 "names": list[string]
+            53..57 "name": string
+            53..63 "name.upper": def upper() -> string
+            53..65 "name.upper()": string
+            40..65 "lambda name: name.upper()": def lambda(name: string) -> string
+            20..66 "sorted(names, key = lambda name: name.upper())": list[string]
+            67..70 "max": def max(*args: Iterable['0] | '0, key: Callable[['0], Any] | None = None) -> Any
+            71..72 "1": Literal[1]
+            74..75 "2": Literal[2]
+            94..95 "n": int
+            93..95 "-n": int
+            83..95 "lambda n: -n": def lambda(n: int) -> int
+            67..96 "max(1, 2, key = lambda n: -n)": Any
+        "#]],
+    );
+}
+
 #[test]
 fn test_bad_assignments() {
     check_infer(
@@ -2051,6 +2493,99 @@ def f():
     );
 }
 
+#[test]
+fn test_narrowing_if_stmts() {
+    check_infer_with_code_flow_analysis(
+        r#"
+def f(x, y):
+    # type: (int | None, string | list[string]) -> None
+    if x != None:
+        x
+    else:
+        x
+    if type(y) == "list":
+        y
+    else:
+        y
+    y
+    if not x:
+        return
+    x
+"#,
+        expect![[r#"
+            77..78 "x": int | None
+            82..86 "None": None
+            77..86 "x != None": bool
+            96..97 "x": int
+            116..117 "x": None
+            125..129 "type": def type(x0: Any) -> string
+            130..131 "y": string | list[string]
+            125..132 "type(y)": string
+            136..142 "\"list\"": Literal["list"]
+            125..142 "type(y) == \"list\"": bool
+            152..153 "y": list[string]
+            172..173 "y": string
+            178..179 "y": list[string] | string
+            191..192 "x": int | None
+            187..192 "not x": bool
+            213..214 "x": int
+        "#]],
+    );
+}
+
+#[test]
+fn test_narrowing_logic_exprs_and_comprehensions() {
+    check_infer_with_code_flow_analysis(
+        r#"
+def g(x):
+    # type: (string | None) -> None
+    y = x != None and x
+    z = [x for _ in [1] if x]
+    x
+    w = x or ""
+"#,
+        expect![[r#"
+            51..52 "y": bool | string
+            55..56 "x": string | None
+            60..64 "None": None
+            55..64 "x != None": bool
+            69..70 "x": string
+            55..70 "x != None and x": bool | string
+            75..76 "z": list[string]
+            80..81 "x": string
+            86..87 "_": int
+            92..93 "1": Literal[1]
+            91..94 "[1]": list[int]
+            98..99 "x": string | None
+            79..100 "[x for _ in [1] if x]": list[string]
+            105..106 "x": string | None
+            111..112 "w": string
+            115..116 "x": string | None
+            120..122 "\"\"": Literal[""]
+            115..122 "x or \"\"": string
+        "#]],
+    );
+}
+
+#[test]
+fn test_narrowing_unknown_with_type() {
+    check_infer_with_code_flow_analysis(
+        r#"
+def h(x):
+    if type(x) == "list":
+        x
+"#,
+        expect![[r#"
+            18..22 "type": def type(x0: Any) -> string
+            23..24 "x": Unknown
+            18..25 "type(x)": string
+            29..35 "\"list\"": Literal["list"]
+            18..35 "type(x) == \"list\"": bool
+            45..46 "x": list[Unknown]
+        "#]],
+    );
+}
+
 #[test]
 fn test_possibly_unbound() {
     check_infer_with_code_flow_analysis(
@@ -2157,7 +2692,212 @@ def f():
             33..40 "1, 2, 3": tuple[Literal[1], Literal[2], Literal[3]]
             50..51 "x": Literal["one"]
             54..59 "\"one\"": Literal["one"]
-            64..65 "x": string | int
+            64..65 "x": int | string
+        "#]],
+    );
+}
+
+#[test]
+fn test_for_reassignment() {
+    check_infer_with_code_flow_analysis(
+        r#"
+def f(items):
+    # type: (list[string]) -> None
+    total = 0
+    for item in items:
+        total = total + 1
+    total
+    result = None
+    for item in items:
+        if result == None:
+            result = item
+    result
+"#,
+        expect![[r#"
+            54..59 "total": Literal[0]
+            62..63 "0": Literal[0]
+            72..76 "item": string
+            80..85 "items": list[string]
+            95..100 "total": int
+            103..108 "total": int
+            111..112 "1": Literal[1]
+            103..112 "total + 1": int
+            117..122 "total": int
+            127..133 "result": None
+            136..140 "None": None
+            149..153 "item": string
+            157..162 "items": list[string]
+            175..181 "result": None | string
+            185..189 "None": None
+            175..189 "result == None": bool
+            203..209 "result": string
+            212..216 "item": string
+            221..227 "result": None | string
+        "#]],
+    );
+}
+
+#[test]
+fn test_narrowing_eliminated_types() {
+    check_infer_with_code_flow_analysis(
+        r#"
+def f(x):
+    # type: (int) -> None
+    if x == None:
+        x.foo
+        x[0]
+        -x
+        x + 1
+        x()
+    x
+"#,
+        expect![[r#"
+            44..45 "x": int
+            49..53 "None": None
+            44..53 "x == None": bool
+            63..64 "x": Never
+            63..68 "x.foo": Never
+            77..78 "x": Never
+            79..80 "0": Literal[0]
+            77..81 "x[0]": Never
+            91..92 "x": Never
+            90..92 "-x": Never
+            101..102 "x": Never
+            105..106 "1": Literal[1]
+            101..106 "x + 1": Never
+            115..116 "x": Never
+            115..118 "x()": Unknown
+            123..124 "x": int
+        "#]],
+    );
+}
+
+#[test]
+fn test_loop_widening() {
+    check_infer_with_code_flow_analysis(
+        r#"
+def f():
+    x = 1
+    y = 1
+    z = 1
+    for _ in [1]:
+        z = y
+        y = x
+        x = "one"
+    z
+"#,
+        expect![[r#"
+            14..15 "x": Literal[1]
+            18..19 "1": Literal[1]
+            24..25 "y": Literal[1]
+            28..29 "1": Literal[1]
+            34..35 "z": Literal[1]
+            38..39 "1": Literal[1]
+            48..49 "_": int
+            54..55 "1": Literal[1]
+            53..56 "[1]": list[int]
+            66..67 "z": int | string
+            70..71 "y": int | string
+            80..81 "y": int | string
+            84..85 "x": int | string
+            94..95 "x": Literal["one"]
+            98..103 "\"one\"": Literal["one"]
+            108..109 "z": int | string
+        "#]],
+    );
+}
+
+#[test]
+fn test_nested_loops() {
+    check_infer_with_code_flow_analysis(
+        r#"
+def f():
+    x = 1
+    for i in [1]:
+        for j in [1]:
+            x = "one"
+        x
+        x = None
+    x
+"#,
+        expect![[r#"
+            14..15 "x": Literal[1]
+            18..19 "1": Literal[1]
+            28..29 "i": int
+            34..35 "1": Literal[1]
+            33..36 "[1]": list[int]
+            50..51 "j": int
+            56..57 "1": Literal[1]
+            55..58 "[1]": list[int]
+            72..73 "x": Literal["one"]
+            76..81 "\"one\"": Literal["one"]
+            90..91 "x": int | None | string
+            100..101 "x": None
+            104..108 "None": None
+            113..114 "x": int | None
+        "#]],
+    );
+}
+
+#[test]
+fn test_loop_break_and_continue() {
+    check_infer_with_code_flow_analysis(
+        r#"
+def f(items):
+    # type: (list[string]) -> None
+    x = 1
+    for item in items:
+        if item == "a":
+            x = "a"
+            continue
+        if item == "b":
+            x = None
+            break
+        x = 1.5
+    x
+"#,
+        expect![[r#"
+            54..55 "x": Literal[1]
+            58..59 "1": Literal[1]
+            68..72 "item": string
+            76..81 "items": list[string]
+            94..98 "item": string
+            102..105 "\"a\"": Literal["a"]
+            94..105 "item == \"a\"": bool
+            119..120 "x": Literal["a"]
+            123..126 "\"a\"": Literal["a"]
+            159..163 "item": string
+            167..170 "\"b\"": Literal["b"]
+            159..170 "item == \"b\"": bool
+            184..185 "x": None
+            188..192 "None": None
+            219..220 "x": float
+            223..226 "1.5": float
+            231..232 "x": None | int | string | float
+        "#]],
+    );
+}
+
+#[test]
+fn test_loop_without_fixed_point() {
+    check_infer_with_code_flow_analysis(
+        r#"
+def f():
+    x = 1
+    for _ in [1]:
+        x = [x]
+    x
+"#,
+        expect![[r#"
+            14..15 "x": Literal[1]
+            18..19 "1": Literal[1]
+            28..29 "_": int
+            34..35 "1": Literal[1]
+            33..36 "[1]": list[int]
+            46..47 "x": list[int | list[int | list[int | list[int]]] | list[int | list[int | list[int | list[int]]]]]
+            51..52 "x": int | list[int | list[int | list[int]]] | list[int | list[int | list[int | list[int]]]]
+            50..53 "[x]": list[int | list[int | list[int | list[int]]] | list[int | list[int | list[int | list[int]]]]]
+            58..59 "x": int | list[int | list[int | list[int]]] | list[int | list[int | list[int | list[int]]]]
         "#]],
     );
 }
@@ -2302,6 +3042,25 @@ y = 234
     );
 }
 
+#[test]
+fn test_unreachable_without_code_flow_analysis() {
+    check_infer(
+        r#"
+def f():
+    return 1
+    print("a")
+"#,
+        expect![[r#"
+            21..22 "1": Literal[1]
+            27..32 "print": def print(*args: Any, str: string = None) -> None
+            33..36 "\"a\"": Literal["a"]
+            27..37 "print(\"a\")": None
+
+            27..37 Code is unreachable
+        "#]],
+    );
+}
+
 #[test]
 fn test_definitions_beyond_current_scope() {
     check_infer_with_code_flow_analysis(
@@ -2,10 +2,23 @@ use starpls_common::Db;
 use starpls_common::Diagnostic;
 use starpls_common::FileId;
 use starpls_hir::diagnostics_for_file;
+use starpls_hir::recursion_diagnostics;
 
+use crate::lints::lints;
 use crate::Database;
 
 pub(crate) fn diagnostics(db: &Database, file_id: FileId) -> Vec<Diagnostic> {
+    let mut diagnostics = semantic_diagnostics(db, file_id);
+    diagnostics.extend(
+        lints(db, file_id)
+            .into_iter()
+            .map(|result| result.diagnostic),
+    );
+    diagnostics
+}
+
+/// Returns all diagnostics for the given file except for the ones reported by lints.
+pub(crate) fn semantic_diagnostics(db: &Database, file_id: FileId) -> Vec<Diagnostic> {
     let file = match db.get_file(file_id) {
         Some(file) => file,
         None => return Vec::new(),
@@ -18,5 +31,790 @@ pub(crate) fn diagnostics(db: &Database, file_id: FileId) -> Vec<Diagnostic> {
     diagnostics_for_file(db, file)
         .take(128)
         .chain(diagnostics)
+        .chain(recursion_diagnostics(db, file))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+    use std::ops::Range;
+
+    use expect_test::expect;
+    use expect_test::Expect;
+    use starpls_bazel::APIContext;
+    use starpls_common::Db as _;
+    use starpls_common::DiagnosticCode;
+    use starpls_common::Dialect;
+    use starpls_common::FileId;
+    use starpls_common::FileInfo;
+    use starpls_hir::Fixture;
+
+    use crate::Analysis;
+
+    /// Checks the diagnostics with the given code for the last of the given files, ordered by
+    /// their position. Files with paths ending in `:BUILD` are added as BUILD files.
+    fn check_diagnostics(files: &[(&str, &str)], code: DiagnosticCode, expect: Expect) {
+        let (mut analysis, loader) = Analysis::new_for_test();
+        let mut fixture = Fixture::new(&mut analysis.db);
+        let mut last = None;
+        for (path, contents) in files {
+            let file_id = if path.ends_with(":BUILD") {
+                fixture.add_file_with_options(
+                    &mut analysis.db,
+                    path,
+                    contents,
+                    Dialect::Bazel,
+                    Some(FileInfo::Bazel {
+                        api_context: APIContext::Build,
+                        is_external: false,
+                    }),
+                )
+            } else {
+                fixture.add_file(&mut analysis.db, path, contents)
+            };
+            last = Some((file_id, *contents));
+        }
+        loader.add_files_from_fixture(&analysis.db, &fixture);
+
+        let (file_id, contents) = last.expect("at least one file");
+        let mut diagnostics = super::diagnostics(&analysis.db, file_id);
+        diagnostics.retain(|diagnostic| diagnostic.code == code);
+        diagnostics.sort_by_key(|diagnostic| diagnostic.range.range.start());
+
+        let mut actual = String::new();
+        for diagnostic in diagnostics {
+            let range = Range::<usize>::from(diagnostic.range.range);
+            writeln!(
+                actual,
+                "{:?} {}: {}",
+                &contents[range.clone()],
+                range.start,
+                diagnostic.message
+            )
+            .unwrap();
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn test_frozen_mutation() {
+        check_diagnostics(
+            &[
+                ("//:defs.bzl", "LOADED = []\n"),
+                (
+                    "//:main.bzl",
+                    r#"
+load("//:defs.bzl", "LOADED")
+
+_LIST = [1]
+_DICT = {"a": 1}
+_LIST.append(2)
+
+def f(x, y = []):
+    _LIST.append(x)
+    _DICT.update(b = 2)
+    _DICT["c"] = 3
+    _LIST[0] += 1
+    _LIST += [4]
+    y.append(x)
+    local = []
+    local.append(x)
+    LOADED.extend([x])
+    return _DICT.pop("a")
+
+def g():
+    _LIST = []
+    _LIST += [1]
+    return _LIST
+"#,
+                ),
+            ],
+            DiagnosticCode::FrozenMutation,
+            expect![[r#"
+                "_LIST.append(x)" 100: Cannot mutate global "_LIST" from a function, as globals are frozen once the module is loaded
+                "_DICT.update(b = 2)" 120: Cannot mutate global "_DICT" from a function, as globals are frozen once the module is loaded
+                "_DICT[\"c\"]" 144: Cannot mutate global "_DICT" from a function, as globals are frozen once the module is loaded
+                "_LIST[0]" 163: Cannot mutate global "_LIST" from a function, as globals are frozen once the module is loaded
+                "_LIST" 181: Cannot mutate global "_LIST" from a function, as globals are frozen once the module is loaded
+                "LOADED.extend([x])" 249: Cannot mutate "LOADED", as values loaded from other modules are frozen
+                "_DICT.pop(\"a\")" 279: Cannot mutate global "_DICT" from a function, as globals are frozen once the module is loaded
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_frozen_mutation_in_load_time_helpers() {
+        check_diagnostics(
+            &[(
+                "//:main.bzl",
+                r#"
+_REGISTRY = {}
+
+def _register(name, value):
+    _REGISTRY[name] = value
+
+def _register_all():
+    _register("a", 1)
+
+_register_all()
+
+def remove(name):
+    _REGISTRY.pop(name)
+"#,
+            )],
+            DiagnosticCode::FrozenMutation,
+            expect![[r#"
+                "_REGISTRY.pop(name)" 157: Cannot mutate global "_REGISTRY" from a function, as globals are frozen once the module is loaded
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_duplicate_dict_keys() {
+        check_diagnostics(
+            &[(
+                "//:main.bzl",
+                r#"
+d = {"a": 1, "b": 2, "a": 3, 1: 4, 2: 5, 1: 6}
+"#,
+            )],
+            DiagnosticCode::DuplicateKey,
+            expect![[r#"
+                "\"a\"" 22: Duplicate key "a" in dict literal
+                "1" 42: Duplicate key 1 in dict literal
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_duplicate_keys_in_large_dicts() {
+        let entries = (0..40)
+            .map(|i| format!("\"k{}\": {}", i, i))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let contents = format!("d = {{{}, \"k7\": 40}}\n", entries);
+        check_diagnostics(
+            &[("//:main.bzl", contents.as_str())],
+            DiagnosticCode::DuplicateKey,
+            expect![[r#"
+                "\"k7\"" 425: Duplicate key "k7" in dict literal
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_repeated_keyword_argument() {
+        check_diagnostics(
+            &[(
+                "//:main.bzl",
+                r#"
+def f(a, b = 1):
+    pass
+
+f(1, b = 2, b = 3)
+"#,
+            )],
+            DiagnosticCode::InvalidArgument,
+            expect![[r#"
+                "b = 3" 40: Keyword argument "b" is repeated
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_invalid_attribute_values() {
+        check_diagnostics(
+            &[(
+                "//:main.bzl",
+                r#"
+def _impl(ctx):
+    pass
+
+my_rule = rule(
+    implementation = _impl,
+    attrs = {
+        "dep": attr.label(),
+        "deps": attr.label_list(),
+        "mode": attr.string(values = ["fast", "slow"]),
+        "count": attr.int(),
+        "enabled": attr.bool(),
+    },
+)
+
+def my_macro(name):
+    my_rule(
+        name = name,
+        dep = "//foo:",
+        deps = [":a", "//pkg/:b"],
+        mode = "medium",
+        visibility = select({"//conditions:default": ["//visibility:public"]}),
+    )
+    my_rule(
+        name = name + "_2",
+        mode = select({":fast": "fast", "//conditions:default": "other"}),
+        deps = [":a"] + select({":fast": ["//x:"]}),
+    )
+"#,
+            )],
+            DiagnosticCode::InvalidAttributeValue,
+            expect![[r#"
+                "\"//foo:\"" 344: Invalid label "//foo:": empty target
+                "\"//pkg/:b\"" 376: Invalid label "//pkg/:b": package ends with slash
+                "\"medium\"" 404: Value "medium" is not allowed for attribute "mode", expected one of "fast", "slow"
+                "select({\"//conditions:default\": [\"//visibility:public\"]})" 435: Attribute "visibility" is not configurable and cannot be set with select()
+                "\"other\"" 605: Value "other" is not allowed for attribute "mode", expected one of "fast", "slow"
+                "\"//x:\"" 658: Invalid label "//x:": empty target
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_attribute_value_types() {
+        check_diagnostics(
+            &[(
+                "//:main.bzl",
+                r#"
+def _impl(ctx):
+    pass
+
+my_rule = rule(
+    implementation = _impl,
+    attrs = {
+        "deps": attr.label_list(),
+        "count": attr.int(),
+        "enabled": attr.bool(),
+    },
+)
+
+def my_macro(name):
+    my_rule(
+        name = name,
+        deps = ":a",
+        count = True,
+        enabled = 1,
+    )
+    my_rule(
+        name = name + "_2",
+        count = select({":a": 1, "//conditions:default": "2"}),
+        enabled = 2,
+    )
+"#,
+            )],
+            DiagnosticCode::TypeMismatch,
+            expect![[r#"
+                "\":a\"" 260: Argument of type "Literal[":a"]" cannot be assigned to parameter of type "list[string]"
+                "True" 282: Argument of type "Literal[True]" cannot be assigned to parameter of type "int"
+                "\"2\"" 413: Argument of type "Literal["2"]" cannot be assigned to parameter of type "int"
+                "2" 438: Argument of type "Literal[2]" cannot be assigned to parameter of type "bool"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_unresolved_labels() {
+        check_diagnostics(
+            &[
+                (
+                    "//:defs.bzl",
+                    r#"
+def _impl(ctx):
+    pass
+
+my_rule = rule(
+    implementation = _impl,
+    attrs = {
+        "src": attr.label(),
+        "deps": attr.label_list(),
+    },
+)
+"#,
+                ),
+                ("//foo:bar.cc", ""),
+                (
+                    "//foo:BUILD",
+                    r#"
+load("//:defs.bzl", "my_rule")
+
+my_rule(name = "bar")
+"#,
+                ),
+                (
+                    "//pkg:BUILD",
+                    r#"
+load("//:defs.bzl", "my_rule")
+
+my_rule(
+    name = "a",
+    src = "//foo:bar.cc",
+    deps = [
+        ":b",
+        ":c",
+        "//foo:bar",
+        "//foo:barr",
+        "//foo:missing.cc",
+        "//foo:libbar.so",
+        "//nope:x",
+        "@ext//lib:x",
+    ],
+    visibility = ["//visibility:public", "//foo:__pkg__"],
+)
+
+my_rule(name = "b")
+"#,
+                ),
+            ],
+            DiagnosticCode::UnresolvedLabel,
+            expect![[r#"
+                "\":c\"" 119: Could not resolve label ":c": target "c" is not declared in the package's BUILD file
+                "\"//foo:barr\"" 154: Could not resolve label "//foo:barr": target "barr" is not declared in the package's BUILD file
+                "\"//foo:missing.cc\"" 176: Could not resolve label "//foo:missing.cc": source file "missing.cc" does not exist
+                "\"//nope:x\"" 231: Could not resolve label "//nope:x": package does not exist
+                "\"@ext//lib:x\"" 251: Could not resolve label "@ext//lib:x": external repository "ext" has not been fetched yet
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_unresolved_labels_with_unknown_targets() {
+        check_diagnostics(
+            &[
+                (
+                    "//:defs.bzl",
+                    r#"
+def _impl(ctx):
+    pass
+
+my_rule = rule(
+    implementation = _impl,
+    attrs = {
+        "deps": attr.label_list(),
+    },
+)
+
+def my_macro(name):
+    my_rule(name = name + "_test")
+
+def npm_link_all_packages():
+    pass
+"#,
+                ),
+                (
+                    "//computed:BUILD",
+                    r#"
+load("//:defs.bzl", "my_rule")
+
+NAME = "x"
+
+my_rule(name = NAME + "_lib")
+"#,
+                ),
+                (
+                    "//macro:BUILD",
+                    r#"
+load("//:defs.bzl", "my_macro")
+
+my_macro(name = "m")
+"#,
+                ),
+                (
+                    "//nameless:BUILD",
+                    r#"
+load("//:defs.bzl", "npm_link_all_packages")
+
+npm_link_all_packages()
+"#,
+                ),
+                (
+                    "//comp:BUILD",
+                    r#"
+load("//:defs.bzl", "my_rule")
+
+[my_rule(name = name) for name in ["a"]]
+"#,
+                ),
+                (
+                    "//outdir:BUILD",
+                    r#"
+load("//:defs.bzl", "my_rule")
+
+package(default_visibility = ["//visibility:public"])
+
+my_rule(name = "node_modules")
+"#,
+                ),
+                (
+                    "//pkg:BUILD",
+                    r#"
+load("//:defs.bzl", "my_rule")
+
+my_rule(
+    name = "a",
+    deps = [
+        "//computed:x_lib",
+        "//macro:m_test",
+        "//nameless:node_modules/foo",
+        "//comp:a",
+        "//outdir:node_modules/foo",
+        "//outdir:missing",
+    ],
+)
+"#,
+                ),
+            ],
+            DiagnosticCode::UnresolvedLabel,
+            expect![[r#"
+                "\"//outdir:missing\"" 229: Could not resolve label "//outdir:missing": target "missing" is not declared in the package's BUILD file
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_private_symbols() {
+        check_diagnostics(
+            &[
+                (
+                    "//:defs.bzl",
+                    r#"
+_private = 1
+_hidden = 2
+public = 3
+"#,
+                ),
+                (
+                    "//foo:BUILD",
+                    r#"
+load("//:defs.bzl", "_private", "public", _alias = "_hidden")
+"#,
+                ),
+            ],
+            DiagnosticCode::PrivateSymbol,
+            expect![[r#"
+                "\"_private\"" 21: Symbol "_private" is private and cannot be loaded
+                "_alias = \"_hidden\"" 43: Symbol "_hidden" is private and cannot be loaded
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_load_visibility() {
+        check_diagnostics(
+            &[
+                ("//lib:BUILD", ""),
+                (
+                    "//lib:defs.bzl",
+                    r#"
+visibility(["//app/...", "-//app/internal"])
+
+def foo():
+    pass
+"#,
+                ),
+                (
+                    "//lib:private.bzl",
+                    r#"
+visibility("private")
+
+def bar():
+    pass
+"#,
+                ),
+                (
+                    "//lib:public.bzl",
+                    r#"
+visibility("public")
+
+def baz():
+    pass
+"#,
+                ),
+                (
+                    "//app/internal:BUILD",
+                    r#"
+load("//lib:defs.bzl", "foo")
+load("//lib:private.bzl", "bar")
+load("//lib:public.bzl", "baz")
+"#,
+                ),
+            ],
+            DiagnosticCode::LoadVisibility,
+            expect![[r#"
+                "load(\"//lib:defs.bzl\", \"foo\")" 1: Module "//lib:defs.bzl" is not visible to package "//app/internal"
+                "load(\"//lib:private.bzl\", \"bar\")" 31: Module "//lib:private.bzl" is not visible to package "//app/internal"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_load_from_directory_without_package() {
+        check_diagnostics(
+            &[
+                (
+                    "//lib:defs.bzl",
+                    r#"
+def foo():
+    pass
+"#,
+                ),
+                ("//pkg:BUILD", ""),
+                (
+                    "//pkg:defs.bzl",
+                    r#"
+load("//lib:defs.bzl", "foo")
+"#,
+                ),
+            ],
+            DiagnosticCode::UnresolvedModule,
+            expect![[r#"
+                "load(\"//lib:defs.bzl\", \"foo\")" 1: Module "//lib:defs.bzl" is not in a package, since there's no BUILD file in its directory or any of its parents
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_type_mismatch_with_nested_lambdas() {
+        check_diagnostics(
+            &[(
+                "//:main.bzl",
+                r#"
+def f(x):
+    # type: (list[int]) -> None
+    pass
+
+def g(x):
+    # type: (string) -> None
+    pass
+
+f([lambda: 1])
+g((lambda: 1) if True else 2)
+"#,
+            )],
+            DiagnosticCode::TypeMismatch,
+            expect![[r#"
+                "[lambda: 1]" 104: Argument of type "list[def lambda() -> Literal[1]]" cannot be assigned to parameter of type "list[int]"
+                "(lambda: 1) if True else 2" 119: Argument of type "def lambda() -> Literal[1] | int" cannot be assigned to parameter of type "string"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_type_annotations_in_bazel_files() {
+        check_diagnostics(
+            &[(
+                "//:main.bzl",
+                r#"
+def f(x: int, *args: str) -> int:
+    return x
+"#,
+            )],
+            DiagnosticCode::SyntaxError,
+            expect![[r#"
+                ": int" 8: Type annotations are not supported in this dialect
+                ": str" 20: Type annotations are not supported in this dialect
+                "-> int" 27: Type annotations are not supported in this dialect
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_return_type_in_bazel_files() {
+        check_diagnostics(
+            &[(
+                "//:main.bzl",
+                r#"
+def f() -> int:
+    pass
+"#,
+            )],
+            DiagnosticCode::SyntaxError,
+            expect![[r#"
+                "-> int" 9: Type annotations are not supported in this dialect
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_type_mismatch_with_key_functions() {
+        check_diagnostics(
+            &[(
+                "//:main.bzl",
+                r#"
+def by_length(s):
+    # type: (string) -> int
+    return len(s)
+
+def by_value(n):
+    # type: (int) -> int
+    return n
+
+sorted(["a"], key = by_length)
+sorted(["a"], key = by_value)
+sorted(["a"], key = lambda x, y: x)
+"#,
+            )],
+            DiagnosticCode::TypeMismatch,
+            expect![[r#"
+                "by_value" 173: Argument of type "def by_value(n: int) -> int" cannot be assigned to parameter of type "Callable[[string], Any] | None"
+                "lambda x, y: x" 203: Argument of type "def lambda(x: string, y) -> string" cannot be assigned to parameter of type "Callable[[string], Any] | None"
+            "#]],
+        );
+    }
+
+    /// Checks the recursion diagnostics for the last of the given files. Files are numbered in
+    /// order, starting from 0.
+    fn check_recursion(files: &[(&str, &str)], expect: Expect) {
+        let (mut analysis, loader) = Analysis::new_for_test();
+        let mut fixture = Fixture::new(&mut analysis.db);
+        let mut file_id = None;
+        for (path, contents) in files {
+            file_id = Some(fixture.add_file(&mut analysis.db, path, contents));
+        }
+        loader.add_files_from_fixture(&analysis.db, &fixture);
+
+        let contents = |file_id: FileId| {
+            analysis
+                .db
+                .get_file(file_id)
+                .expect("file")
+                .contents(&analysis.db)
+                .clone()
+        };
+        let file = analysis.db.get_file(file_id.expect("file")).expect("file");
+        let mut actual = String::new();
+        for diagnostic in starpls_hir::recursion_diagnostics(&analysis.db, file) {
+            let range = Range::<usize>::from(diagnostic.range.range);
+            writeln!(
+                actual,
+                "{:?} {}: {}",
+                &contents(diagnostic.range.file_id)[range.clone()],
+                range.start,
+                diagnostic.message
+            )
+            .unwrap();
+            for related in diagnostic.related_information.unwrap_or_default() {
+                let range = Range::<usize>::from(related.range.range);
+                writeln!(
+                    actual,
+                    "    {:?} {:?} {}: {}",
+                    related.range.file_id,
+                    &contents(related.range.file_id)[range.clone()],
+                    range.start,
+                    related.message
+                )
+                .unwrap();
+            }
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn test_direct_recursion() {
+        check_recursion(
+            &[(
+                "//:main.bzl",
+                r#"
+def f(n):
+    if n > 0:
+        f(n - 1)
+
+def g():
+    return len([])
+"#,
+            )],
+            expect![[r#"
+                "f(n - 1)" 33: Recursive call to "f", which is not allowed in Starlark: f -> f
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_mutual_recursion() {
+        check_recursion(
+            &[(
+                "//:main.bzl",
+                r#"
+def a():
+    b()
+
+def b():
+    a()
+"#,
+            )],
+            expect![[r#"
+                "b()" 14: Recursive call to "b", which is not allowed in Starlark: a -> b -> a
+                    FileId(0) "a()" 32: "b" calls "a"
+                "a()" 32: Recursive call to "a", which is not allowed in Starlark: b -> a -> b
+                    FileId(0) "b()" 14: "a" calls "b"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_calls_in_lambdas_are_ignored() {
+        check_recursion(
+            &[(
+                "//:main.bzl",
+                r#"
+def a():
+    b()
+
+def b():
+    c = lambda: a()
+    c()
+"#,
+            )],
+            expect![""],
+        );
+    }
+
+    #[test]
+    fn test_nested_functions() {
+        check_recursion(
+            &[(
+                "//:main.bzl",
+                r#"
+def outer():
+    def inner():
+        outer()
+    inner()
+"#,
+            )],
+            expect![[r#"
+                "inner()" 51: Recursive call to "inner", which is not allowed in Starlark: outer -> inner -> outer
+                    FileId(0) "outer()" 39: "inner" calls "outer"
+                "outer()" 39: Recursive call to "outer", which is not allowed in Starlark: inner -> outer -> inner
+                    FileId(0) "inner()" 51: "outer" calls "inner"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_recursion_across_files() {
+        check_recursion(
+            &[
+                (
+                    "//:a.bzl",
+                    r#"
+load("//:b.bzl", "b")
+
+def a():
+    b()
+"#,
+                ),
+                (
+                    "//:b.bzl",
+                    r#"
+load("//:a.bzl", "a")
+
+def b():
+    a()
+"#,
+                ),
+            ],
+            expect![[r#"
+                "a()" 37: Recursive call to "a", which is not allowed in Starlark: b -> a -> b
+                    FileId(0) "b()" 37: "a" calls "b"
+            "#]],
+        );
+    }
+}
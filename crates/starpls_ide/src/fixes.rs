@@ -0,0 +1,390 @@
+use rustc_hash::FxHashSet;
+use starpls_bazel::APIContext;
+use starpls_common::Db;
+use starpls_common::Diagnostic;
+use starpls_common::DiagnosticCode;
+use starpls_common::File;
+use starpls_common::FileId;
+use starpls_hir::Semantics;
+use starpls_syntax::ast::AstNode;
+use starpls_syntax::ast::{self};
+use starpls_syntax::SyntaxKind::*;
+use starpls_syntax::TextRange;
+
+use crate::diagnostics::semantic_diagnostics;
+use crate::lints::lints;
+use crate::lints::move_load_to_top;
+use crate::util::statement_range_with_line_end;
+use crate::Database;
+use crate::TextEdit;
+
+mod type_errors;
+
+/// Describes whether a fix can be applied without human review.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Applicability {
+    /// The fix is known to preserve the meaning of the program, and can be applied automatically.
+    Safe,
+    /// The fix may change the meaning of the program, and should be reviewed before being applied.
+    Unsafe,
+}
+
+/// A set of edits that resolves a diagnostic.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fix {
+    pub label: String,
+    pub code: DiagnosticCode,
+    /// The range of the diagnostic that this fix resolves.
+    pub range: TextRange,
+    pub edits: Vec<TextEdit>,
+    pub applicability: Applicability,
+}
+
+/// Returns the given text with the given edits applied. The edits must not overlap.
+#[cfg(test)]
+pub(crate) fn apply_edits(text: &str, edits: impl IntoIterator<Item = TextEdit>) -> String {
+    let mut edits = edits.into_iter().collect::<Vec<_>>();
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start()));
+    let mut text = text.to_string();
+    for edit in edits {
+        text.replace_range(std::ops::Range::<usize>::from(edit.range), &edit.new_text);
+    }
+    text
+}
+
+pub(crate) fn fixes(db: &Database, file_id: FileId) -> Vec<Fix> {
+    let Some(file) = db.get_file(file_id) else {
+        return Vec::new();
+    };
+
+    let sema = Semantics::new(db);
+    let mut seen_names = FxHashSet::default();
+    semantic_diagnostics(db, file_id)
+        .into_iter()
+        .filter(|diagnostic| diagnostic.range.file_id == file_id)
+        .flat_map(|diagnostic| -> Vec<Fix> {
+            match diagnostic.code {
+                DiagnosticCode::UnusedLoad => remove_unused_load_item(&sema, file, &diagnostic)
+                    .into_iter()
+                    .collect(),
+                DiagnosticCode::UndefinedName => {
+                    // Every reference to an undefined name is reported separately, but only one
+                    // load item needs to be added for all of them.
+                    let name = &file.contents(db)[diagnostic.range.range];
+                    if !seen_names.insert(name.to_string()) {
+                        return Vec::new();
+                    }
+                    add_missing_load_item(&sema, file, &diagnostic, name)
+                        .into_iter()
+                        .collect()
+                }
+                // In BUILD files, misplaced `load` statements are reported during lowering
+                // rather than by the `load-on-top` lint, so the lint doesn't provide their fixes.
+                DiagnosticCode::LoadOnTop if file.api_context(db) == Some(APIContext::Build) => {
+                    move_load_to_top(&sema.parse(file).tree(db), diagnostic.range.range)
+                        .map(|fix| Fix {
+                            label: fix.label,
+                            code: diagnostic.code,
+                            range: diagnostic.range.range,
+                            edits: fix.edits,
+                            applicability: fix.applicability,
+                        })
+                        .into_iter()
+                        .collect()
+                }
+                DiagnosticCode::UnsupportedOperation => {
+                    let mut fixes = type_errors::convert_operand(&sema, file, &diagnostic);
+                    fixes.extend(type_errors::add_none_check(&sema, file, &diagnostic));
+                    fixes
+                }
+                DiagnosticCode::TypeMismatch => {
+                    type_errors::convert_argument(&sema, file, &diagnostic)
+                }
+                DiagnosticCode::PossiblyUnbound => {
+                    type_errors::initialize_variable(&sema, file, &diagnostic)
+                        .into_iter()
+                        .collect()
+                }
+                _ => Vec::new(),
+            }
+        })
+        .chain(
+            lints(db, file_id)
+                .into_iter()
+                .filter_map(|result| result.fix),
+        )
+        .collect()
+}
+
+fn remove_unused_load_item(sema: &Semantics, file: File, diagnostic: &Diagnostic) -> Option<Fix> {
+    let root = sema.parse(file).syntax(sema.db);
+    let load_item = root
+        .covering_element(diagnostic.range.range)
+        .ancestors()
+        .find_map(ast::LoadItem::cast)?;
+    let load_stmt = load_item.syntax().parent().and_then(ast::LoadStmt::cast)?;
+    let items = load_stmt.items().collect::<Vec<_>>();
+    let index = items
+        .iter()
+        .position(|item| item.syntax() == load_item.syntax())?;
+
+    let range = if items.len() == 1 {
+        // Remove the entire `load` statement, along with the rest of its line if there's nothing
+        // else on it.
+        statement_range_with_line_end(load_stmt.syntax())
+    } else if let Some(next_item) = items.get(index + 1) {
+        // Remove the item along with the separator that follows it.
+        TextRange::new(
+            load_item.syntax().text_range().start(),
+            next_item.syntax().text_range().start(),
+        )
+    } else {
+        // This is the last item, so remove the separator that precedes it instead.
+        TextRange::new(
+            items[index - 1].syntax().text_range().end(),
+            load_item.syntax().text_range().end(),
+        )
+    };
+
+    Some(Fix {
+        label: "Remove unused load item".to_string(),
+        code: diagnostic.code,
+        range: diagnostic.range.range,
+        edits: vec![TextEdit {
+            range,
+            new_text: String::new(),
+        }],
+        applicability: Applicability::Safe,
+    })
+}
+
+fn add_missing_load_item(
+    sema: &Semantics,
+    file: File,
+    diagnostic: &Diagnostic,
+    name: &str,
+) -> Option<Fix> {
+    // Only consider modules that are already loaded, and only if exactly one of them exports the
+    // missing name. Otherwise, we can't tell which module the name was meant to come from.
+    let mut candidates = sema
+        .parse(file)
+        .tree(sema.db)
+        .statements()
+        .filter_map(|stmt| match stmt {
+            ast::Statement::Load(load_stmt) => Some(load_stmt),
+            _ => None,
+        })
+        .filter(|load_stmt| {
+            sema.resolve_load_stmt(file, load_stmt)
+                .map(|loaded_file| {
+                    sema.scope_for_module(loaded_file)
+                        .exports()
+                        .any(|(export, _)| export.as_str() == name)
+                })
+                .unwrap_or(false)
+        });
+    let load_stmt = candidates.next()?;
+    if candidates.next().is_some() {
+        return None;
+    }
+
+    let (offset, new_text) = match load_stmt.items().last() {
+        Some(last_item) => {
+            let offset = last_item.syntax().text_range().end();
+            match load_item_indent(&last_item) {
+                Some(indent) => (offset, format!(",\n{}\"{}\"", indent, name)),
+                None => (offset, format!(", \"{}\"", name)),
+            }
+        }
+        None => (
+            load_stmt.module()?.syntax().text_range().end(),
+            format!(", \"{}\"", name),
+        ),
+    };
+
+    Some(Fix {
+        label: format!("Add \"{}\" to the existing load statement", name),
+        code: diagnostic.code,
+        range: diagnostic.range.range,
+        edits: vec![TextEdit {
+            range: TextRange::empty(offset),
+            new_text,
+        }],
+        applicability: Applicability::Unsafe,
+    })
+}
+
+/// Returns the indentation of a load item that starts on its own line, e.g. in a `load` statement
+/// that lists one item per line.
+fn load_item_indent(load_item: &ast::LoadItem) -> Option<String> {
+    let token = load_item.syntax().prev_sibling_or_token()?.into_token()?;
+    if token.kind() != WHITESPACE {
+        return None;
+    }
+    let text = token.text();
+    let line_start = text.rfind('\n')? + 1;
+    Some(text[line_start..].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use expect_test::Expect;
+    use starpls_bazel::APIContext;
+
+    use crate::fixes::apply_edits;
+    use crate::Analysis;
+
+    fn check_fixes(files: &[(&str, &str)], expect: Expect) {
+        let files = files
+            .iter()
+            .map(|(path, contents)| (*path, APIContext::Bzl, *contents))
+            .collect::<Vec<_>>();
+        let (analysis, file_id) = Analysis::from_files_for_test(Default::default(), &files);
+        let fixes = analysis.snapshot().fixes(file_id).unwrap();
+        let (_, _, contents) = files.last().unwrap();
+        expect.assert_eq(&apply_edits(
+            contents,
+            fixes.into_iter().flat_map(|fix| fix.edits),
+        ));
+    }
+
+    #[test]
+    fn test_remove_unused_load_items() {
+        check_fixes(
+            &[
+                (
+                    "//:foo.bzl",
+                    r#"
+def foo():
+    pass
+
+def bar():
+    pass
+
+def baz():
+    pass
+"#,
+                ),
+                (
+                    "//:bar.bzl",
+                    r#"
+load("//:foo.bzl", "foo", "bar", "baz")
+
+bar()
+"#,
+                ),
+            ],
+            expect![[r#"
+
+                load("//:foo.bzl", "bar")
+
+                bar()
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_remove_unused_load_stmt() {
+        check_fixes(
+            &[
+                (
+                    "//:foo.bzl",
+                    r#"
+def foo():
+    pass
+"#,
+                ),
+                (
+                    "//:bar.bzl",
+                    r#"
+load("//:foo.bzl", _foo = "foo")
+
+x = 1
+"#,
+                ),
+            ],
+            expect![[r#"
+
+
+                x = 1
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_keep_load_items_used_in_type_comments() {
+        check_fixes(
+            &[
+                (
+                    "//:foo.bzl",
+                    r#"
+def foo():
+    pass
+"#,
+                ),
+                (
+                    "//:bar.bzl",
+                    r#"
+load("//:foo.bzl", Foo = "foo")
+
+def bar(x):
+    # type: (list[Foo]) -> None
+    pass
+"#,
+                ),
+            ],
+            expect![[r#"
+
+                load("//:foo.bzl", Foo = "foo")
+
+                def bar(x):
+                    # type: (list[Foo]) -> None
+                    pass
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_add_missing_load_item() {
+        check_fixes(
+            &[
+                (
+                    "//:foo.bzl",
+                    r#"
+def foo():
+    pass
+
+def bar():
+    pass
+"#,
+                ),
+                (
+                    "//:bar.bzl",
+                    r#"
+load(
+    "//:foo.bzl",
+    "foo",
+)
+
+foo()
+bar()
+bar()
+"#,
+                ),
+            ],
+            expect![[r#"
+
+                load(
+                    "//:foo.bzl",
+                    "foo",
+                    "bar",
+                )
+
+                foo()
+                bar()
+                bar()
+            "#]],
+        );
+    }
+}
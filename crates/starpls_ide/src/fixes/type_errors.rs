@@ -0,0 +1,421 @@
+//! Fixes for type errors reported during type inference.
+
+use starpls_common::Diagnostic;
+use starpls_common::File;
+use starpls_hir::Semantics;
+use starpls_syntax::ast::AstNode;
+use starpls_syntax::ast::{self};
+use starpls_syntax::SyntaxKind::*;
+use starpls_syntax::SyntaxNode;
+use starpls_syntax::TextRange;
+use starpls_syntax::TextSize;
+
+use crate::Applicability;
+use crate::Fix;
+use crate::TextEdit;
+
+/// Suggests converting an operand of an unsupported arithmetic operation with `str()` or `int()`,
+/// e.g. for `"foo" + 1`.
+pub(super) fn convert_operand(sema: &Semantics, file: File, diagnostic: &Diagnostic) -> Vec<Fix> {
+    let mut fixes = Vec::new();
+    let Some(binary_expr) = find_node_at_range::<ast::BinaryExpr>(sema, file, diagnostic) else {
+        return fixes;
+    };
+    let (Some(lhs), Some(rhs), Some((_, ast::BinaryOp::Arith(op)))) = (
+        binary_expr.lhs(),
+        binary_expr.rhs(),
+        binary_expr.binary_op_info(),
+    ) else {
+        return fixes;
+    };
+    let (Some(lhs_ty), Some(rhs_ty)) =
+        (sema.type_of_expr(file, &lhs), sema.type_of_expr(file, &rhs))
+    else {
+        return fixes;
+    };
+
+    // Concatenate strings instead.
+    if op == ast::ArithOp::Add {
+        if lhs_ty.is_string() && rhs_ty.is_scalar() {
+            fixes.push(wrap_with_call(diagnostic, &rhs, "str"));
+        } else if lhs_ty.is_scalar() && rhs_ty.is_string() {
+            fixes.push(wrap_with_call(diagnostic, &lhs, "str"));
+        }
+    }
+
+    // Do integer arithmetic instead.
+    if lhs_ty.is_string() && rhs_ty.is_int() {
+        fixes.push(wrap_with_call(diagnostic, &lhs, "int"));
+    } else if lhs_ty.is_int() && rhs_ty.is_string() {
+        fixes.push(wrap_with_call(diagnostic, &rhs, "int"));
+    }
+
+    fixes
+}
+
+/// Suggests converting an argument to the type of the parameter that it's passed to, either with
+/// `str()` or `int()`, or by wrapping it in a list.
+pub(super) fn convert_argument(sema: &Semantics, file: File, diagnostic: &Diagnostic) -> Vec<Fix> {
+    let mut fixes = Vec::new();
+    let Some(expr) = find_node_at_range::<ast::Expression>(sema, file, diagnostic) else {
+        return fixes;
+    };
+    let (Some(ty), Some(expected_ty)) = (
+        sema.type_of_expr(file, &expr),
+        sema.expected_type_of_argument(file, &expr),
+    ) else {
+        return fixes;
+    };
+
+    if ty.is_scalar() && expected_ty.accepts_string(sema.db) {
+        fixes.push(wrap_with_call(diagnostic, &expr, "str"));
+    }
+    if ty.is_string() && expected_ty.accepts_int(sema.db) {
+        fixes.push(wrap_with_call(diagnostic, &expr, "int"));
+    }
+    if expected_ty
+        .list_element_ty()
+        .is_some_and(|element_ty| ty.is_assignable_to(sema.db, &element_ty))
+    {
+        fixes.push(Fix {
+            label: "Wrap in a list".to_string(),
+            code: diagnostic.code,
+            range: diagnostic.range.range,
+            edits: wrap(&expr, "[", "]"),
+            applicability: Applicability::Unsafe,
+        });
+    }
+
+    fixes
+}
+
+/// Suggests skipping the statement that contains an unsupported operation if one of its operands
+/// is `None`, e.g. for `x + 1` where `x` has type `int | None`.
+pub(super) fn add_none_check(sema: &Semantics, file: File, diagnostic: &Diagnostic) -> Option<Fix> {
+    let expr = find_node_at_range::<ast::Expression>(sema, file, diagnostic)?;
+    let operands = match &expr {
+        ast::Expression::Binary(expr) => vec![expr.lhs(), expr.rhs()],
+        ast::Expression::Unary(expr) => vec![expr.expr()],
+        _ => return None,
+    };
+
+    // Only check operands that are free of side effects, since they're evaluated twice.
+    let operand = operands.into_iter().flatten().find(|operand| {
+        matches!(operand, ast::Expression::Name(_) | ast::Expression::Dot(_))
+            && sema
+                .type_of_expr(file, operand)
+                .is_some_and(|ty| ty.is_optional())
+    })?;
+
+    // The statement is moved into the body of the new `if` statement, which is only
+    // straightforward if it fits on a single line.
+    let stmt = enclosing_statement(expr.syntax())?;
+    let stmt_text = stmt.text().to_string();
+    if stmt_text.contains('\n') {
+        return None;
+    }
+    let indent = line_indent(file.contents(sema.db), stmt.text_range().start())?;
+    let operand = operand.syntax().text().to_string();
+
+    Some(Fix {
+        label: format!("Add a None check for \"{}\"", operand),
+        code: diagnostic.code,
+        range: diagnostic.range.range,
+        edits: vec![TextEdit {
+            range: stmt.text_range(),
+            new_text: format!("if {} != None:\n{}    {}", operand, indent, stmt_text),
+        }],
+        applicability: Applicability::Unsafe,
+    })
+}
+
+/// Suggests initializing a possibly unbound variable to `None` before the `if` statement or `for`
+/// loop that assigns it.
+pub(super) fn initialize_variable(
+    sema: &Semantics,
+    file: File,
+    diagnostic: &Diagnostic,
+) -> Option<Fix> {
+    let name_ref = find_node_at_range::<ast::NameRef>(sema, file, diagnostic)?;
+    let name = name_ref.syntax().text().to_string();
+    let branch_stmt = find_assigning_branch_stmt(enclosing_statement(name_ref.syntax())?, &name)?;
+    let indent = line_indent(file.contents(sema.db), branch_stmt.text_range().start())?;
+
+    Some(Fix {
+        label: format!(
+            "Initialize \"{}\" before the {}",
+            name,
+            if branch_stmt.kind() == IF_STMT {
+                "if statement"
+            } else {
+                "for loop"
+            }
+        ),
+        code: diagnostic.code,
+        range: diagnostic.range.range,
+        edits: vec![TextEdit {
+            range: TextRange::empty(branch_stmt.text_range().start()),
+            new_text: format!("{} = None\n{}", name, indent),
+        }],
+        applicability: Applicability::Unsafe,
+    })
+}
+
+/// Finds the closest `if` statement or `for` loop that precedes the given statement, or one of
+/// the statements containing it, and assigns the given name. The search stops at function
+/// boundaries.
+fn find_assigning_branch_stmt(mut stmt: SyntaxNode, name: &str) -> Option<SyntaxNode> {
+    loop {
+        let mut prev = stmt.prev_sibling();
+        while let Some(node) = prev {
+            if matches!(node.kind(), IF_STMT | FOR_STMT) && assigns_name(&node, name) {
+                return Some(node);
+            }
+            prev = node.prev_sibling();
+        }
+
+        let parent_stmt = stmt.parent()?.parent()?;
+        if parent_stmt.kind() == DEF_STMT {
+            return None;
+        }
+        stmt = enclosing_statement(&parent_stmt)?;
+    }
+}
+
+fn assigns_name(stmt: &SyntaxNode, name: &str) -> bool {
+    stmt.descendants()
+        .filter_map(ast::NameRef::cast)
+        .any(|name_ref| name_ref.syntax().text() == name && is_assignment_target(&name_ref))
+}
+
+fn is_assignment_target(name_ref: &ast::NameRef) -> bool {
+    let mut node = name_ref.syntax().clone();
+    while let Some(parent) = node.parent() {
+        match parent.kind() {
+            PAREN_EXPR | TUPLE_EXPR | LIST_EXPR => node = parent,
+            LOOP_VARIABLES => {
+                return parent
+                    .parent()
+                    .is_some_and(|parent| parent.kind() == FOR_STMT)
+            }
+            ASSIGN_STMT => {
+                return ast::AssignStmt::cast(parent)
+                    .and_then(|assign_stmt| assign_stmt.lhs())
+                    .is_some_and(|lhs| lhs.syntax() == &node)
+            }
+            _ => return false,
+        }
+    }
+    false
+}
+
+fn find_node_at_range<N: AstNode>(
+    sema: &Semantics,
+    file: File,
+    diagnostic: &Diagnostic,
+) -> Option<N> {
+    let range = diagnostic.range.range;
+    sema.parse(file)
+        .syntax(sema.db)
+        .covering_element(range)
+        .ancestors()
+        .take_while(|node| node.text_range() == range)
+        .find_map(N::cast)
+}
+
+/// Returns the statement containing the given node, i.e. its closest ancestor that is a child of
+/// a suite or of the module itself.
+fn enclosing_statement(node: &SyntaxNode) -> Option<SyntaxNode> {
+    node.ancestors().find(|node| {
+        node.parent()
+            .is_some_and(|parent| matches!(parent.kind(), SUITE | MODULE))
+    })
+}
+
+/// Returns the indentation of the line containing the given offset, or `None` if there's anything
+/// other than whitespace between the start of the line and the offset.
+fn line_indent(text: &str, offset: TextSize) -> Option<&str> {
+    let offset = usize::from(offset);
+    let line_start = text[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let indent = &text[line_start..offset];
+    indent
+        .chars()
+        .all(|c| c == ' ' || c == '\t')
+        .then_some(indent)
+}
+
+fn wrap_with_call(diagnostic: &Diagnostic, expr: &ast::Expression, callee: &str) -> Fix {
+    Fix {
+        label: format!("Wrap in {}()", callee),
+        code: diagnostic.code,
+        range: diagnostic.range.range,
+        edits: wrap(expr, &format!("{}(", callee), ")"),
+        applicability: Applicability::Unsafe,
+    }
+}
+
+fn wrap(expr: &ast::Expression, prefix: &str, suffix: &str) -> Vec<TextEdit> {
+    let range = expr.syntax().text_range();
+    vec![
+        TextEdit {
+            range: TextRange::empty(range.start()),
+            new_text: prefix.to_string(),
+        },
+        TextEdit {
+            range: TextRange::empty(range.end()),
+            new_text: suffix.to_string(),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use expect_test::expect;
+    use expect_test::Expect;
+    use starpls_bazel::APIContext;
+
+    use crate::fixes::apply_edits;
+    use crate::Analysis;
+    use crate::InferenceOptions;
+
+    /// Renders the label of each fix for the given file, followed by the file contents with only
+    /// that fix applied.
+    fn check_type_error_fixes(contents: &str, expect: Expect) {
+        let (analysis, file_id) = Analysis::from_files_for_test(
+            InferenceOptions {
+                use_code_flow_analysis: true,
+                ..Default::default()
+            },
+            &[("//:foo.bzl", APIContext::Bzl, contents)],
+        );
+
+        let mut actual = String::new();
+        for fix in analysis.snapshot().fixes(file_id).unwrap() {
+            let contents = apply_edits(contents, fix.edits);
+            writeln!(actual, "{}\n{}\n", fix.label, contents.trim()).unwrap();
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn test_convert_operand() {
+        check_type_error_fixes(
+            r#"
+x = "foo" + 1
+"#,
+            expect![[r#"
+                Wrap in str()
+                x = "foo" + str(1)
+
+                Wrap in int()
+                x = int("foo") + 1
+
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_convert_operand_subtraction() {
+        check_type_error_fixes(
+            r#"
+x = 1 - "2"
+"#,
+            expect![[r#"
+                Wrap in int()
+                x = 1 - int("2")
+
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_convert_argument() {
+        check_type_error_fixes(
+            r#"
+def f(name, count, srcs):
+    # type: (string, int, list[string]) -> None
+    pass
+
+f(1, "2", "foo.txt")
+"#,
+            expect![[r#"
+                Wrap in str()
+                def f(name, count, srcs):
+                    # type: (string, int, list[string]) -> None
+                    pass
+
+                f(str(1), "2", "foo.txt")
+
+                Wrap in int()
+                def f(name, count, srcs):
+                    # type: (string, int, list[string]) -> None
+                    pass
+
+                f(1, int("2"), "foo.txt")
+
+                Wrap in a list
+                def f(name, count, srcs):
+                    # type: (string, int, list[string]) -> None
+                    pass
+
+                f(1, "2", ["foo.txt"])
+
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_add_none_check() {
+        check_type_error_fixes(
+            r#"
+def f(x):
+    # type: (int | None) -> None
+    print(x + 1)
+"#,
+            expect![[r#"
+                Add a None check for "x"
+                def f(x):
+                    # type: (int | None) -> None
+                    if x != None:
+                        print(x + 1)
+
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_initialize_variable() {
+        check_type_error_fixes(
+            r#"
+def f(cond, srcs):
+    if cond:
+        x = 1
+    for src in srcs:
+        pass
+    print(x, src)
+"#,
+            expect![[r#"
+                Initialize "x" before the if statement
+                def f(cond, srcs):
+                    x = None
+                    if cond:
+                        x = 1
+                    for src in srcs:
+                        pass
+                    print(x, src)
+
+                Initialize "src" before the for loop
+                def f(cond, srcs):
+                    if cond:
+                        x = 1
+                    src = None
+                    for src in srcs:
+                        pass
+                    print(x, src)
+
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,911 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::mem;
+
+use starpls_bazel::APIContext;
+use starpls_common::Db as _;
+use starpls_common::Dialect;
+use starpls_common::FileId;
+use starpls_syntax::ast::AstNode;
+use starpls_syntax::ast::AstToken;
+use starpls_syntax::ast::{self};
+use starpls_syntax::parse_module;
+use starpls_syntax::SyntaxElement;
+use starpls_syntax::SyntaxKind;
+use starpls_syntax::SyntaxKind::*;
+use starpls_syntax::SyntaxNode;
+use starpls_syntax::SyntaxToken;
+use starpls_syntax::TextRange;
+use starpls_syntax::TextSize;
+use starpls_syntax::T;
+
+use crate::Database;
+use crate::TextEdit;
+
+const INDENT_UNIT: &str = "    ";
+
+/// Attributes whose values are sorted in BUILD files when they are lists of string literals.
+const SORTABLE_ATTRIBUTES: &[&str] = &[
+    "data",
+    "deps",
+    "exported_deps",
+    "exports",
+    "hdrs",
+    "resources",
+    "runtime_deps",
+    "srcs",
+    "tests",
+    "textual_hdrs",
+    "visibility",
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FormatError {
+    /// The file contains a syntax error. Files with syntax errors are never formatted, since
+    /// doing so could change their meaning.
+    Syntax { message: String, range: TextRange },
+    /// The formatted output didn't parse cleanly or lost comments. This indicates a bug in the
+    /// formatter, and the file is left unchanged.
+    InvalidOutput,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Syntax { message, range } => write!(
+                f,
+                "syntax error at offset {}: {}",
+                u32::from(range.start()),
+                message
+            ),
+            FormatError::InvalidOutput => f.write_str("formatting produced invalid output"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+/// Formatting rules that only apply to some kinds of files.
+#[derive(Clone, Copy, Debug, Default)]
+struct Style {
+    /// Put each argument of a top-level call on its own line if there's more than one, e.g. for
+    /// rule instantiations in BUILD files.
+    expand_top_level_calls: bool,
+    /// Sort lists of string literals passed to well-known attributes of top-level calls.
+    sort_attribute_lists: bool,
+}
+
+impl Style {
+    fn for_file(dialect: Dialect, api_context: Option<APIContext>) -> Self {
+        match (dialect, api_context) {
+            (Dialect::Bazel, Some(APIContext::Build)) => Style {
+                expand_top_level_calls: true,
+                sort_attribute_lists: true,
+            },
+            (Dialect::Bazel, Some(APIContext::Workspace)) => Style {
+                expand_top_level_calls: true,
+                sort_attribute_lists: false,
+            },
+            _ => Style::default(),
+        }
+    }
+}
+
+/// Formats a file in the database with the rules for its dialect and API context, returning the
+/// edits to apply. Returns `None` if the file doesn't exist or can't be formatted.
+pub(crate) fn format_file(db: &Database, file_id: FileId) -> Option<Vec<TextEdit>> {
+    let file = db.get_file(file_id)?;
+    let contents = file.contents(db);
+    let formatted = format(contents, file.dialect(db), file.api_context(db)).ok()?;
+    if &formatted == contents {
+        return Some(Vec::new());
+    }
+    Some(vec![TextEdit {
+        range: TextRange::up_to(TextSize::of(contents.as_str())),
+        new_text: formatted,
+    }])
+}
+
+/// Formats a Starlark file, loosely following the conventions of buildifier. The rules used
+/// depend on the kind of file, e.g. BUILD files get rule-specific formatting that `.bzl` files
+/// don't.
+pub fn format(
+    text: &str,
+    dialect: Dialect,
+    api_context: Option<APIContext>,
+) -> Result<String, FormatError> {
+    let mut error = None;
+    let parse = parse_module(text, &mut |err| {
+        error.get_or_insert(err);
+    });
+    if let Some(err) = error {
+        return Err(FormatError::Syntax {
+            message: err.message,
+            range: err.range,
+        });
+    }
+
+    let root = parse.syntax();
+    let mut printer = Printer::new(text, Style::for_file(dialect, api_context));
+    printer.statements(&root);
+    let formatted = printer.finish();
+
+    // Sanity check the output, so that a bug in the formatter can never corrupt a file.
+    let mut has_errors = false;
+    let formatted_root = parse_module(&formatted, &mut |_| has_errors = true).syntax();
+    if has_errors || count_comments(&root) != count_comments(&formatted_root) {
+        return Err(FormatError::InvalidOutput);
+    }
+
+    Ok(formatted)
+}
+
+fn count_comments(root: &SyntaxNode) -> usize {
+    root.descendants_with_tokens()
+        .filter(|el| matches!(el.kind(), COMMENT | TYPE_COMMENT))
+        .count()
+}
+
+/// An element of a delimited list, e.g. an argument in a call or an entry in a dict.
+struct Item {
+    node: SyntaxNode,
+    leading_comments: Vec<String>,
+    trailing_comment: Option<String>,
+}
+
+struct Printer<'a> {
+    text: &'a str,
+    style: Style,
+    out: String,
+    indent: usize,
+    at_line_start: bool,
+    blank_line_pending: bool,
+    suppress_blank_line: bool,
+    last_line_is_comment: bool,
+    /// The last token written, used to determine the spacing before the next one. This is `None`
+    /// if no space should be inserted, e.g. after punctuation written by the printer itself.
+    last_token: Option<SyntaxToken>,
+}
+
+impl<'a> Printer<'a> {
+    fn new(text: &'a str, style: Style) -> Self {
+        Self {
+            text,
+            style,
+            out: String::with_capacity(text.len()),
+            indent: 0,
+            at_line_start: true,
+            blank_line_pending: false,
+            suppress_blank_line: true,
+            last_line_is_comment: false,
+            last_token: None,
+        }
+    }
+
+    fn finish(mut self) -> String {
+        while self.out.ends_with("\n\n") {
+            self.out.pop();
+        }
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+        self.out
+    }
+
+    fn write(&mut self, text: &str) {
+        if self.at_line_start {
+            if self.blank_line_pending && !self.out.is_empty() {
+                self.out.push('\n');
+            }
+            for _ in 0..self.indent {
+                self.out.push_str(INDENT_UNIT);
+            }
+            self.at_line_start = false;
+            self.blank_line_pending = false;
+            self.suppress_blank_line = false;
+        }
+        self.out.push_str(text);
+    }
+
+    fn newline(&mut self) {
+        if !self.at_line_start {
+            self.out.push('\n');
+            self.at_line_start = true;
+            self.last_token = None;
+        }
+    }
+
+    fn blank_line(&mut self) {
+        if !self.suppress_blank_line && !self.out.is_empty() {
+            self.blank_line_pending = true;
+        }
+    }
+
+    fn element(&mut self, el: &SyntaxElement) {
+        if let Some(node) = el.as_node() {
+            self.node(node);
+        } else if let Some(token) = el.as_token() {
+            self.token(token);
+        }
+    }
+
+    fn node(&mut self, node: &SyntaxNode) {
+        match node.kind() {
+            SUITE => self.suite(node),
+            TYPE_COMMENT => self.comment(node.clone().into(), &node.text().to_string()),
+            LIST_EXPR | DICT_EXPR | ARGUMENTS | PARAMETERS | LOAD_STMT => self.delimited(node),
+            TUPLE_EXPR if first_token_kind(node) == Some(T!['(']) => self.delimited(node),
+            PAREN_EXPR | LIST_COMP | DICT_COMP => self.grouped(node),
+            BINARY_EXPR => self.binary(node),
+            _ => {
+                for el in node.children_with_tokens() {
+                    self.element(&el);
+                }
+            }
+        }
+    }
+
+    fn token(&mut self, token: &SyntaxToken) {
+        match token.kind() {
+            WHITESPACE | INDENT | DEDENT => {}
+            NEWLINE => {
+                if self.at_line_start {
+                    self.blank_line();
+                } else {
+                    self.newline();
+                }
+            }
+            COMMENT => self.comment(token.clone().into(), token.text()),
+            T![;] => {
+                // Put statements that are separated by semicolons on their own lines.
+                let mut next = token.next_sibling_or_token();
+                while next.as_ref().map(|el| el.kind()) == Some(WHITESPACE) {
+                    next = next.and_then(|el| el.next_sibling_or_token());
+                }
+                if !matches!(next.map(|el| el.kind()), None | Some(NEWLINE | COMMENT)) {
+                    self.newline();
+                }
+            }
+            STRING => self.significant(token, &normalize_string(token.text())),
+            _ => self.significant(token, token.text()),
+        }
+    }
+
+    fn significant(&mut self, token: &SyntaxToken, text: &str) {
+        if !self.at_line_start
+            && self
+                .last_token
+                .as_ref()
+                .map(|prev| needs_space(prev, token))
+                .unwrap_or(false)
+        {
+            self.write(" ");
+        }
+        self.write(text);
+        self.last_token = Some(token.clone());
+        self.last_line_is_comment = false;
+    }
+
+    fn comment(&mut self, el: SyntaxElement, text: &str) {
+        let text = text.trim_end();
+        if self.at_line_start {
+            // Comments on their own line are indented like the surrounding statements, except
+            // that a comment that was dedented relative to its block is kept that way.
+            let indent = mem::replace(&mut self.indent, 0);
+            self.indent = indent.min(self.comment_indent(&el));
+            self.write(text);
+            self.indent = indent;
+            self.last_line_is_comment = true;
+        } else {
+            self.write("  ");
+            self.write(text);
+        }
+
+        // Comments within statements must be followed by a line break, since they extend to the
+        // end of the line. At the statement level, the line break is a separate token.
+        if !matches!(
+            el.parent().map(|parent| parent.kind()),
+            Some(MODULE | SUITE)
+        ) {
+            self.newline();
+        }
+        self.last_token = None;
+    }
+
+    /// Returns the number of enclosing blocks that a comment was indented into.
+    fn comment_indent(&self, el: &SyntaxElement) -> usize {
+        let start = usize::from(el.text_range().start());
+        let line_start = self.text[..start].rfind('\n').map_or(0, |pos| pos + 1);
+        let column = self.text[line_start..start].chars().count();
+        el.parent()
+            .into_iter()
+            .flat_map(|parent| parent.ancestors())
+            .filter(|node| node.kind() == SUITE)
+            .filter_map(|suite| {
+                suite
+                    .children_with_tokens()
+                    .find(|el| el.kind() == INDENT)
+                    .map(|indent| usize::from(indent.text_range().len()))
+            })
+            .filter(|width| *width <= column)
+            .count()
+    }
+
+    fn statements(&mut self, node: &SyntaxNode) {
+        let is_module = node.kind() == MODULE;
+        for el in node.children_with_tokens() {
+            if is_module && el.kind() == DEF_STMT {
+                // Separate top-level function definitions from the surrounding statements.
+                if !self.last_line_is_comment {
+                    self.blank_line();
+                }
+                self.element(&el);
+                self.blank_line();
+            } else {
+                self.element(&el);
+            }
+        }
+    }
+
+    fn suite(&mut self, node: &SyntaxNode) {
+        self.indent += 1;
+        self.suppress_blank_line = true;
+
+        // Expand suites that are on the same line as their header, e.g. `if x: return`.
+        let is_inline = node
+            .children_with_tokens()
+            .find(|el| el.kind() != WHITESPACE)
+            .map(|el| !matches!(el.kind(), NEWLINE | COMMENT | TYPE_COMMENT))
+            .unwrap_or(false);
+        if is_inline {
+            self.newline();
+        }
+
+        self.statements(node);
+        self.indent -= 1;
+    }
+
+    /// Prints a comma-separated list enclosed by delimiters, e.g. a list or the arguments to a
+    /// call. The list is printed on a single line unless it was already split across multiple
+    /// lines, contains comments, or ends with a trailing comma.
+    fn delimited(&mut self, node: &SyntaxNode) {
+        let mut elements = node.children_with_tokens();
+
+        // Print everything up to the opening delimiter, e.g. the `load` keyword.
+        let open = loop {
+            match elements.next() {
+                Some(el) if matches!(el.kind(), T!['('] | T!['['] | T!['{']) => {
+                    break el.into_token().unwrap();
+                }
+                Some(el) => self.element(&el),
+                None => return,
+            }
+        };
+
+        let mut items: Vec<Item> = Vec::new();
+        let mut open_comment = None;
+        let mut dangling_comments = Vec::new();
+        let mut close = None;
+        let mut has_newline = false;
+        let mut newline_since_last = false;
+        let mut has_trailing_comma = false;
+        for el in elements {
+            match el.kind() {
+                WHITESPACE => {
+                    if el.as_token().unwrap().text().contains('\n') {
+                        has_newline = true;
+                        newline_since_last = true;
+                    }
+                }
+                T![,] => {
+                    has_trailing_comma = true;
+                    newline_since_last = false;
+                }
+                T![')'] | T![']'] | T!['}'] => {
+                    close = el.into_token();
+                    break;
+                }
+                COMMENT | TYPE_COMMENT => {
+                    let text = el.to_string().trim_end().to_string();
+                    let can_trail = !newline_since_last && dangling_comments.is_empty();
+                    match items.last_mut() {
+                        Some(item) if can_trail && item.trailing_comment.is_none() => {
+                            item.trailing_comment = Some(text)
+                        }
+                        None if can_trail && open_comment.is_none() => open_comment = Some(text),
+                        _ => dangling_comments.push(text),
+                    }
+                    newline_since_last = true;
+                }
+                _ => {
+                    if let Some(node) = el.into_node() {
+                        items.push(Item {
+                            node,
+                            leading_comments: mem::take(&mut dangling_comments),
+                            trailing_comment: None,
+                        });
+                    }
+                    has_trailing_comma = false;
+                    newline_since_last = false;
+                }
+            }
+        }
+
+        let has_comments = open_comment.is_some()
+            || !dangling_comments.is_empty()
+            || items
+                .iter()
+                .any(|item| !item.leading_comments.is_empty() || item.trailing_comment.is_some());
+        let is_single_element_tuple = node.kind() == TUPLE_EXPR && items.len() == 1;
+        let expand = has_comments
+            || (!items.is_empty()
+                && (has_newline
+                    || (has_trailing_comma && !is_single_element_tuple)
+                    || self.is_expanded_call(node, items.len())));
+
+        if !has_comments {
+            self.sort_items(node, &mut items);
+        }
+
+        self.significant(&open, open.text());
+        if expand {
+            self.indent += 1;
+            if let Some(comment) = open_comment {
+                self.write("  ");
+                self.write(&comment);
+            }
+            for item in items {
+                self.newline();
+                for comment in item.leading_comments {
+                    self.write(&comment);
+                    self.newline();
+                }
+                self.node(&item.node);
+                self.write(",");
+                if let Some(comment) = item.trailing_comment {
+                    self.write("  ");
+                    self.write(&comment);
+                }
+            }
+            for comment in dangling_comments {
+                self.newline();
+                self.write(&comment);
+            }
+            self.indent -= 1;
+            self.newline();
+        } else {
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    self.write(", ");
+                    self.last_token = None;
+                }
+                self.node(&item.node);
+            }
+            if is_single_element_tuple {
+                self.write(",");
+            }
+        }
+
+        if let Some(close) = close {
+            self.significant(&close, close.text());
+        }
+    }
+
+    fn is_expanded_call(&self, node: &SyntaxNode, num_arguments: usize) -> bool {
+        self.style.expand_top_level_calls
+            && node.kind() == ARGUMENTS
+            && num_arguments > 1
+            && node
+                .parent()
+                .and_then(|call| call.parent())
+                .map(|parent| parent.kind() == MODULE)
+                .unwrap_or(false)
+    }
+
+    fn sort_items(&self, node: &SyntaxNode, items: &mut [Item]) {
+        match node.kind() {
+            // The loaded module always comes first, followed by the loaded symbols sorted by the
+            // names they're bound to.
+            LOAD_STMT if items.len() > 1 => {
+                items[1..].sort_by_cached_key(|item| load_item_name(&item.node));
+            }
+            LIST_EXPR if self.style.sort_attribute_lists && is_sortable_list(node) => {
+                if items
+                    .iter()
+                    .all(|item| string_literal_value(&item.node).is_some())
+                {
+                    items.sort_by_cached_key(|item| {
+                        label_sort_key(string_literal_value(&item.node).unwrap_or_default())
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Prints an expression enclosed by delimiters that isn't a comma-separated list, i.e. a
+    /// parenthesized expression or a comprehension.
+    fn grouped(&mut self, node: &SyntaxNode) {
+        let expand = node.children_with_tokens().any(|el| {
+            el.kind() == COMMENT
+                || el
+                    .as_token()
+                    .map(|token| token.kind() == WHITESPACE && token.text().contains('\n'))
+                    .unwrap_or(false)
+        });
+
+        for el in node.children_with_tokens() {
+            match el.kind() {
+                T!['('] | T!['['] | T!['{'] => {
+                    self.element(&el);
+                    if expand {
+                        self.indent += 1;
+                        self.newline();
+                    }
+                }
+                T![')'] | T![']'] | T!['}'] => {
+                    if expand {
+                        self.indent -= 1;
+                        self.newline();
+                    }
+                    self.element(&el);
+                }
+                COMP_CLAUSE_FOR | COMP_CLAUSE_IF if expand => {
+                    self.newline();
+                    self.element(&el);
+                }
+                _ => self.element(&el),
+            }
+        }
+    }
+
+    /// Prints a binary expression, preserving line breaks around its operator.
+    fn binary(&mut self, node: &SyntaxNode) {
+        let mut is_continued = false;
+        for el in node.children_with_tokens() {
+            let is_line_break = el
+                .as_token()
+                .map(|token| {
+                    token.kind() == WHITESPACE
+                        && token.text().contains('\n')
+                        && !token.text().contains('\\')
+                })
+                .unwrap_or(false);
+            if is_line_break {
+                if !is_continued {
+                    self.indent += 1;
+                    is_continued = true;
+                }
+                self.newline();
+            } else {
+                self.element(&el);
+            }
+        }
+        if is_continued {
+            self.indent -= 1;
+        }
+    }
+}
+
+fn first_token_kind(node: &SyntaxNode) -> Option<SyntaxKind> {
+    node.first_token().map(|token| token.kind())
+}
+
+/// Returns whether a list is the value of a sortable attribute of a top-level call, e.g. the `srcs`
+/// of a rule instantiated in a BUILD file.
+fn is_sortable_list(node: &SyntaxNode) -> bool {
+    let Some(argument) = node.parent().and_then(ast::KeywordArgument::cast) else {
+        return false;
+    };
+    let is_sortable_attribute = argument
+        .name()
+        .map(|name| SORTABLE_ATTRIBUTES.contains(&name.syntax().text().to_string().as_str()))
+        .unwrap_or(false);
+    is_sortable_attribute
+        && argument
+            .syntax()
+            .ancestors()
+            .nth(3)
+            .map(|parent| parent.kind() == MODULE)
+            .unwrap_or(false)
+}
+
+fn string_literal_value(node: &SyntaxNode) -> Option<String> {
+    if node.kind() != LITERAL_EXPR {
+        return None;
+    }
+    let token = node.first_token()?;
+    ast::String::cast(token)?.value().map(String::from)
+}
+
+/// Sorts labels so that local targets come first, followed by targets in the same repository and
+/// then targets in other repositories. This matches the order used by buildifier.
+fn label_sort_key(label: String) -> (u8, String) {
+    let priority = if label.starts_with('@') {
+        3
+    } else if label.starts_with("//") {
+        2
+    } else if label.starts_with(':') {
+        1
+    } else {
+        0
+    };
+    (priority, label)
+}
+
+fn load_item_name(node: &SyntaxNode) -> Option<String> {
+    match ast::LoadItem::cast(node.clone())? {
+        ast::LoadItem::Direct(item) => ast::String::cast(item.name()?)?.value().map(String::from),
+        ast::LoadItem::Aliased(item) => Some(item.alias()?.syntax().text().to_string()),
+    }
+}
+
+fn needs_space(prev: &SyntaxToken, cur: &SyntaxToken) -> bool {
+    let parent_kind = |token: &SyntaxToken| token.parent().map(|parent| parent.kind());
+    match (prev.kind(), cur.kind()) {
+        (T!['('] | T!['['] | T!['{'] | T![.], _) => false,
+        (_, T![')'] | T![']'] | T!['}'] | T![,] | T![;] | T![:] | T![.]) => false,
+        (_, T!['('] | T!['['])
+            if matches!(
+                parent_kind(cur),
+                Some(ARGUMENTS | PARAMETERS | INDEX_EXPR | SLICE_EXPR | LOAD_STMT)
+            ) =>
+        {
+            false
+        }
+        (T![:], _) => parent_kind(prev) != Some(SLICE_EXPR),
+        (T![+] | T![-] | T![~], _) => parent_kind(prev) != Some(UNARY_EXPR),
+        (T![*] | T![**], _) => !matches!(
+            parent_kind(prev),
+            Some(
+                ARGS_LIST_PARAMETER
+                    | KWARGS_DICT_PARAMETER
+                    | UNPACKED_LIST_ARGUMENT
+                    | UNPACKED_DICT_ARGUMENT
+            )
+        ),
+        _ => true,
+    }
+}
+
+/// Converts single-quoted strings to double-quoted ones, if that doesn't require any escaping.
+fn normalize_string(text: &str) -> Cow<'_, str> {
+    let Some(prefix_len) = text.find(['\'', '"']) else {
+        return Cow::Borrowed(text);
+    };
+    let (prefix, quoted) = text.split_at(prefix_len);
+    for quote in ["'''", "'"] {
+        if quoted.len() >= 2 * quote.len() && quoted.starts_with(quote) && quoted.ends_with(quote) {
+            let body = &quoted[quote.len()..quoted.len() - quote.len()];
+            if body.contains(['"', '\\']) {
+                break;
+            }
+            let quote = quote.replace('\'', "\"");
+            return Cow::Owned(format!("{}{}{}{}", prefix, quote, body, quote));
+        }
+    }
+    Cow::Borrowed(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use expect_test::Expect;
+    use starpls_bazel::APIContext;
+    use starpls_common::Dialect;
+
+    use super::format;
+    use super::FormatError;
+
+    fn check_with_api_context(input: &str, api_context: APIContext, expect: Expect) {
+        let formatted = format(input, Dialect::Bazel, Some(api_context)).unwrap();
+        expect.assert_eq(&formatted);
+        assert_eq!(
+            format(&formatted, Dialect::Bazel, Some(api_context)).as_ref(),
+            Ok(&formatted)
+        );
+    }
+
+    fn check(input: &str, expect: Expect) {
+        check_with_api_context(input, APIContext::Bzl, expect)
+    }
+
+    #[test]
+    fn test_format_statements() {
+        check(
+            r#"
+load("//:foo.bzl", "foo", 'bar')
+x = [1,2,3]; y = {'a' : 1}  # trailing comment
+def f(a, b=1, *args, **kwargs):
+  if a: return b
+  # comment
+  return [x for x in args if x]
+z = f(-1, *y)
+"#,
+            expect![[r#"
+                load("//:foo.bzl", "bar", "foo")
+                x = [1, 2, 3]
+                y = {"a": 1}  # trailing comment
+
+                def f(a, b = 1, *args, **kwargs):
+                    if a:
+                        return b
+                    # comment
+                    return [x for x in args if x]
+
+                z = f(-1, *y)
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_format_multiline_collections() {
+        check(
+            r#"
+x = [
+    1,  # one
+    # two
+    2
+]
+y = foo(a,
+        b)
+z = (1,)
+w = [a, b,]
+"#,
+            expect![[r#"
+                x = [
+                    1,  # one
+                    # two
+                    2,
+                ]
+                y = foo(
+                    a,
+                    b,
+                )
+                z = (1,)
+                w = [
+                    a,
+                    b,
+                ]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_format_build_file() {
+        check_with_api_context(
+            r#"
+cc_library(name = "foo", srcs = ["b.cc", "//x:y", ":gen", "a.cc"], deps = ["@z//:w", "//a"])
+cc_binary(name="bin")
+"#,
+            APIContext::Build,
+            expect![[r#"
+                cc_library(
+                    name = "foo",
+                    srcs = ["a.cc", "b.cc", ":gen", "//x:y"],
+                    deps = ["//a", "@z//:w"],
+                )
+                cc_binary(name = "bin")
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_format_comments() {
+        check(
+            r#"# Header comment.
+
+# Comment before a load.
+load("//:foo.bzl", "foo")  # trailing load comment
+def f(x):  # trailing def comment
+    # leading body comment
+    y = x # trailing comment
+    # trailing body comment
+
+# Comment between definitions.
+z = {
+  # leading entry comment
+  "a": 1,  # trailing entry comment
+  "b": [1, 2],
+  # dangling comment
+}
+call(
+    a,  # first
+    # before b
+    b = 2,
+)
+# Final comment.
+"#,
+            expect![[r##"
+                # Header comment.
+
+                # Comment before a load.
+                load("//:foo.bzl", "foo")  # trailing load comment
+
+                def f(x):  # trailing def comment
+                    # leading body comment
+                    y = x  # trailing comment
+                    # trailing body comment
+
+                # Comment between definitions.
+                z = {
+                    # leading entry comment
+                    "a": 1,  # trailing entry comment
+                    "b": [1, 2],
+                    # dangling comment
+                }
+                call(
+                    a,  # first
+                    # before b
+                    b = 2,
+                )
+                # Final comment.
+            "##]],
+        );
+    }
+
+    #[test]
+    fn test_format_nested_brackets() {
+        check(
+            r#"
+x = {"a": [1, (2, 3), {"b": [4]}], "c": f(g(h(1)), [i for i in [1, 2]])}
+y = foo(bar(1,
+    2), [
+    [1, 2],
+    [3, 4]])
+z = [[
+    1,
+]]
+w = f()[0](1)[{"k": "v"}["k"]]
+"#,
+            expect![[r#"
+                x = {"a": [1, (2, 3), {"b": [4]}], "c": f(g(h(1)), [i for i in [1, 2]])}
+                y = foo(bar(
+                    1,
+                    2,
+                ), [
+                    [1, 2],
+                    [3, 4],
+                ])
+                z = [[
+                    1,
+                ]]
+                w = f()[0](1)[{"k": "v"}["k"]]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_format_idempotent() {
+        let inputs = [
+            "x = 1\n",
+            "def f():\n    pass\n",
+            "x = [\n    1,\n    # comment\n]\n",
+            "f(\n    a,\n    b = [\n        c,\n    ],\n)  # trailing\n",
+            "if a:\n    pass\nelif b:\n    pass\nelse:\n    pass\n",
+            "x = (\n    1 +\n    2\n)\n",
+            "x = lambda a, b = 1: a if b else -a\n",
+            "for a, b in c.items():\n    continue\n",
+            "\"\"\"Docstring.\"\"\"\n\nload(\":a.bzl\", \"a\")\n",
+        ];
+        for api_context in [APIContext::Bzl, APIContext::Build] {
+            for input in inputs {
+                let formatted = format(input, Dialect::Bazel, Some(api_context)).unwrap();
+                assert_eq!(
+                    format(&formatted, Dialect::Bazel, Some(api_context)).as_ref(),
+                    Ok(&formatted),
+                    "formatting isn't idempotent for {:?}",
+                    input
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_syntax_error() {
+        assert!(matches!(
+            format("def f(:\n", Dialect::Bazel, Some(APIContext::Bzl)),
+            Err(FormatError::Syntax { .. })
+        ));
+    }
+}
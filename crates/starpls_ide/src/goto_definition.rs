@@ -307,6 +307,7 @@ impl<'a> GotoDefinitionHandler<'a> {
                     target_file_id: build_file_id,
                 }])
             }
+            ResolvedPath::MissingPackage | ResolvedPath::UnfetchedRepository { .. } => None,
         }
     }
 
@@ -5,16 +5,22 @@ use std::sync::Arc;
 
 use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
+use rustc_hash::FxHashSet;
 use salsa::ParallelDatabase;
 use starpls_bazel::APIContext;
 use starpls_bazel::Builtins;
+use starpls_bazel::Label;
 use starpls_common::Db;
 use starpls_common::Diagnostic;
+use starpls_common::DiagnosticCode;
 use starpls_common::Dialect;
 use starpls_common::File;
 use starpls_common::FileId;
 use starpls_common::FileInfo;
+use starpls_common::FilePackage;
+use starpls_common::LabelResolution;
 use starpls_common::LoadItemCandidate;
+use starpls_common::PackageResolution;
 use starpls_common::ResolvedPath;
 use starpls_hir::BuiltinDefs;
 pub use starpls_hir::Cancelled;
@@ -36,6 +42,10 @@ pub use crate::completions::TextEdit;
 pub use crate::document_symbols::DocumentSymbol;
 pub use crate::document_symbols::SymbolKind;
 pub use crate::document_symbols::SymbolTag;
+pub use crate::fixes::Applicability;
+pub use crate::fixes::Fix;
+pub use crate::format::format;
+pub use crate::format::FormatError;
 pub use crate::hover::Hover;
 pub use crate::hover::Markup;
 pub use crate::signature_help::ParameterInfo;
@@ -46,9 +56,12 @@ mod completions;
 mod diagnostics;
 mod document_symbols;
 mod find_references;
+mod fixes;
+mod format;
 mod goto_definition;
 mod hover;
 mod line_index;
+mod lints;
 mod show_hir;
 mod show_syntax_tree;
 mod signature_help;
@@ -61,13 +74,51 @@ pub(crate) struct Database {
     builtin_defs: Arc<DashMap<Dialect, BuiltinDefs>>,
     storage: salsa::Storage<Self>,
     files: Arc<DashMap<FileId, File>>,
+    label_resolutions: Arc<DashMap<(FileId, String), LabelResolution>>,
+    package_resolutions: Arc<DashMap<FileId, PackageResolution>>,
     loader: Arc<dyn FileLoader>,
     gcx: Arc<GlobalContext>,
     prelude_file: Option<FileId>,
     all_workspace_targets: Arc<Vec<String>>,
+    enabled_lints: Arc<FxHashSet<DiagnosticCode>>,
 }
 
 impl Database {
+    fn resolve_label_path(&self, label: &str, from: FileId) -> Option<ResolvedPath> {
+        let dialect = self.get_file(from)?.dialect(self);
+        self.resolve_path(label, dialect, from).ok().flatten()
+    }
+
+    /// Resolves all previously resolved labels and packages again, updating the ones whose
+    /// results changed.
+    fn refresh_label_resolutions(&mut self) {
+        let gcx = self.gcx.clone();
+        let _guard = gcx.cancel();
+        let label_resolutions = self
+            .label_resolutions
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect::<Vec<_>>();
+        for ((from, label), label_resolution) in label_resolutions {
+            let resolved_path = self.resolve_label_path(&label, from);
+            if label_resolution.resolved_path(self) != &resolved_path {
+                label_resolution.set_resolved_path(self).to(resolved_path);
+            }
+        }
+
+        let package_resolutions = self
+            .package_resolutions
+            .iter()
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect::<Vec<_>>();
+        for (file_id, package_resolution) in package_resolutions {
+            let package = self.loader.resolve_package(file_id);
+            if package_resolution.package(self) != &package {
+                package_resolution.set_package(self).to(package);
+            }
+        }
+    }
+
     fn apply_file_changes(&mut self, changes: Vec<(FileId, FileChange)>) {
         let gcx = self.gcx.clone();
         let _guard = gcx.cancel();
@@ -95,11 +146,14 @@ impl salsa::ParallelDatabase for Database {
         salsa::Snapshot::new(Database {
             builtin_defs: self.builtin_defs.clone(),
             files: self.files.clone(),
+            label_resolutions: self.label_resolutions.clone(),
+            package_resolutions: self.package_resolutions.clone(),
             gcx: self.gcx.clone(),
             loader: self.loader.clone(),
             storage: self.storage.snapshot(),
             prelude_file: self.prelude_file,
             all_workspace_targets: self.all_workspace_targets.clone(),
+            enabled_lints: self.enabled_lints.clone(),
         })
     }
 }
@@ -195,9 +249,29 @@ impl starpls_common::Db for Database {
         Ok(Some(resolved_path))
     }
 
+    fn resolve_label(&self, label: &str, from: FileId) -> LabelResolution {
+        match self.label_resolutions.entry((from, label.to_string())) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                let resolved_path = self.resolve_label_path(label, from);
+                *entry.insert(LabelResolution::new(self, resolved_path))
+            }
+        }
+    }
+
     fn resolve_build_file(&self, file_id: FileId) -> Option<String> {
         self.loader.resolve_build_file(file_id)
     }
+
+    fn resolve_package(&self, file_id: FileId) -> PackageResolution {
+        match self.package_resolutions.entry(file_id) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                let package = self.loader.resolve_package(file_id);
+                *entry.insert(PackageResolution::new(self, package))
+            }
+        }
+    }
 }
 
 impl starpls_hir::Db for Database {
@@ -298,11 +372,14 @@ impl Analysis {
             db: Database {
                 builtin_defs: Default::default(),
                 files: Default::default(),
+                label_resolutions: Default::default(),
+                package_resolutions: Default::default(),
                 gcx: Arc::new(GlobalContext::new(options)),
                 storage: Default::default(),
                 loader,
                 prelude_file: None,
                 all_workspace_targets: Arc::default(),
+                enabled_lints: Arc::default(),
             },
         }
     }
@@ -317,6 +394,21 @@ impl Analysis {
         }
     }
 
+    /// Sets the number of threads that can run type inference concurrently, e.g. when computing
+    /// diagnostics from multiple snapshots in parallel. Each thread maintains its own inference
+    /// caches, so this also discards any cached inference results.
+    pub fn set_max_inference_threads(&mut self, num_threads: usize) {
+        let options = self.db.gcx.options().clone();
+        self.db.gcx = Arc::new(GlobalContext::with_num_contexts(options, num_threads));
+    }
+
+    /// Replaces the options used for type inference, e.g. after the workspace's settings change.
+    /// This discards any cached inference results.
+    pub fn set_inference_options(&mut self, options: InferenceOptions) {
+        let num_contexts = self.db.gcx.num_contexts();
+        self.db.gcx = Arc::new(GlobalContext::with_num_contexts(options, num_contexts));
+    }
+
     pub fn set_builtin_defs(&mut self, builtins: Builtins, rules: Builtins) {
         self.db.set_builtin_defs(Dialect::Bazel, builtins, rules);
     }
@@ -329,6 +421,17 @@ impl Analysis {
         self.db.set_all_workspace_targets(targets);
     }
 
+    /// Resolves the labels and packages checked by previous analyses again, e.g. after packages or
+    /// source files were added or removed.
+    pub fn refresh_label_resolutions(&mut self) {
+        self.db.refresh_label_resolutions();
+    }
+
+    /// Enables the lints with the given codes in addition to the ones that are on by default.
+    pub fn set_enabled_lints(&mut self, codes: impl IntoIterator<Item = DiagnosticCode>) {
+        self.db.enabled_lints = Arc::new(codes.into_iter().collect());
+    }
+
     #[cfg(test)]
     pub(crate) fn new_for_test() -> (Analysis, Arc<SimpleFileLoader>) {
         let loader = Arc::new(SimpleFileLoader::default());
@@ -336,6 +439,33 @@ impl Analysis {
         (analysis, loader)
     }
 
+    /// Creates an analysis containing the given files, each given as its path, API context, and
+    /// contents. The files can load each other. Returns the ID of the last file.
+    #[cfg(test)]
+    pub(crate) fn from_files_for_test(
+        options: InferenceOptions,
+        files: &[(&str, APIContext, &str)],
+    ) -> (Analysis, FileId) {
+        let loader = Arc::new(SimpleFileLoader::default());
+        let mut analysis = Analysis::new(loader.clone(), options);
+        let mut fixture = Fixture::new(&mut analysis.db);
+        let mut last = None;
+        for (path, api_context, contents) in files {
+            last = Some(fixture.add_file_with_options(
+                &mut analysis.db,
+                path,
+                contents,
+                Dialect::Bazel,
+                Some(FileInfo::Bazel {
+                    api_context: api_context.clone(),
+                    is_external: false,
+                }),
+            ));
+        }
+        loader.add_files_from_fixture(&analysis.db, &fixture);
+        (analysis, last.expect("at least one file"))
+    }
+
     #[cfg(test)]
     pub(crate) fn from_single_file_fixture(fixture: &str) -> (Analysis, Fixture) {
         let (mut analysis, loader) = Self::new_for_test();
@@ -350,6 +480,13 @@ pub struct AnalysisSnapshot {
 }
 
 impl AnalysisSnapshot {
+    /// Takes another snapshot of the same database, e.g. for use on another thread.
+    pub fn snapshot(&self) -> AnalysisSnapshot {
+        AnalysisSnapshot {
+            db: self.db.snapshot(),
+        }
+    }
+
     pub fn completions(
         &self,
         pos: FilePosition,
@@ -370,6 +507,14 @@ impl AnalysisSnapshot {
         self.query(|db| find_references::find_references(db, pos))
     }
 
+    pub fn fixes(&self, file_id: FileId) -> Cancellable<Vec<Fix>> {
+        self.query(|db| fixes::fixes(db, file_id))
+    }
+
+    pub fn format(&self, file_id: FileId) -> Cancellable<Option<Vec<TextEdit>>> {
+        self.query(|db| format::format_file(db, file_id))
+    }
+
     pub fn goto_definition(
         &self,
         pos: FilePosition,
@@ -470,6 +615,9 @@ pub trait FileLoader: Send + Sync + 'static {
 
     /// If the specified file is a BUILD file, returns its package.
     fn resolve_build_file(&self, file_id: FileId) -> Option<String>;
+
+    /// Returns the package that contains the specified file.
+    fn resolve_package(&self, file_id: FileId) -> Option<FilePackage>;
 }
 
 /// Simple implementation of [`FileLoader`] backed by a HashMap.
@@ -496,13 +644,52 @@ impl SimpleFileLoader {
 }
 
 impl FileLoader for SimpleFileLoader {
+    /// Resolves labels against the paths of the loaded files, e.g. `//foo:bar.txt`. Packages are
+    /// declared by files named `//<package>:BUILD`, and all external repositories are treated as
+    /// unfetched.
     fn resolve_path(
         &self,
-        _path: &str,
+        path: &str,
         _dialect: Dialect,
-        _from: FileId,
+        from: FileId,
     ) -> anyhow::Result<Option<ResolvedPath>> {
-        Ok(None)
+        let Ok(label) = Label::parse(path) else {
+            return Ok(None);
+        };
+        if !label.repo().is_empty() {
+            return Ok(Some(ResolvedPath::UnfetchedRepository {
+                repo: label.repo().to_string(),
+            }));
+        }
+
+        let package = if label.is_relative() {
+            let from_path = self
+                .0
+                .iter()
+                .find(|entry| entry.file_id == from)
+                .map(|entry| entry.key().clone());
+            match from_path.as_deref().map(Label::parse) {
+                Some(Ok(from_label)) => from_label.package().to_string(),
+                _ => return Ok(None),
+            }
+        } else {
+            label.package().to_string()
+        };
+
+        let source_path = format!("//{}:{}", package, label.target());
+        Ok(Some(if self.0.contains_key(&source_path) {
+            ResolvedPath::Source {
+                path: PathBuf::from(source_path),
+            }
+        } else if let Some(build_file) = self.0.get(&format!("//{}:BUILD", package)) {
+            ResolvedPath::BuildTarget {
+                build_file: build_file.file_id,
+                target: label.target().to_string(),
+                contents: None,
+            }
+        } else {
+            ResolvedPath::MissingPackage
+        }))
     }
 
     fn load_file(
@@ -526,4 +713,18 @@ impl FileLoader for SimpleFileLoader {
     fn resolve_build_file(&self, _file_id: FileId) -> Option<String> {
         None
     }
+
+    /// Files are placed in the package named by their path, e.g. `//foo:defs.bzl` belongs to
+    /// `foo`, as long as the package's BUILD file, `//foo:BUILD`, exists.
+    fn resolve_package(&self, file_id: FileId) -> Option<FilePackage> {
+        let entry = self.0.iter().find(|entry| entry.file_id == file_id)?;
+        let label = Label::parse(entry.key()).ok()?;
+        if !self.0.contains_key(&format!("//{}:BUILD", label.package())) {
+            return None;
+        }
+        Some(FilePackage {
+            repo: label.repo().to_string(),
+            package: label.package().to_string(),
+        })
+    }
 }
@@ -0,0 +1,260 @@
+//! Lints are checks that run after lowering and type inference, on top of the syntax tree and
+//! the semantic model. Unlike the diagnostics reported by `starpls_hir`, each lint is self-contained:
+//! it declares its diagnostic code, default severity, and the kinds of files that it applies to,
+//! and may provide a fix for each problem it reports. Lints that enforce a particular style rather
+//! than catch likely mistakes are off by default, and are enabled with
+//! [`Analysis::set_enabled_lints`](crate::Analysis::set_enabled_lints).
+//!
+//! To add a lint, create a module for it under `lints/` and register it in [`LINTS`].
+
+use starpls_bazel::APIContext;
+use starpls_common::Db;
+use starpls_common::Diagnostic;
+use starpls_common::DiagnosticCode;
+use starpls_common::File;
+use starpls_common::FileId;
+use starpls_common::FileRange;
+use starpls_common::Severity;
+use starpls_hir::Semantics;
+use starpls_syntax::ast;
+use starpls_syntax::TextRange;
+
+use crate::Applicability;
+use crate::Database;
+use crate::Fix;
+use crate::TextEdit;
+
+mod duplicated_name;
+mod load_on_top;
+mod native_build;
+mod out_of_order_load;
+mod positional_args;
+mod unnormalized_label;
+mod unsorted_dict_items;
+
+pub(crate) use load_on_top::move_load_to_top;
+
+/// All registered lints, in the order in which they're run.
+static LINTS: &[Lint] = &[
+    native_build::LINT,
+    positional_args::LINT,
+    load_on_top::LINT,
+    out_of_order_load::LINT,
+    unsorted_dict_items::LINT,
+    duplicated_name::LINT,
+    unnormalized_label::LINT,
+];
+
+pub(crate) struct Lint {
+    pub(crate) code: DiagnosticCode,
+    pub(crate) default_severity: Severity,
+    /// Whether the lint runs without being enabled explicitly.
+    pub(crate) enabled_by_default: bool,
+    /// The kinds of Bazel files that this lint applies to. If empty, the lint applies to all
+    /// files, including plain Starlark files.
+    pub(crate) api_contexts: &'static [APIContext],
+    pub(crate) check: fn(&mut LintContext),
+}
+
+impl Lint {
+    fn applies_to(&self, db: &Database, api_context: Option<&APIContext>) -> bool {
+        (self.enabled_by_default || db.enabled_lints.contains(&self.code))
+            && (self.api_contexts.is_empty()
+                || api_context.is_some_and(|api_context| self.api_contexts.contains(api_context)))
+    }
+}
+
+/// The fix for a problem reported by a lint.
+pub(crate) struct LintFix {
+    pub(crate) label: String,
+    pub(crate) edits: Vec<TextEdit>,
+    pub(crate) applicability: Applicability,
+}
+
+/// A problem reported by a lint, along with its fix, if any.
+pub(crate) struct LintResult {
+    pub(crate) diagnostic: Diagnostic,
+    pub(crate) fix: Option<Fix>,
+}
+
+pub(crate) struct LintContext<'a> {
+    pub(crate) sema: &'a Semantics<'a>,
+    pub(crate) file: File,
+    pub(crate) module: ast::Module,
+    lint: &'static Lint,
+    results: &'a mut Vec<LintResult>,
+}
+
+impl LintContext<'_> {
+    pub(crate) fn file_id(&self) -> FileId {
+        self.file.id(self.sema.db)
+    }
+
+    /// Returns the 1-based line number of the given range's start.
+    pub(crate) fn line_number(&self, range: TextRange) -> u32 {
+        starpls_common::line_index(self.sema.db, self.file)
+            .line_col(range.start())
+            .line
+            + 1
+    }
+
+    pub(crate) fn report(&mut self, range: TextRange, message: impl Into<String>) {
+        self.report_with_fix(range, message, None);
+    }
+
+    pub(crate) fn report_with_fix(
+        &mut self,
+        range: TextRange,
+        message: impl Into<String>,
+        fix: Option<LintFix>,
+    ) {
+        let code = self.lint.code;
+        self.results.push(LintResult {
+            diagnostic: Diagnostic {
+                message: message.into(),
+                severity: self.lint.default_severity.clone(),
+                range: FileRange {
+                    file_id: self.file_id(),
+                    range,
+                },
+                tags: None,
+                code,
+                related_information: None,
+            },
+            fix: fix.map(|fix| Fix {
+                label: fix.label,
+                code,
+                range,
+                edits: fix.edits,
+                applicability: fix.applicability,
+            }),
+        });
+    }
+}
+
+/// Runs every enabled lint that applies to the given file.
+pub(crate) fn lints(db: &Database, file_id: FileId) -> Vec<LintResult> {
+    let Some(file) = db.get_file(file_id) else {
+        return Vec::new();
+    };
+
+    let sema = Semantics::new(db);
+    let api_context = file.api_context(db);
+    let module = sema.parse(file).tree(db);
+    let mut results = Vec::new();
+    for lint in LINTS
+        .iter()
+        .filter(|lint| lint.applies_to(db, api_context.as_ref()))
+    {
+        (lint.check)(&mut LintContext {
+            sema: &sema,
+            file,
+            module: module.clone(),
+            lint,
+            results: &mut results,
+        });
+    }
+    results
+}
+
+/// Returns the string value of the given expression if it's a string literal.
+pub(crate) fn string_value(expr: &ast::Expression) -> Option<String> {
+    match expr {
+        ast::Expression::Literal(expr) => match expr.kind() {
+            ast::LiteralKind::String(s) => s.value().map(String::from),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+    use std::ops::Range;
+
+    use expect_test::Expect;
+    use starpls_bazel::APIContext;
+    use starpls_common::DiagnosticCode;
+    use starpls_common::Severity;
+
+    use crate::fixes::apply_edits;
+    use crate::lints::Lint;
+    use crate::Analysis;
+
+    /// Runs all lints, including the ones that are off by default, on the given file, then
+    /// renders the reported problems followed by the file contents with all fixes applied.
+    pub(crate) fn check_lints(api_context: APIContext, contents: &str, expect: Expect) {
+        let (mut analysis, file_id) = Analysis::from_files_for_test(
+            Default::default(),
+            &[("BUILD.bazel", api_context, contents)],
+        );
+        analysis.set_enabled_lints(super::LINTS.iter().map(|lint| lint.code));
+
+        let results = super::lints(&analysis.db, file_id);
+        let mut actual = String::new();
+        let mut edits = Vec::new();
+        for result in results {
+            let range = Range::<usize>::from(result.diagnostic.range.range);
+            writeln!(
+                actual,
+                "{} {:?} {}: {}",
+                result.diagnostic.code,
+                &contents[range.clone()],
+                range.start,
+                result.diagnostic.message
+            )
+            .unwrap();
+            edits.extend(result.fix.into_iter().flat_map(|fix| fix.edits));
+        }
+
+        let contents = apply_edits(contents, edits);
+        writeln!(actual, "---\n{}", contents.trim()).unwrap();
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn test_style_lints_are_off_by_default() {
+        let contents = r#"
+load(":b.bzl", "b")
+load(":a.bzl", "a")
+
+x = {"b": a, "a": b}
+"#;
+        let (mut analysis, file_id) = Analysis::from_files_for_test(
+            Default::default(),
+            &[("BUILD.bazel", APIContext::Bzl, contents)],
+        );
+        let codes = |analysis: &Analysis| {
+            super::lints(&analysis.db, file_id)
+                .into_iter()
+                .map(|result| result.diagnostic.code)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(codes(&analysis), []);
+
+        analysis.set_enabled_lints([DiagnosticCode::UnsortedDictItems]);
+        assert_eq!(codes(&analysis), [DiagnosticCode::UnsortedDictItems]);
+    }
+
+    #[test]
+    fn test_disabled_lints_dont_apply_to_their_api_contexts() {
+        let lint = Lint {
+            code: DiagnosticCode::NativeBuild,
+            default_severity: Severity::Warning,
+            enabled_by_default: false,
+            api_contexts: &[APIContext::Build],
+            check: |_| {},
+        };
+        let (mut analysis, _) = Analysis::from_files_for_test(
+            Default::default(),
+            &[("BUILD.bazel", APIContext::Build, "")],
+        );
+        assert!(!lint.applies_to(&analysis.db, Some(&APIContext::Build)));
+
+        analysis.set_enabled_lints([DiagnosticCode::NativeBuild]);
+        assert!(lint.applies_to(&analysis.db, Some(&APIContext::Build)));
+        assert!(!lint.applies_to(&analysis.db, Some(&APIContext::Bzl)));
+        assert!(!lint.applies_to(&analysis.db, None));
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use starpls_bazel::APIContext;
+use starpls_common::DiagnosticCode;
+use starpls_common::Severity;
+use starpls_syntax::ast::AstNode;
+use starpls_syntax::ast::{self};
+
+use crate::lints::string_value;
+use crate::lints::Lint;
+use crate::lints::LintContext;
+
+/// Reports rule and macro calls in BUILD files that reuse the name of an earlier target. Bazel
+/// fails to load packages with duplicate target names, so this is reported as an error.
+pub(super) const LINT: Lint = Lint {
+    code: DiagnosticCode::DuplicatedName,
+    default_severity: Severity::Error,
+    enabled_by_default: true,
+    api_contexts: &[APIContext::Build],
+    check,
+};
+
+fn check(cx: &mut LintContext) {
+    let mut names = HashMap::new();
+    let stmts = cx.module.statements().collect::<Vec<_>>();
+    for stmt in stmts {
+        let ast::Statement::Expr(ast::Expression::Call(call_expr)) = stmt else {
+            continue;
+        };
+        let Some(name_expr) = call_expr
+            .arguments()
+            .into_iter()
+            .flat_map(|args| args.arguments())
+            .find_map(|arg| match arg {
+                ast::Argument::Keyword(arg)
+                    if arg
+                        .name()
+                        .and_then(|name| name.name())
+                        .map(|name| name.text() == "name")
+                        .unwrap_or(false) =>
+                {
+                    arg.expr()
+                }
+                _ => None,
+            })
+        else {
+            continue;
+        };
+        let Some(name) = string_value(&name_expr) else {
+            continue;
+        };
+
+        let range = name_expr.syntax().text_range();
+        match names.get(&name) {
+            Some(line) => cx.report(
+                range,
+                format!(
+                    "A target named \"{}\" was already defined on line {}",
+                    name, line
+                ),
+            ),
+            None => {
+                let line = cx.line_number(range);
+                names.insert(name, line);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use starpls_bazel::APIContext;
+
+    use crate::lints::tests::check_lints;
+
+    #[test]
+    fn test_duplicated_name() {
+        check_lints(
+            APIContext::Build,
+            r#"
+cc_library(name = "foo")
+
+cc_binary(
+    name = "foo",
+)
+
+cc_test(name = "bar")
+"#,
+            expect![[r#"
+                duplicated-name "\"foo\"" 49: A target named "foo" was already defined on line 2
+                ---
+                cc_library(name = "foo")
+
+                cc_binary(
+                    name = "foo",
+                )
+
+                cc_test(name = "bar")
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use starpls_bazel::APIContext;
+use starpls_common::DiagnosticCode;
+use starpls_common::Severity;
+use starpls_syntax::ast::AstNode;
+use starpls_syntax::ast::{self};
+use starpls_syntax::TextRange;
+use starpls_syntax::TextSize;
+use starpls_syntax::T;
+
+use crate::lints::Lint;
+use crate::lints::LintContext;
+use crate::lints::LintFix;
+use crate::util::statement_range_with_line_end;
+use crate::Applicability;
+use crate::TextEdit;
+
+/// Reports `load` statements that come after other statements. Only the module docstring may
+/// precede them. Doesn't apply to BUILD files, where this is an error.
+pub(super) const LINT: Lint = Lint {
+    code: DiagnosticCode::LoadOnTop,
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    api_contexts: &[],
+    check,
+};
+
+fn check(cx: &mut LintContext) {
+    // In BUILD files, `load` statements that come after other statements are errors, which are
+    // reported during lowering instead.
+    if cx.file.api_context(cx.sema.db) == Some(APIContext::Build) {
+        return;
+    }
+
+    for (load_stmt, fix) in misplaced_loads(&cx.module) {
+        cx.report_with_fix(
+            load_stmt.syntax().text_range(),
+            "Load statements should be at the top of the file",
+            fix,
+        );
+    }
+}
+
+/// Returns the fix for the misplaced `load` statement with the given range, if any.
+pub(crate) fn move_load_to_top(module: &ast::Module, range: TextRange) -> Option<LintFix> {
+    misplaced_loads(module)
+        .into_iter()
+        .find(|(load_stmt, _)| load_stmt.syntax().text_range() == range)
+        .and_then(|(_, fix)| fix)
+}
+
+/// Returns the `load` statements that come after other statements, along with fixes that move
+/// them to the top of the file.
+fn misplaced_loads(module: &ast::Module) -> Vec<(ast::LoadStmt, Option<LintFix>)> {
+    // Misplaced `load` statements are moved to just after the last `load` statement at the top
+    // of the file, or to just before the first statement that isn't a `load` statement.
+    let mut insert_at: Option<(TextSize, bool)> = None;
+    let mut misplaced = Vec::new();
+    let mut seen_other_stmt = false;
+    for (index, stmt) in module.statements().enumerate() {
+        match stmt {
+            ast::Statement::Load(load_stmt) if seen_other_stmt => misplaced.push(load_stmt),
+            ast::Statement::Load(load_stmt) => {
+                let range = statement_range_with_line_end(load_stmt.syntax());
+                let ends_line = range.end() != load_stmt.syntax().text_range().end();
+                insert_at = Some((range.end(), ends_line));
+            }
+            ast::Statement::Expr(ast::Expression::Literal(expr))
+                if index == 0 && matches!(expr.kind(), ast::LiteralKind::String(_)) => {}
+            stmt => {
+                if !seen_other_stmt && insert_at.is_none() {
+                    insert_at = Some((stmt.syntax().text_range().start(), true));
+                }
+                seen_other_stmt = true;
+            }
+        }
+    }
+
+    misplaced
+        .into_iter()
+        .map(|load_stmt| {
+            let fix = insert_at
+                .filter(|_| starts_line(&load_stmt))
+                .map(|(offset, ends_line)| {
+                    let text = load_stmt.syntax().text().to_string();
+                    LintFix {
+                        label: "Move the load statement to the top of the file".to_string(),
+                        edits: vec![
+                            TextEdit {
+                                range: TextRange::empty(offset),
+                                new_text: if ends_line {
+                                    format!("{}\n", text)
+                                } else {
+                                    format!("\n{}", text)
+                                },
+                            },
+                            TextEdit {
+                                range: statement_range_with_line_end(load_stmt.syntax()),
+                                new_text: String::new(),
+                            },
+                        ],
+                        applicability: Applicability::Safe,
+                    }
+                });
+            (load_stmt, fix)
+        })
+        .collect()
+}
+
+fn starts_line(load_stmt: &ast::LoadStmt) -> bool {
+    load_stmt
+        .syntax()
+        .prev_sibling_or_token()
+        .map(|el| el.kind() == T!['\n'])
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use starpls_bazel::APIContext;
+
+    use crate::lints::tests::check_lints;
+
+    #[test]
+    fn test_load_on_top() {
+        check_lints(
+            APIContext::Bzl,
+            r#"
+"""Docstring."""
+
+load(":a.bzl", "a")
+
+a()
+
+load(":b.bzl", "b")
+
+b()
+"#,
+            expect![[r#"
+                load-on-top "load(\":b.bzl\", \"b\")" 45: Load statements should be at the top of the file
+                ---
+                """Docstring."""
+
+                load(":a.bzl", "a")
+                load(":b.bzl", "b")
+
+                a()
+
+
+                b()
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_load_on_top_without_leading_loads() {
+        check_lints(
+            APIContext::Bzl,
+            r#"
+x = 1
+load(":a.bzl", "a")
+"#,
+            expect![[r#"
+                load-on-top "load(\":a.bzl\", \"a\")" 7: Load statements should be at the top of the file
+                ---
+                load(":a.bzl", "a")
+                x = 1
+            "#]],
+        );
+    }
+}
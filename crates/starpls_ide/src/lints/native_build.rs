@@ -0,0 +1,106 @@
+use starpls_bazel::APIContext;
+use starpls_common::DiagnosticCode;
+use starpls_common::Severity;
+use starpls_syntax::ast::AstNode;
+use starpls_syntax::ast::{self};
+use starpls_syntax::TextRange;
+
+use crate::lints::Lint;
+use crate::lints::LintContext;
+use crate::lints::LintFix;
+use crate::Applicability;
+use crate::TextEdit;
+
+/// Reports uses of the `native` module in BUILD files, where its members are available as
+/// globals.
+pub(super) const LINT: Lint = Lint {
+    code: DiagnosticCode::NativeBuild,
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    api_contexts: &[APIContext::Build],
+    check,
+};
+
+fn check(cx: &mut LintContext) {
+    let dot_exprs = cx
+        .module
+        .syntax()
+        .descendants()
+        .filter_map(ast::DotExpr::cast)
+        .collect::<Vec<_>>();
+    for dot_expr in dot_exprs {
+        let is_native = match dot_expr.expr() {
+            Some(ast::Expression::Name(name_ref)) => name_ref
+                .name()
+                .map(|name| name.text() == "native")
+                .unwrap_or(false),
+            _ => false,
+        };
+        let Some(field) = dot_expr.field().filter(|_| is_native) else {
+            continue;
+        };
+
+        let range = TextRange::new(
+            dot_expr.syntax().text_range().start(),
+            field.syntax().text_range().start(),
+        );
+        cx.report_with_fix(
+            range,
+            "The \"native\" module shouldn't be used in BUILD files, its members are available as global symbols",
+            Some(LintFix {
+                label: "Remove the \"native.\" prefix".to_string(),
+                edits: vec![TextEdit {
+                    range,
+                    new_text: String::new(),
+                }],
+                applicability: Applicability::Safe,
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use starpls_bazel::APIContext;
+
+    use crate::lints::tests::check_lints;
+
+    #[test]
+    fn test_native_build() {
+        check_lints(
+            APIContext::Build,
+            r#"
+native.cc_library(
+    name = "foo",
+    srcs = native.glob(["*.cc"]),
+)
+"#,
+            expect![[r#"
+                native-build "native." 1: The "native" module shouldn't be used in BUILD files, its members are available as global symbols
+                native-build "native." 49: The "native" module shouldn't be used in BUILD files, its members are available as global symbols
+                ---
+                cc_library(
+                    name = "foo",
+                    srcs = glob(["*.cc"]),
+                )
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_native_bzl() {
+        check_lints(
+            APIContext::Bzl,
+            r#"
+def foo():
+    native.cc_library(name = "foo")
+"#,
+            expect![[r#"
+                ---
+                def foo():
+                    native.cc_library(name = "foo")
+            "#]],
+        );
+    }
+}
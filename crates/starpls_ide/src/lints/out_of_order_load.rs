@@ -0,0 +1,157 @@
+use starpls_common::DiagnosticCode;
+use starpls_common::Severity;
+use starpls_syntax::ast::AstNode;
+use starpls_syntax::ast::AstToken;
+use starpls_syntax::ast::{self};
+use starpls_syntax::SyntaxKind::*;
+use starpls_syntax::T;
+
+use crate::lints::Lint;
+use crate::lints::LintContext;
+use crate::lints::LintFix;
+use crate::Applicability;
+use crate::TextEdit;
+
+/// Reports `load` statements that aren't sorted by the labels of the modules they load.
+pub(super) const LINT: Lint = Lint {
+    code: DiagnosticCode::OutOfOrderLoad,
+    default_severity: Severity::Warning,
+    enabled_by_default: false,
+    api_contexts: &[],
+    check,
+};
+
+fn check(cx: &mut LintContext) {
+    // Group the top-level `load` statements into runs that are separated only by whitespace. Each
+    // run can be sorted without having to move any comments around.
+    let mut runs: Vec<Vec<ast::LoadStmt>> = vec![Vec::new()];
+    for element in cx.module.syntax().children_with_tokens() {
+        match element.as_node().cloned().map(ast::LoadStmt::cast) {
+            Some(Some(load_stmt)) => runs.last_mut().unwrap().push(load_stmt),
+            Some(None) => runs.push(Vec::new()),
+            None if matches!(element.kind(), WHITESPACE | T!['\n']) => {}
+            None => runs.push(Vec::new()),
+        }
+    }
+
+    let mut prev_key = None;
+    for run in runs {
+        let mut fix = sort_loads_fix(&run);
+        for load_stmt in run {
+            let Some(module) = load_stmt.module() else {
+                continue;
+            };
+            let Some(key) = load_sort_key(&load_stmt) else {
+                continue;
+            };
+            if prev_key.as_ref().is_some_and(|prev_key| &key < prev_key) {
+                cx.report_with_fix(
+                    module.syntax().text_range(),
+                    "Load statement is out of order, load statements should be sorted by their labels",
+                    fix.take(),
+                );
+            }
+            prev_key = Some(key);
+        }
+    }
+}
+
+/// Returns a fix that sorts the given run of `load` statements, or `None` if they're already
+/// sorted.
+fn sort_loads_fix(load_stmts: &[ast::LoadStmt]) -> Option<LintFix> {
+    let mut sorted = load_stmts
+        .iter()
+        .map(|load_stmt| Some((load_sort_key(load_stmt)?, load_stmt)))
+        .collect::<Option<Vec<_>>>()?;
+    sorted.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let edits = load_stmts
+        .iter()
+        .zip(sorted)
+        .filter(|(load_stmt, (_, sorted_load_stmt))| load_stmt != sorted_load_stmt)
+        .map(|(load_stmt, (_, sorted_load_stmt))| TextEdit {
+            range: load_stmt.syntax().text_range(),
+            new_text: sorted_load_stmt.syntax().text().to_string(),
+        })
+        .collect::<Vec<_>>();
+    (!edits.is_empty()).then(|| LintFix {
+        label: "Sort load statements".to_string(),
+        edits,
+        applicability: Applicability::Safe,
+    })
+}
+
+/// Returns the key that `load` statements are sorted by. This matches buildifier: modules from
+/// explicitly named repositories come first, followed by modules in other packages of the current
+/// repository, and finally modules in the current package. Ties are broken by file name.
+fn load_sort_key(load_stmt: &ast::LoadStmt) -> Option<(bool, bool, String, String)> {
+    let label = ast::String::cast(load_stmt.module()?.name()?)?.value()?;
+    let (package, name) = label.split_once(':').unwrap_or(("", &*label));
+    Some((
+        !label.starts_with('@'),
+        package.is_empty(),
+        package.to_string(),
+        name.to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use starpls_bazel::APIContext;
+
+    use crate::lints::tests::check_lints;
+
+    #[test]
+    fn test_out_of_order_load() {
+        check_lints(
+            APIContext::Bzl,
+            r#"
+load(":local.bzl", "c")
+load("//foo:bar.bzl", "b")
+load("@rules_cc//cc:defs.bzl", "a")
+
+a()
+b()
+c()
+"#,
+            expect![[r#"
+                out-of-order-load "\"//foo:bar.bzl\"" 30: Load statement is out of order, load statements should be sorted by their labels
+                out-of-order-load "\"@rules_cc//cc:defs.bzl\"" 57: Load statement is out of order, load statements should be sorted by their labels
+                ---
+                load("@rules_cc//cc:defs.bzl", "a")
+                load("//foo:bar.bzl", "b")
+                load(":local.bzl", "c")
+
+                a()
+                b()
+                c()
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_out_of_order_load_with_comments() {
+        check_lints(
+            APIContext::Bzl,
+            r#"
+load("//foo:b.bzl", "b")
+# A comment.
+load("//foo:a.bzl", "a")
+
+a()
+b()
+"#,
+            expect![[r#"
+                out-of-order-load "\"//foo:a.bzl\"" 44: Load statement is out of order, load statements should be sorted by their labels
+                ---
+                load("//foo:b.bzl", "b")
+                # A comment.
+                load("//foo:a.bzl", "a")
+
+                a()
+                b()
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use starpls_bazel::APIContext;
+use starpls_common::DiagnosticCode;
+use starpls_common::Severity;
+use starpls_syntax::ast::AstNode;
+use starpls_syntax::ast::{self};
+
+use crate::lints::Lint;
+use crate::lints::LintContext;
+
+/// Reports positional arguments in top-level calls in BUILD files. Rules and macros should
+/// always be called with keyword arguments, except for a few builtins.
+pub(super) const LINT: Lint = Lint {
+    code: DiagnosticCode::PositionalArgs,
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    api_contexts: &[APIContext::Build],
+    check,
+};
+
+/// Functions that are commonly called with positional arguments in BUILD files.
+const FUNCTIONS_WITH_POSITIONAL_ARGUMENTS: &[&str] =
+    &["distribs", "exports_files", "licenses", "print"];
+
+fn check(cx: &mut LintContext) {
+    let call_exprs = cx
+        .module
+        .statements()
+        .filter_map(|stmt| match stmt {
+            ast::Statement::Expr(ast::Expression::Call(call_expr)) => Some(call_expr),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    for call_expr in call_exprs {
+        match call_expr.callee() {
+            Some(ast::Expression::Name(name_ref))
+                if name_ref
+                    .name()
+                    .map(|name| !FUNCTIONS_WITH_POSITIONAL_ARGUMENTS.contains(&name.text()))
+                    .unwrap_or(false) => {}
+            _ => continue,
+        }
+
+        for arg in call_expr
+            .arguments()
+            .into_iter()
+            .flat_map(|args| args.arguments())
+        {
+            if let ast::Argument::Simple(arg) = arg {
+                cx.report(
+                    arg.syntax().text_range(),
+                    "All top-level calls in BUILD files, except for some builtins, should use keyword arguments",
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use starpls_bazel::APIContext;
+
+    use crate::lints::tests::check_lints;
+
+    #[test]
+    fn test_positional_args() {
+        check_lints(
+            APIContext::Build,
+            r#"
+exports_files(["foo.txt"])
+
+my_macro("foo", srcs = ["foo.cc"])
+"#,
+            expect![[r#"
+                positional-args "\"foo\"" 38: All top-level calls in BUILD files, except for some builtins, should use keyword arguments
+                ---
+                exports_files(["foo.txt"])
+
+                my_macro("foo", srcs = ["foo.cc"])
+            "#]],
+        );
+    }
+}
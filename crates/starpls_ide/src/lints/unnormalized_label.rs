@@ -0,0 +1,157 @@
+use starpls_bazel::APIContext;
+use starpls_bazel::Label;
+use starpls_common::DiagnosticCode;
+use starpls_common::Severity;
+use starpls_syntax::ast::AstNode;
+use starpls_syntax::ast::{self};
+use starpls_syntax::TextRange;
+use starpls_syntax::TextSize;
+
+use crate::lints::string_value;
+use crate::lints::Lint;
+use crate::lints::LintContext;
+use crate::lints::LintFix;
+use crate::Applicability;
+use crate::TextEdit;
+
+/// Reports labels in the arguments of top-level calls in BUILD files that spell out a target
+/// with the same name as its package, e.g. `//foo/bar:bar`, which can be shortened to `//foo/bar`.
+pub(super) const LINT: Lint = Lint {
+    code: DiagnosticCode::UnnormalizedLabel,
+    default_severity: Severity::Warning,
+    enabled_by_default: true,
+    api_contexts: &[APIContext::Build],
+    check,
+};
+
+fn check(cx: &mut LintContext) {
+    let string_exprs = cx
+        .module
+        .statements()
+        .filter_map(|stmt| match stmt {
+            ast::Statement::Expr(ast::Expression::Call(call_expr)) => Some(call_expr),
+            _ => None,
+        })
+        .flat_map(|call_expr| call_expr.arguments())
+        .flat_map(|args| args.syntax().descendants().collect::<Vec<_>>())
+        .filter_map(ast::Expression::cast)
+        .filter(|expr| matches!(expr, ast::Expression::Literal(_)))
+        .collect::<Vec<_>>();
+    for expr in string_exprs {
+        let Some(value) = string_value(&expr) else {
+            continue;
+        };
+        let Some(normalized) = normalize_label(&value) else {
+            continue;
+        };
+
+        // Only literals that spell out the label verbatim can be fixed by removing the redundant
+        // suffix, which rules out strings that contain escape sequences.
+        let range = expr.syntax().text_range();
+        let text = expr.syntax().text().to_string();
+        let fix = text.find(value.as_str()).map(|offset| {
+            let start = range.start() + TextSize::from((offset + normalized.len()) as u32);
+            let end = range.start() + TextSize::from((offset + value.len()) as u32);
+            LintFix {
+                label: format!("Replace with \"{}\"", normalized),
+                edits: vec![TextEdit {
+                    range: TextRange::new(start, end),
+                    new_text: String::new(),
+                }],
+                applicability: Applicability::Safe,
+            }
+        });
+        cx.report_with_fix(
+            range,
+            format!("Label \"{}\" can be shortened to \"{}\"", value, normalized),
+            fix,
+        );
+    }
+}
+
+/// Returns the shortest form of the given label, if it's shorter than the label itself.
+fn normalize_label(value: &str) -> Option<String> {
+    let label = Label::parse(value).ok()?;
+    if label.is_relative() || !label.has_leading_slashes() || !value.contains(':') {
+        return None;
+    }
+
+    let target = label.target();
+    let redundant_suffix = if label.package().is_empty() {
+        // `@repo//:repo` is the same as `@repo`.
+        if label.repo().is_empty() || label.repo() != target {
+            return None;
+        }
+        target.len() + "//:".len()
+    } else {
+        // `//foo/bar:bar` is the same as `//foo/bar`.
+        if label.package().rsplit('/').next() != Some(target) {
+            return None;
+        }
+        target.len() + ":".len()
+    };
+    Some(value[..value.len() - redundant_suffix].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use starpls_bazel::APIContext;
+
+    use crate::lints::tests::check_lints;
+
+    #[test]
+    fn test_unnormalized_label() {
+        check_lints(
+            APIContext::Build,
+            r#"
+cc_library(
+    name = "foo",
+    deps = [
+        "//foo/bar:bar",
+        "@repo//baz:baz",
+        "@repo//:repo",
+        "//foo/bar",
+        "//foo/bar:baz",
+        ":bar",
+        "@other//:repo",
+    ],
+    visibility = ["//foo:__pkg__"],
+)
+"#,
+            expect![[r#"
+                unnormalized-label "\"//foo/bar:bar\"" 52: Label "//foo/bar:bar" can be shortened to "//foo/bar"
+                unnormalized-label "\"@repo//baz:baz\"" 77: Label "@repo//baz:baz" can be shortened to "@repo//baz"
+                unnormalized-label "\"@repo//:repo\"" 103: Label "@repo//:repo" can be shortened to "@repo"
+                ---
+                cc_library(
+                    name = "foo",
+                    deps = [
+                        "//foo/bar",
+                        "@repo//baz",
+                        "@repo",
+                        "//foo/bar",
+                        "//foo/bar:baz",
+                        ":bar",
+                        "@other//:repo",
+                    ],
+                    visibility = ["//foo:__pkg__"],
+                )
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_unnormalized_label_bzl() {
+        check_lints(
+            APIContext::Bzl,
+            r#"
+cc_library(name = "foo", deps = ["//foo:foo"])
+"#,
+            expect![[r#"
+                ---
+                cc_library(name = "foo", deps = ["//foo:foo"])
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use starpls_common::DiagnosticCode;
+use starpls_common::Severity;
+use starpls_syntax::ast::AstNode;
+use starpls_syntax::ast::{self};
+use starpls_syntax::SyntaxKind::*;
+use starpls_syntax::SyntaxNode;
+use starpls_syntax::T;
+
+use crate::lints::string_value;
+use crate::lints::Lint;
+use crate::lints::LintContext;
+use crate::lints::LintFix;
+use crate::Applicability;
+use crate::TextEdit;
+
+/// Reports dictionary literals with string keys that aren't sorted. Dictionaries in statements
+/// that are preceded by or contain a `# @unsorted-dict-items` comment are skipped.
+pub(super) const LINT: Lint = Lint {
+    code: DiagnosticCode::UnsortedDictItems,
+    default_severity: Severity::Warning,
+    enabled_by_default: false,
+    api_contexts: &[],
+    check,
+};
+
+const SUPPRESSION_COMMENT: &str = "@unsorted-dict-items";
+
+fn check(cx: &mut LintContext) {
+    let dict_exprs = cx
+        .module
+        .syntax()
+        .descendants()
+        .filter_map(ast::DictExpr::cast)
+        .filter(|dict_expr| !is_suppressed(dict_expr.syntax()))
+        .collect::<Vec<_>>();
+    for dict_expr in dict_exprs {
+        // Only consider dictionaries whose keys are all string literals.
+        let Some(entries) = dict_expr
+            .entries()
+            .map(|entry| Some((string_value(&entry.key()?)?, entry)))
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+        let Some(unsorted_entry) = entries
+            .windows(2)
+            .find(|window| dict_key_sort_key(&window[1].0) < dict_key_sort_key(&window[0].0))
+            .map(|window| &window[1].1)
+        else {
+            continue;
+        };
+
+        // Moving comments around is tricky, so only provide a fix if there aren't any.
+        let has_comments = dict_expr
+            .syntax()
+            .descendants_with_tokens()
+            .any(|el| el.kind() == COMMENT);
+        let fix = (!has_comments).then(|| {
+            let mut sorted = entries.clone();
+            sorted.sort_by(|(a, _), (b, _)| dict_key_sort_key(a).cmp(&dict_key_sort_key(b)));
+            LintFix {
+                label: "Sort dictionary items".to_string(),
+                edits: entries
+                    .iter()
+                    .zip(sorted)
+                    .filter(|((_, entry), (_, sorted_entry))| entry != sorted_entry)
+                    .map(|((_, entry), (_, sorted_entry))| TextEdit {
+                        range: entry.syntax().text_range(),
+                        new_text: sorted_entry.syntax().text().to_string(),
+                    })
+                    .collect(),
+                // Sorting changes the iteration order of the dictionary.
+                applicability: Applicability::Unsafe,
+            }
+        });
+
+        let range = unsorted_entry
+            .key()
+            .map(|key| key.syntax().text_range())
+            .unwrap_or_else(|| unsorted_entry.syntax().text_range());
+        cx.report_with_fix(
+            range,
+            "Dictionary items are out of their lexicographical order",
+            fix,
+        );
+    }
+}
+
+/// Orders keys lexicographically, except that private keys (starting with `_`) come after other
+/// keys, and `"//conditions:default"` comes after all other public keys.
+fn dict_key_sort_key(key: &str) -> (bool, bool, &str) {
+    (key.starts_with('_'), key == "//conditions:default", key)
+}
+
+/// Returns whether the top-level statement containing the given node contains or is directly
+/// preceded by a suppression comment.
+fn is_suppressed(node: &SyntaxNode) -> bool {
+    let Some(stmt) = node
+        .ancestors()
+        .find(|node| node.parent().map(|parent| parent.kind()) == Some(MODULE))
+    else {
+        return false;
+    };
+
+    let is_suppression_comment =
+        |text: &str| text.starts_with('#') && text.contains(SUPPRESSION_COMMENT);
+    if stmt
+        .descendants_with_tokens()
+        .filter_map(|el| el.into_token())
+        .any(|token| token.kind() == COMMENT && is_suppression_comment(token.text()))
+    {
+        return true;
+    }
+
+    let mut prev = stmt.prev_sibling_or_token();
+    while let Some(token) = prev.and_then(|el| el.into_token()) {
+        match token.kind() {
+            COMMENT if is_suppression_comment(token.text()) => return true,
+            COMMENT | WHITESPACE | T!['\n'] => prev = token.prev_sibling_or_token(),
+            _ => break,
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use starpls_bazel::APIContext;
+
+    use crate::lints::tests::check_lints;
+
+    #[test]
+    fn test_unsorted_dict_items() {
+        check_lints(
+            APIContext::Bzl,
+            r#"
+x = {
+    "b": 1,
+    "_c": 2,
+    "//conditions:default": 3,
+    "a": 4,
+}
+"#,
+            expect![[r#"
+                unsorted-dict-items "\"//conditions:default\"" 36: Dictionary items are out of their lexicographical order
+                ---
+                x = {
+                    "a": 4,
+                    "b": 1,
+                    "//conditions:default": 3,
+                    "_c": 2,
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_unsorted_dict_items_skipped() {
+        check_lints(
+            APIContext::Bzl,
+            r#"
+x = {"b": 1, y: 2, "a": 3}
+
+# @unsorted-dict-items
+z = {"b": 1, "a": 2}
+"#,
+            expect![[r#"
+                ---
+                x = {"b": 1, y: 2, "a": 3}
+
+                # @unsorted-dict-items
+                z = {"b": 1, "a": 2}
+            "#]],
+        );
+    }
+}
@@ -163,3 +163,42 @@ pub(crate) fn signature_help(
         }],
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use expect_test::Expect;
+
+    use crate::Analysis;
+    use crate::FilePosition;
+
+    fn check_signature_help(fixture: &str, expect: Expect) {
+        let (analysis, fixture) = Analysis::from_single_file_fixture(fixture);
+        let help = analysis
+            .snapshot()
+            .signature_help(
+                fixture
+                    .cursor_pos
+                    .map(|(file_id, pos)| FilePosition { file_id, pos })
+                    .unwrap(),
+            )
+            .unwrap()
+            .unwrap();
+
+        expect.assert_eq(&help.signatures[0].label);
+    }
+
+    #[test]
+    fn test_type_vars_in_return_type() {
+        check_signature_help(
+            r#"
+def first(xs):
+    # type: (list[T]) -> T
+    return xs[0]
+
+first(["a", $0"b"])
+"#,
+            expect![[r#"def first(xs: list[Unknown]) -> string"#]],
+        );
+    }
+}
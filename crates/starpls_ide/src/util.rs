@@ -1,6 +1,10 @@
 use starpls_syntax::SyntaxKind;
+use starpls_syntax::SyntaxKind::*;
+use starpls_syntax::SyntaxNode;
 use starpls_syntax::SyntaxToken;
+use starpls_syntax::TextRange;
 use starpls_syntax::TokenAtOffset;
+use starpls_syntax::T;
 
 pub(crate) fn pick_best_token(
     tokens: TokenAtOffset<SyntaxToken>,
@@ -9,6 +13,24 @@ pub(crate) fn pick_best_token(
     tokens.max_by_key(|token| f(token.kind()))
 }
 
+/// Returns the range of the given statement, extended to the end of its line if there's nothing
+/// else on it. Removing this range removes the statement without leaving behind an empty line.
+pub(crate) fn statement_range_with_line_end(stmt: &SyntaxNode) -> TextRange {
+    let mut end = stmt.text_range().end();
+    let mut next = stmt.next_sibling_or_token();
+    while let Some(token) = next.and_then(|el| el.into_token()) {
+        match token.kind() {
+            WHITESPACE => next = token.next_sibling_or_token(),
+            T!['\n'] => {
+                end = token.text_range().end();
+                break;
+            }
+            _ => break,
+        }
+    }
+    TextRange::new(stmt.text_range().start(), end)
+}
+
 // TODO(withered-magic): This logic should probably be more sophisticated, but it works well
 // enough for now.
 pub(crate) fn unindent_doc(doc: &str) -> String {
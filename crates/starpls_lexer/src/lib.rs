@@ -457,7 +457,7 @@ impl Cursor<'_> {
             // One-character operators and their corresponding augmented assignments.
             '+' => augmented_assign!(PlusEq, Plus),
             '-' => match self.first() {
-                '>' if self.type_comment_tokens => {
+                '>' => {
                     self.bump();
                     Arrow
                 }
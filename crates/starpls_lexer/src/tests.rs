@@ -370,7 +370,7 @@ fn test_octal_numbers() {
 #[test]
 fn test_punctuation() {
     check_lexing(
-        r#"+ - * / // % ** ~ & | ^ << >> . , = ; : ( ) [ ] { } < > >= <= == != += -= *= /= //= %= &= |= ^= <<= >>="#,
+        r#"+ - * / // % ** ~ & | ^ << >> . , = ; : ( ) [ ] { } < > >= <= == != += -= *= /= //= %= &= |= ^= <<= >>= ->"#,
         expect![[r#"
             Token { kind: Plus, len: 1 }
             Token { kind: Whitespace, len: 1 }
@@ -453,6 +453,8 @@ fn test_punctuation() {
             Token { kind: LtLtEq, len: 3 }
             Token { kind: Whitespace, len: 1 }
             Token { kind: GtGtEq, len: 3 }
+            Token { kind: Whitespace, len: 1 }
+            Token { kind: Arrow, len: 2 }
         "#]],
     );
 }
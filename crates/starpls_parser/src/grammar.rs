@@ -31,9 +31,9 @@ pub(crate) fn type_comment_body(p: &mut Parser) {
             m.complete(p, IGNORE_TYPE);
         }
         T!['('] => {
-            function_type(p);
+            function_type(p, EMPTY);
         }
-        _ => union_type(p),
+        _ => union_type(p, EMPTY),
     }
 
     // We only parse one type, so if there's any remaining tokens, add them
@@ -250,7 +250,7 @@ fn lambda_expr(p: &mut Parser) -> CompletedMarker {
     p.bump(T![lambda]);
     if p.at_kinds(PARAMETER_START) {
         let m = p.start();
-        parameters(p);
+        parameters(p, false);
         m.complete(p, PARAMETERS);
     }
     if !p.eat(T![:]) {
@@ -4,35 +4,45 @@ use crate::syntax_kind::SyntaxKindSet;
 pub(crate) const PARAMETER_START: SyntaxKindSet = SyntaxKindSet::new(&[T![ident], T![*], T![**]]);
 
 /// Grammar: `Parameters = Parameter {',' Parameter}.`
-pub(crate) fn parameters(p: &mut Parser) {
+///
+/// Type annotations are only allowed for the parameters of function definitions, since they
+/// would be ambiguous in lambda expressions.
+pub(crate) fn parameters(p: &mut Parser, allow_annotations: bool) {
     // let m = p.start();
-    parameter(p);
+    parameter(p, allow_annotations);
     while p.at(T![,]) && PARAMETER_START.contains(p.nth(1)) {
         p.bump(T![,]);
-        parameter(p);
+        parameter(p, allow_annotations);
     }
     // m.complete(p, PARAMETERS);
     p.eat(T![,]);
 }
 
-/// Grammar: `Parameter  = identifier | identifier '=' Test | '*' | '*' identifier | '**' identifier`
-pub(crate) fn parameter(p: &mut Parser) {
+/// Grammar: `Parameter  = identifier [TypeAnnotation] | identifier [TypeAnnotation] '=' Test | '*' | '*' identifier [TypeAnnotation] | '**' identifier [TypeAnnotation]`
+pub(crate) fn parameter(p: &mut Parser, allow_annotations: bool) {
     let m = p.start();
     match p.current() {
         T![*] => {
             p.bump(T![*]);
-            name(p);
+            if name(p).is_some() && allow_annotations {
+                type_annotation(p);
+            }
             m.complete(p, ARGS_LIST_PARAMETER);
         }
         T![**] => {
             p.bump(T![**]);
             if name(p).is_none() {
                 p.error("Expected identifier")
+            } else if allow_annotations {
+                type_annotation(p);
             }
             m.complete(p, KWARGS_DICT_PARAMETER);
         }
         T![ident] => {
             assert!(name(p).is_some());
+            if allow_annotations {
+                type_annotation(p);
+            }
             if p.eat(T![=]) {
                 if p.at_kinds(EXPR_START) {
                     test(p);
@@ -45,3 +55,13 @@ pub(crate) fn parameter(p: &mut Parser) {
         _ => unreachable!(),
     }
 }
+
+/// Grammar: `TypeAnnotation = ':' Type .`
+fn type_annotation(p: &mut Parser) {
+    if p.at(T![:]) {
+        let m = p.start();
+        p.bump(T![:]);
+        annotation_type(p);
+        m.complete(p, TYPE_ANNOTATION);
+    }
+}
@@ -39,7 +39,7 @@ pub(crate) fn statement(p: &mut Parser) {
 
 /// Parses a function definition.
 ///
-/// Grammar: `DefStmt = 'def' identifier '(' [Parameters [',']] ')' ':' Suite .`
+/// Grammar: `DefStmt = 'def' identifier '(' [Parameters [',']] ')' ['->' Type] ':' Suite .`
 pub(crate) fn def_stmt(p: &mut Parser) {
     let m = p.start();
     p.bump(T![def]);
@@ -57,7 +57,7 @@ pub(crate) fn def_stmt(p: &mut Parser) {
         let param_marker = p.start();
         p.bump(T!['(']);
         if p.at_kinds(PARAMETER_START) {
-            parameters(p);
+            parameters(p, true);
         }
 
         if !p.eat(T![')']) {
@@ -67,6 +67,16 @@ pub(crate) fn def_stmt(p: &mut Parser) {
             return;
         }
         param_marker.complete(p, PARAMETERS);
+
+        // Parse the return type annotation, if present.
+        // test test_def_stmt_annotations
+        // def f(x: int, *args: str, y: list[str] = [], **kwargs) -> int | None: pass
+        if p.at(ARROW) {
+            let m = p.start();
+            p.bump(ARROW);
+            annotation_type(p);
+            m.complete(p, RETURN_TYPE);
+        }
     } else {
         if p.current() != T![:] {
             p.error_recover_until("Expected parameter list", STMT_RECOVERY);
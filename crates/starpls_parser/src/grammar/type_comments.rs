@@ -7,9 +7,25 @@ const TYPE_START: SyntaxKindSet = SyntaxKindSet::new(&[T![ident], ELLIPSIS]);
 
 const PARAMETER_TYPE_START: SyntaxKindSet = TYPE_START.union(SyntaxKindSet::new(&[T![*], T![**]]));
 
-const EMPTY: SyntaxKindSet = SyntaxKindSet::new(&[]);
+const ANNOTATION_START: SyntaxKindSet = SyntaxKindSet::new(&[T![ident], T![None]]);
 
-pub(crate) fn types(p: &mut Parser, stop: Option<SyntaxKind>) {
+/// Tokens at which to stop recovering from errors in inline annotations, so that the rest of the
+/// function definition can still be parsed.
+const ANNOTATION_RECOVERY: SyntaxKindSet =
+    SyntaxKindSet::new(&[T![,], T![')'], T![=], T![:], T!['\n']]);
+
+pub(crate) const EMPTY: SyntaxKindSet = SyntaxKindSet::new(&[]);
+
+/// Parses the type of an inline annotation, e.g. the `int | None` in `def f(x: int | None)`.
+pub(crate) fn annotation_type(p: &mut Parser) {
+    if p.at_kinds(ANNOTATION_START) {
+        union_type(p, ANNOTATION_RECOVERY);
+    } else {
+        p.error("Expected type");
+    }
+}
+
+pub(crate) fn types(p: &mut Parser, stop: Option<SyntaxKind>, recover: SyntaxKindSet) {
     let cond = |p: &mut Parser| match stop {
         Some(stop) => !p.at(EOF) && !p.at(stop),
         None => !p.at(EOF),
@@ -17,43 +33,43 @@ pub(crate) fn types(p: &mut Parser, stop: Option<SyntaxKind>) {
     if !cond(p) {
         return;
     }
-    union_type(p);
+    union_type(p, recover);
     while cond(p) {
         if !p.eat(T![,]) {
-            p.error_recover_until("Expected \",\"", EMPTY);
+            p.error_recover_until("Expected \",\"", recover);
             break;
         }
         if !p.at_kinds(TYPE_START) {
-            p.error_recover_until("Expected type", EMPTY);
+            p.error_recover_until("Expected type", recover);
             break;
         }
-        union_type(p);
+        union_type(p, recover);
     }
 }
 
-pub(crate) fn parameter_types(p: &mut Parser) {
-    parameter_type(p);
+pub(crate) fn parameter_types(p: &mut Parser, recover: SyntaxKindSet) {
+    parameter_type(p, recover);
     while p.at(T![,]) && PARAMETER_TYPE_START.contains(p.nth(1)) {
         p.bump(T![,]);
-        parameter_type(p);
+        parameter_type(p, recover);
     }
 }
 
-pub(crate) fn parameter_type(p: &mut Parser) {
+pub(crate) fn parameter_type(p: &mut Parser, recover: SyntaxKindSet) {
     let m = p.start();
     match p.current() {
         T![ident] => {
-            union_type(p);
+            union_type(p, recover);
             m.complete(p, SIMPLE_PARAMETER_TYPE);
         }
         T![*] => {
             p.bump(T![*]);
-            union_type(p);
+            union_type(p, recover);
             m.complete(p, ARGS_LIST_PARAMETER_TYPE);
         }
         T![**] => {
             p.bump(T![**]);
-            union_type(p);
+            union_type(p, recover);
             m.complete(p, KWARGS_DICT_PARAMETER_TYPE);
         }
         ELLIPSIS => {
@@ -64,8 +80,8 @@ pub(crate) fn parameter_type(p: &mut Parser) {
     }
 }
 
-pub(crate) fn union_type(p: &mut Parser) {
-    let mut m = match type_(p) {
+pub(crate) fn union_type(p: &mut Parser, recover: SyntaxKindSet) {
+    let mut m = match type_(p, recover) {
         Some(m) => m,
         None => return,
     };
@@ -76,12 +92,12 @@ pub(crate) fn union_type(p: &mut Parser) {
     };
     while p.at(T![|]) {
         p.bump(T![|]);
-        type_(p);
+        type_(p, recover);
     }
     union_marker.complete(p, UNION_TYPE);
 }
 
-pub(crate) fn type_(p: &mut Parser) -> Option<CompletedMarker> {
+pub(crate) fn type_(p: &mut Parser, recover: SyntaxKindSet) -> Option<CompletedMarker> {
     Some(match p.current() {
         T![None] => {
             let m = p.start();
@@ -93,7 +109,7 @@ pub(crate) fn type_(p: &mut Parser) -> Option<CompletedMarker> {
             path_segment(p);
             while p.eat(T![.]) {
                 if !p.at(T![ident]) {
-                    p.error_recover_until("Expected type segment", EMPTY);
+                    p.error_recover_until("Expected type segment", recover);
                     return Some(m.complete(p, PATH_TYPE));
                 }
                 path_segment(p);
@@ -101,42 +117,42 @@ pub(crate) fn type_(p: &mut Parser) -> Option<CompletedMarker> {
             if p.at(T!['[']) {
                 let m = p.start();
                 p.bump(T!['[']);
-                types(p, Some(T![']']));
+                types(p, Some(T![']']), recover);
                 p.eat(T![']']);
                 m.complete(p, GENERIC_ARGUMENTS);
             }
             m.complete(p, PATH_TYPE)
         }
-        T!['('] => function_type(p),
+        T!['('] => function_type(p, recover),
         ELLIPSIS => {
             let m = p.start();
             p.bump(ELLIPSIS);
             m.complete(p, ELLIPSIS_TYPE)
         }
         _ => {
-            p.error_recover_until("Expected type", EMPTY);
+            p.error_recover_until("Expected type", recover);
             return None;
         }
     })
 }
 
-pub(crate) fn function_type(p: &mut Parser) -> CompletedMarker {
+pub(crate) fn function_type(p: &mut Parser, recover: SyntaxKindSet) -> CompletedMarker {
     let m = p.start();
     p.bump(T!['(']);
     if p.at_kinds(TYPE_START) {
         let m = p.start();
-        parameter_types(p);
+        parameter_types(p, recover);
         m.complete(p, PARAMETER_TYPES);
     }
     if !p.eat(T![')']) {
-        p.error_recover_until("\"(\" was not closed", EMPTY);
+        p.error_recover_until("\"(\" was not closed", recover);
         return m.complete(p, FUNCTION_TYPE);
     }
     if !p.eat(ARROW) {
-        p.error_recover_until("Expected \"->\"", EMPTY);
+        p.error_recover_until("Expected \"->\"", recover);
         return m.complete(p, FUNCTION_TYPE);
     }
-    type_(p);
+    type_(p, recover);
     m.complete(p, FUNCTION_TYPE)
 }
 
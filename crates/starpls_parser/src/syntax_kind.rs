@@ -145,6 +145,9 @@ pub enum SyntaxKind {
     ARGS_LIST_PARAMETER_TYPE,   // *int
     KWARGS_DICT_PARAMETER_TYPE, // **int
 
+    TYPE_ANNOTATION, // the ": int" in def f(x: int)
+    RETURN_TYPE,     // the "-> int" in def f() -> int
+
     TYPE_COMMENT,
     TYPE_COMMENT_PREFIX,
     TYPE_COMMENT_BODY,
@@ -386,7 +389,7 @@ impl From<SyntaxKind> for u16 {
 }
 
 /// A bitset of `SyntaxKind`s. Only `SyntaxKind`s corresponding to lexer tokens should be added to a `SyntaxKindSet`.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct SyntaxKindSet(u128);
 
 impl SyntaxKindSet {
@@ -279,6 +279,7 @@ ast_node! {
     child parameters -> Parameters;
     child suite -> Suite;
     child name -> Name;
+    child return_type -> ReturnType;
 }
 
 impl DefStmt {
@@ -835,6 +836,14 @@ impl Parameter {
         .and_then(|name| name.name())
         .map(|token| token.text().to_string())
     }
+
+    pub fn type_annotation(&self) -> Option<TypeAnnotation> {
+        match self {
+            Parameter::Simple(param) => param.type_annotation(),
+            Parameter::ArgsList(param) => param.type_annotation(),
+            Parameter::KwargsDict(param) => param.type_annotation(),
+        }
+    }
 }
 
 impl AstNode for Parameter {
@@ -880,16 +889,31 @@ ast_node! {
     SimpleParameter => SIMPLE_PARAMETER
     child default -> Expression;
     child name -> Name;
+    child type_annotation -> TypeAnnotation;
 }
 
 ast_node! {
     ArgsListParameter => ARGS_LIST_PARAMETER
     child name -> Name;
+    child type_annotation -> TypeAnnotation;
 }
 
 ast_node! {
     KwargsDictParameter => KWARGS_DICT_PARAMETER
     child name -> Name;
+    child type_annotation -> TypeAnnotation;
+}
+
+ast_node! {
+    /// An inline parameter type annotation, e.g. the `: int` in `def f(x: int)`.
+    TypeAnnotation => TYPE_ANNOTATION
+    child type_ -> Type;
+}
+
+ast_node! {
+    /// An inline return type annotation, e.g. the `-> int` in `def f() -> int`.
+    ReturnType => RETURN_TYPE
+    child type_ -> Type;
 }
 
 ast_node! {